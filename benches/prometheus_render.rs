@@ -0,0 +1,98 @@
+//! Benchmark comparing the two Prometheus text renderers:
+//!
+//! 1. `PrometheusObserver`, which builds a `prometheus::Registry` per scrape
+//! 2. `ExpositionObserver`, which encodes the entries directly
+//!
+//! Run with:
+//! ```bash
+//! cargo bench --bench prometheus_render --features prometheus
+//! ```
+
+use contatori::counters::monotone::Monotone;
+use contatori::counters::unsigned::Unsigned;
+use contatori::counters::Observable;
+use contatori::labeled_group;
+use contatori::observers::exposition::ExpositionObserver;
+use contatori::observers::prometheus::PrometheusObserver;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+labeled_group!(
+    HttpRequests,
+    "http_requests_total",
+    "method",
+    value: Monotone,
+    get: "GET": Monotone,
+    post: "POST": Monotone,
+    put: "PUT": Monotone,
+    delete: "DELETE": Monotone,
+);
+
+/// Builds `n` plain gauges plus one labeled group.
+fn counters(n: usize) -> (Vec<Unsigned>, HttpRequests) {
+    let gauges: Vec<Unsigned> = (0..n)
+        .map(|i| {
+            let gauge = Unsigned::new().with_owned_name(format!("gauge_{}", i));
+            gauge.add(i);
+            gauge
+        })
+        .collect();
+    let requests = HttpRequests::new();
+    requests.get.add(10);
+    requests.post.add(5);
+    (gauges, requests)
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prometheus_render");
+
+    for n in [10, 100, 1000] {
+        let (gauges, requests) = counters(n);
+        let observables: Vec<&dyn Observable> = gauges
+            .iter()
+            .map(|g| g as &dyn Observable)
+            .chain(std::iter::once(&requests as &dyn Observable))
+            .collect();
+
+        let prometheus = PrometheusObserver::new()
+            .with_namespace("bench")
+            .with_const_label("instance", "localhost");
+        group.bench_with_input(
+            BenchmarkId::new("PrometheusObserver", n),
+            &observables,
+            |b, observables| {
+                b.iter(|| black_box(prometheus.render(observables.iter().copied()).unwrap()))
+            },
+        );
+
+        let exposition = ExpositionObserver::new()
+            .with_namespace("bench")
+            .with_const_label("instance", "localhost");
+        group.bench_with_input(
+            BenchmarkId::new("ExpositionObserver", n),
+            &observables,
+            |b, observables| {
+                b.iter(|| black_box(exposition.render(observables.iter().copied()).unwrap()))
+            },
+        );
+
+        let mut buffer = String::with_capacity(64 * 1024);
+        group.bench_with_input(
+            BenchmarkId::new("ExpositionObserver (reused buffer)", n),
+            &observables,
+            |b, observables| {
+                b.iter(|| {
+                    buffer.clear();
+                    exposition
+                        .encode(observables.iter().copied(), &mut buffer)
+                        .unwrap();
+                    black_box(buffer.len())
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
@@ -0,0 +1,213 @@
+//! Derive macros for the [`contatori`](https://docs.rs/contatori) crate.
+//!
+//! This crate is not meant to be used directly: enable the `derive` feature
+//! of `contatori` and use `contatori::metrics::Metrics` instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `contatori::metrics::Metrics` for a struct of counters.
+///
+/// See the documentation of `contatori::metrics` for the supported attributes.
+#[proc_macro_derive(Metrics, attributes(metrics, metric))]
+pub fn derive_metrics(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Struct-level options: `#[metrics(prefix = "...")]`.
+#[derive(Default)]
+struct StructOptions {
+    prefix: String,
+}
+
+impl StructOptions {
+    fn parse(input: &DeriveInput) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("metrics")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("prefix") {
+                    options.prefix = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported `metrics` attribute, expected `prefix`"))
+                }
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+/// Field-level options: `#[metric(name = "...", help = "...", unit = "...",
+/// stability = "...", prefix = "...", skip, nested)]`.
+#[derive(Default)]
+struct FieldOptions {
+    name: Option<LitStr>,
+    help: Option<LitStr>,
+    unit: Option<LitStr>,
+    stability: Option<LitStr>,
+    prefix: Option<LitStr>,
+    skip: bool,
+    nested: bool,
+}
+
+impl FieldOptions {
+    fn parse(field: &syn::Field) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("metric")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("help") {
+                    options.help = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("unit") {
+                    options.unit = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("stability") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    if !matches!(
+                        lit.value().as_str(),
+                        "stable" | "experimental" | "deprecated"
+                    ) {
+                        return Err(syn::Error::new_spanned(
+                            lit,
+                            "expected \"stable\", \"experimental\" or \"deprecated\"",
+                        ));
+                    }
+                    options.stability = Some(lit);
+                } else if meta.path.is_ident("prefix") {
+                    options.prefix = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else if meta.path.is_ident("nested") {
+                    options.nested = true;
+                } else {
+                    return Err(meta.error(
+                        "unsupported `metric` attribute, expected one of \
+                         `name`, `help`, `unit`, `stability`, `prefix`, `skip`, `nested`",
+                    ));
+                }
+                Ok(())
+            })?;
+
+            if options.skip && options.nested {
+                return Err(syn::Error::new_spanned(
+                    attr,
+                    "`skip` and `nested` are mutually exclusive",
+                ));
+            }
+            if options.nested {
+                let naming = [
+                    &options.name,
+                    &options.help,
+                    &options.unit,
+                    &options.stability,
+                ];
+                if let Some(lit) = naming.into_iter().flatten().next() {
+                    return Err(syn::Error::new_spanned(
+                        lit,
+                        "`nested` fields take their names and metadata from the nested struct, \
+                         only `prefix` applies",
+                    ));
+                }
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "`Metrics` can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Metrics` can only be derived for structs",
+            ))
+        }
+    };
+
+    let struct_options = StructOptions::parse(input)?;
+
+    let mut pushes = Vec::new();
+    for field in fields {
+        let options = FieldOptions::parse(field)?;
+        let ident = field.ident.as_ref().expect("named field");
+
+        if options.skip {
+            continue;
+        }
+
+        let prefix = format!(
+            "{}{}",
+            struct_options.prefix,
+            options
+                .prefix
+                .as_ref()
+                .map(LitStr::value)
+                .unwrap_or_default()
+        );
+
+        if options.nested {
+            pushes.push(quote! {
+                set.with_prefix(#prefix, |set| {
+                    ::contatori::metrics::Metrics::collect_metrics(&self.#ident, set)
+                });
+            });
+            continue;
+        }
+
+        let name = match &options.name {
+            Some(lit) if lit.value().is_empty() => {
+                return Err(syn::Error::new_spanned(lit, "metric name cannot be empty"))
+            }
+            Some(lit) => lit.value(),
+            None => ident.to_string(),
+        };
+        let full_name = format!("{}{}", prefix, name);
+
+        let help = options.help.iter();
+        let unit = options.unit.iter();
+        let stability = options.stability.iter().map(|lit| {
+            let variant = match lit.value().as_str() {
+                "experimental" => quote!(Experimental),
+                "deprecated" => quote!(Deprecated),
+                _ => quote!(Stable),
+            };
+            quote!(::contatori::counters::Stability::#variant)
+        });
+        pushes.push(quote! {
+            set.push(
+                ::contatori::metrics::Metric::new(&self.#ident, #full_name)
+                    #(.with_help(#help))*
+                    #(.with_unit(::contatori::counters::Unit::from_name(#unit)))*
+                    #(.with_stability(#stability))*
+            );
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::contatori::metrics::Metrics for #ident #ty_generics #where_clause {
+            fn collect_metrics<'__contatori>(
+                &'__contatori self,
+                set: &mut ::contatori::metrics::MetricSet<'__contatori>,
+            ) {
+                #(#pushes)*
+            }
+        }
+    })
+}
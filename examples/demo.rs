@@ -172,6 +172,7 @@ fn create_counters() -> (Unsigned, Unsigned, Signed, Minimum, Maximum, Average)
 }
 
 /// Simulates concurrent counter updates.
+#[allow(clippy::too_many_arguments)]
 fn simulate_traffic(
     requests: &Arc<Unsigned>,
     errors: &Arc<Unsigned>,
@@ -197,7 +198,7 @@ fn simulate_traffic(
                 req.add(1);
 
                 // Simulate ~5% error rate
-                if (i * iterations + j) % 20 == 0 {
+                if (i * iterations + j).is_multiple_of(20) {
                     err.add(1);
                 }
 
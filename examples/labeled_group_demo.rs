@@ -256,14 +256,14 @@ fn main() {
     // === expand() Demo ===
     println!("\n--- Using expand() ---\n");
     for entry in HTTP_REQUESTS.expand() {
-        match entry.label {
-            Some((key, value)) => {
+        match entry.labels.as_slice() {
+            [(key, value)] => {
                 println!(
                     "  {}{{{}=\"{}\"}} = {}",
                     entry.name, key, value, entry.value
                 );
             }
-            None => {
+            _ => {
                 println!("  {} = {} (base value)", entry.name, entry.value);
             }
         }
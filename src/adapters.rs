@@ -8,6 +8,7 @@
 //! | Wrapper | Description |
 //! |---------|-------------|
 //! | [`Resettable`] | Resets counter when `value()` is called - for periodic metrics |
+//! | [`Family`] | Lazily creates one counter per runtime label value |
 //!
 //! # Macros
 //!
@@ -56,11 +57,32 @@
 //!
 //! // expand() returns all sub-counters with their label
 //! for entry in HTTP.expand() {
-//!     println!("{}: {:?}", entry.name, entry.label);
+//!     println!("{}: {:?}", entry.name, entry.labels);
 //! }
 //! ```
+//!
+//! ## Family
+//!
+//! ```rust
+//! use contatori::adapters::Family;
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::counters::Observable;
+//!
+//! static REQUESTS: Family<Unsigned> = Family::bounded("requests", "tenant", 1000);
+//!
+//! // Label values only known at runtime
+//! let tenant = String::from("acme");
+//! REQUESTS.get_or_create(&tenant).add(1);
+//!
+//! // expand() returns one entry per tenant
+//! assert_eq!(REQUESTS.expand().len(), 1);
+//! ```
 
+mod family;
 mod group;
 mod resettable;
 
+pub use family::{Family, OVERFLOW_LABEL};
+#[doc(hidden)]
+pub use group::__private;
 pub use resettable::Resettable;
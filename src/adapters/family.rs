@@ -0,0 +1,495 @@
+//! Runtime-labeled counter families.
+//!
+//! This module provides [`Family`], a concurrent map from label values known
+//! only at runtime (tenant ids, endpoints, status codes, ...) to lazily
+//! created sharded counters.
+//!
+//! Unlike [`labeled_group!`](crate::labeled_group), which requires every label
+//! value to be a `&'static str` literal, a `Family` creates a new child counter
+//! the first time a label value is seen.
+//!
+//! # Example
+//!
+//! ```rust
+//! use contatori::adapters::Family;
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::counters::Observable;
+//!
+//! static REQUESTS: Family<Unsigned> = Family::new("requests", "tenant");
+//!
+//! REQUESTS.get_or_create("acme").add(1);
+//! REQUESTS.get_or_create("globex").add(2);
+//!
+//! // One entry per child, labeled with the runtime value
+//! let entries = REQUESTS.expand();
+//! assert_eq!(entries.len(), 2);
+//! assert_eq!(entries[0].labels[0].1, "acme");
+//! ```
+
+use crate::counters::{Aggregate, CounterValue, MetricKind, Observable, ObservableEntry};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, RwLock};
+
+/// Label value used for the overflow bucket once the cardinality limit is reached.
+pub const OVERFLOW_LABEL: &str = "other";
+
+/// A family of counters keyed by a label value known only at runtime.
+///
+/// Children are created lazily on first access with [`C::default()`](Default)
+/// and are never removed. Each child is a full sharded counter, so updates to
+/// a child are as cheap as updates to a standalone counter.
+///
+/// # Hot Path
+///
+/// [`get_or_create()`](Self::get_or_create) takes a read lock and performs a
+/// map lookup. On hot paths, look the child up once and keep the returned
+/// [`Arc`] around instead of resolving the label on every update.
+///
+/// # Cardinality Guard
+///
+/// Unbounded label values (user ids, raw URLs, ...) can blow up memory and
+/// the monitoring backend. Use [`bounded()`](Self::bounded)
+/// to cap the number of children: once the limit is reached, new label values
+/// are folded into a single child labeled [`OVERFLOW_LABEL`] (`"other"`).
+///
+/// In bounded families, real label values that read as the overflow label
+/// followed by any number of underscores (`"other"`, `"other_"`, ...) get
+/// one more underscore, so they count towards the limit and never share the
+/// overflow child: `"other"` is exported as `"other_"`.
+///
+/// # Example
+///
+/// ```rust
+/// use contatori::adapters::Family;
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::counters::Observable;
+///
+/// let by_status: Family<Monotone> = Family::bounded("http_responses", "status", 2);
+///
+/// by_status.get_or_create("200").add(10);
+/// by_status.get_or_create("404").add(2);
+/// by_status.get_or_create("500").add(1); // over the limit: goes to "other"
+///
+/// assert_eq!(by_status.len(), 3);
+/// assert_eq!(by_status.overflow().unwrap().value().as_u64(), 1);
+/// assert!(by_status.get("500").is_none());
+/// ```
+pub struct Family<C> {
+    name: Cow<'static, str>,
+    label_key: &'static str,
+    max_cardinality: Option<usize>,
+    children: RwLock<BTreeMap<String, Arc<C>>>,
+}
+
+impl<C> Family<C> {
+    /// Creates a new, empty family named `name` using `label_key` as the label name.
+    ///
+    /// The family has no cardinality limit.
+    pub const fn new(name: &'static str, label_key: &'static str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            label_key,
+            max_cardinality: None,
+            children: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Creates a new, empty family limited to `max_cardinality` distinct label values.
+    ///
+    /// Once `max_cardinality` children exist, any new label value is redirected
+    /// to the [`OVERFLOW_LABEL`] child, which does not count towards the limit.
+    pub const fn bounded(
+        name: &'static str,
+        label_key: &'static str,
+        max_cardinality: usize,
+    ) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            label_key,
+            max_cardinality: Some(max_cardinality),
+            children: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Returns the label key shared by all children.
+    #[inline]
+    pub const fn label_key(&self) -> &'static str {
+        self.label_key
+    }
+
+    /// Returns the configured cardinality limit, if any.
+    #[inline]
+    pub const fn max_cardinality(&self) -> Option<usize> {
+        self.max_cardinality
+    }
+
+    /// Returns the child for `label_value`, if it has already been created.
+    ///
+    /// Unlike [`get_or_create()`](Self::get_or_create), this never creates a
+    /// child and never redirects to the overflow bucket.
+    pub fn get(&self, label_value: &str) -> Option<Arc<C>> {
+        self.read().get(self.key(label_value).as_ref()).cloned()
+    }
+
+    /// Returns the overflow child, if a label value has been redirected to
+    /// it.
+    pub fn overflow(&self) -> Option<Arc<C>> {
+        self.max_cardinality?;
+        self.read().get(OVERFLOW_LABEL).cloned()
+    }
+
+    /// Returns the number of children, including the overflow bucket.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Returns `true` if no child has been created yet.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns the key of the child for `label_value`, escaped in bounded
+    /// families so that no label value names the overflow child.
+    fn key<'a>(&self, label_value: &'a str) -> Cow<'a, str> {
+        let reserved = self.max_cardinality.is_some()
+            && label_value
+                .strip_prefix(OVERFLOW_LABEL)
+                .is_some_and(|rest| rest.bytes().all(|b| b == b'_'));
+        if reserved {
+            Cow::Owned(format!("{}_", label_value))
+        } else {
+            Cow::Borrowed(label_value)
+        }
+    }
+
+    /// Acquires the read lock, recovering from poisoning.
+    ///
+    /// Children are only ever inserted, so a panic while holding the lock
+    /// cannot leave the map in an inconsistent state.
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Arc<C>>> {
+        self.children.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<C: Default> Family<C> {
+    /// Returns the child for `label_value`, creating it on first use.
+    ///
+    /// If a cardinality limit is set and already reached, the overflow child
+    /// labeled [`OVERFLOW_LABEL`] is returned instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use contatori::adapters::Family;
+    /// use contatori::counters::unsigned::Unsigned;
+    /// use contatori::counters::Observable;
+    ///
+    /// let family: Family<Unsigned> = Family::new("requests", "endpoint");
+    ///
+    /// // Keep the handle around on hot paths
+    /// let users = family.get_or_create("/users");
+    /// users.add(1);
+    /// users.add(1);
+    ///
+    /// assert_eq!(family.get_or_create("/users").value().as_u64(), 2);
+    /// ```
+    pub fn get_or_create(&self, label_value: &str) -> Arc<C> {
+        let key = self.key(label_value);
+        if let Some(child) = self.read().get(key.as_ref()) {
+            return Arc::clone(child);
+        }
+
+        let mut children = self.children.write().unwrap_or_else(|e| e.into_inner());
+        // Another thread may have created the child while we waited for the lock
+        if let Some(child) = children.get(key.as_ref()) {
+            return Arc::clone(child);
+        }
+
+        let key = match self.max_cardinality {
+            Some(max) if Self::cardinality(&children) >= max => OVERFLOW_LABEL,
+            _ => key.as_ref(),
+        };
+        Arc::clone(children.entry(key.to_string()).or_default())
+    }
+
+    /// Counts the children that are subject to the cardinality limit.
+    fn cardinality(children: &BTreeMap<String, Arc<C>>) -> usize {
+        children.len() - usize::from(children.contains_key(OVERFLOW_LABEL))
+    }
+}
+
+impl<C: Aggregate> Observable for Family<C> {
+    /// Returns the name shared by all children.
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the [`Aggregate`] of all children: the sum of counters, the
+    /// largest of maxima, the overall average, ...
+    fn value(&self) -> CounterValue {
+        C::aggregate(self.read().values().map(|child| &**child))
+    }
+
+    /// Returns the metric kind of the children, or [`MetricKind::Gauge`] if empty.
+    fn metric_kind(&self) -> MetricKind {
+        self.read()
+            .values()
+            .next()
+            .map(|child| child.metric_kind())
+            .unwrap_or_default()
+    }
+
+    /// Returns whether the children are additive, or `false` if empty.
+    fn is_additive(&self) -> bool {
+        self.read()
+            .values()
+            .next()
+            .is_some_and(|child| child.is_additive())
+    }
+
+    /// Expands the family into one entry per child, ordered by label value.
+    fn expand(&self) -> Vec<ObservableEntry> {
+        self.read()
+            .iter()
+            .map(|(label_value, child)| ObservableEntry {
+                name: self.name.clone(),
+                labels: vec![(self.label_key, Cow::Owned(label_value.clone()))],
+                value: child.value(),
+                metric_kind: child.metric_kind(),
+                distribution: child.distribution(),
+            })
+            .collect()
+    }
+}
+
+impl<C: Debug> Debug for Family<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Family")
+            .field("name", &self.name)
+            .field("label_key", &self.label_key)
+            .field("children", &*self.read())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::average::Average;
+    use crate::counters::maximum::Maximum;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+
+    #[test]
+    fn test_new() {
+        let family: Family<Unsigned> = Family::new("requests", "tenant");
+        assert_eq!(family.name(), "requests");
+        assert_eq!(family.label_key(), "tenant");
+        assert_eq!(family.max_cardinality(), None);
+        assert!(family.is_empty());
+    }
+
+    #[test]
+    fn test_get_or_create_returns_same_child() {
+        let family: Family<Unsigned> = Family::new("requests", "tenant");
+        family.get_or_create("acme").add(1);
+        family.get_or_create("acme").add(2);
+
+        assert_eq!(family.len(), 1);
+        assert_eq!(family.get("acme").unwrap().value().as_u64(), 3);
+        assert!(family.get("globex").is_none());
+    }
+
+    #[test]
+    fn test_expand() {
+        let family: Family<Unsigned> = Family::new("requests", "endpoint");
+        family.get_or_create("/users").add(10);
+        family.get_or_create("/orders").add(5);
+
+        let entries = family.expand();
+        assert_eq!(entries.len(), 2);
+
+        // Children are ordered by label value
+        assert_eq!(entries[0].name, "requests");
+        let (key, value) = &entries[0].labels[0];
+        assert_eq!(*key, "endpoint");
+        assert_eq!(*value, "/orders");
+        assert_eq!(entries[0].value.as_u64(), 5);
+
+        let (_, value) = &entries[1].labels[0];
+        assert_eq!(*value, "/users");
+        assert_eq!(entries[1].value.as_u64(), 10);
+    }
+
+    #[test]
+    fn test_with_owned_name() {
+        let family: Family<Unsigned> =
+            Family::new("", "tenant").with_owned_name(String::from("configured"));
+        family.get_or_create("acme").add(1);
+
+        assert_eq!(family.name(), "configured");
+        assert_eq!(family.expand()[0].name, "configured");
+    }
+
+    #[test]
+    fn test_expand_empty() {
+        let family: Family<Unsigned> = Family::new("requests", "tenant");
+        assert!(family.expand().is_empty());
+    }
+
+    #[test]
+    fn test_max_cardinality_overflow() {
+        let family: Family<Unsigned> = Family::bounded("users", "user", 2);
+        family.get_or_create("a").add(1);
+        family.get_or_create("b").add(1);
+        family.get_or_create("c").add(1);
+        family.get_or_create("d").add(1);
+
+        // Existing children are still reachable
+        family.get_or_create("a").add(1);
+
+        assert_eq!(family.len(), 3);
+        assert_eq!(family.get("a").unwrap().value().as_u64(), 2);
+        assert_eq!(family.overflow().unwrap().value().as_u64(), 2);
+        assert!(family.get("c").is_none());
+    }
+
+    #[test]
+    fn test_overflow_label_collision() {
+        let family: Family<Unsigned> = Family::bounded("users", "user", 2);
+        family.get_or_create("other").add(1);
+        family.get_or_create("other_").add(2);
+        // Both real values took a slot, so this one overflows
+        family.get_or_create("a").add(4);
+
+        assert_eq!(family.get("other").unwrap().value().as_u64(), 1);
+        assert_eq!(family.get("other_").unwrap().value().as_u64(), 2);
+        assert_eq!(family.overflow().unwrap().value().as_u64(), 4);
+
+        let labels: Vec<_> = family
+            .expand()
+            .into_iter()
+            .map(|entry| (entry.labels[0].1.to_string(), entry.value.as_u64()))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("other".to_string(), 4),
+                ("other_".to_string(), 1),
+                ("other__".to_string(), 2)
+            ]
+        );
+
+        // Unbounded families have no overflow child to protect
+        let family: Family<Unsigned> = Family::new("users", "user");
+        family.get_or_create("other").add(1);
+        assert_eq!(family.expand()[0].labels[0].1, "other");
+        assert!(family.overflow().is_none());
+    }
+
+    #[test]
+    fn test_max_cardinality_zero() {
+        let family: Family<Unsigned> = Family::bounded("users", "user", 0);
+        family.get_or_create("a").add(1);
+
+        let entries = family.expand();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].labels[0].1, OVERFLOW_LABEL);
+    }
+
+    #[test]
+    fn test_value_sums_children() {
+        let family: Family<Unsigned> = Family::new("requests", "tenant");
+        family.get_or_create("a").add(3);
+        family.get_or_create("b").add(4);
+        assert_eq!(family.value(), CounterValue::Unsigned(7));
+
+        let signed: Family<Signed> = Family::new("requests", "tenant");
+        signed.get_or_create("a").add(3);
+        signed.get_or_create("b").sub(5);
+        assert_eq!(signed.value(), CounterValue::Signed(-2));
+    }
+
+    #[test]
+    fn test_metric_kind_from_children() {
+        let family: Family<Monotone> = Family::new("requests", "tenant");
+        assert_eq!(family.metric_kind(), MetricKind::Gauge);
+
+        family.get_or_create("a").add(1);
+        assert_eq!(family.metric_kind(), MetricKind::Counter);
+        assert_eq!(family.expand()[0].metric_kind, MetricKind::Counter);
+    }
+
+    #[test]
+    fn test_other_counter_types() {
+        let family: Family<Maximum> = Family::new("peak", "shard");
+        family.get_or_create("0").observe(10);
+        family.get_or_create("0").observe(30);
+        family.get_or_create("1").observe(20);
+
+        let entries = family.expand();
+        assert_eq!(entries[0].value.as_u64(), 30);
+        assert_eq!(entries[1].value.as_u64(), 20);
+        // The family reports the largest maximum, not the sum
+        assert_eq!(family.value().as_u64(), 30);
+
+        let family: Family<Average> = Family::new("latency", "shard");
+        family.get_or_create("0").observe(10);
+        family.get_or_create("1").observe(20);
+        family.get_or_create("1").observe(30);
+        assert_eq!(family.value(), CounterValue::Unsigned(20));
+    }
+
+    #[test]
+    fn test_static_usage() {
+        static TENANTS: Family<Unsigned> = Family::new("static_requests", "tenant");
+
+        TENANTS.get_or_create("acme").add(1);
+        assert_eq!(TENANTS.get("acme").unwrap().value().as_u64(), 1);
+    }
+
+    #[test]
+    fn test_multiple_threads() {
+        use std::thread;
+
+        let family: Arc<Family<Unsigned>> = Arc::new(Family::new("requests", "tenant"));
+        let mut handles = vec![];
+
+        for t in 0..4 {
+            let family = Arc::clone(&family);
+            handles.push(thread::spawn(move || {
+                for i in 0..100 {
+                    family.get_or_create(&format!("tenant-{}", i % 8)).add(1);
+                }
+                family.get_or_create(&format!("own-{}", t)).add(1);
+            }));
+        }
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(family.len(), 12);
+        assert_eq!(family.value().as_u64(), 404);
+    }
+
+    #[test]
+    fn test_debug() {
+        let family: Family<Unsigned> = Family::new("requests", "tenant");
+        family.get_or_create("acme").add(1);
+
+        let debug_str = format!("{:?}", family);
+        assert!(debug_str.contains("Family"));
+        assert!(debug_str.contains("acme"));
+    }
+}
@@ -27,7 +27,7 @@
 //!
 //! // Export via expand()
 //! for entry in HTTP.expand() {
-//!     println!("{}: {:?} = {}", entry.name, entry.label, entry.value);
+//!     println!("{}: {:?} = {}", entry.name, entry.labels, entry.value);
 //! }
 //! ```
 
@@ -59,6 +59,9 @@
 /// - **Labeled fields**: Optional breakdowns that provide dimensional views of the metric.
 ///   These are independent counters, NOT automatically aggregated.
 ///
+/// See [Total Mode](#total-mode) for groups whose unlabeled entry is computed
+/// from the labeled fields instead.
+///
 /// # Example
 ///
 /// ```rust
@@ -101,8 +104,337 @@
 /// http_requests{method="PUT"} 15
 /// http_requests{method="DELETE"} 5
 /// ```
+///
+/// # Multiple Label Keys
+///
+/// Passing a tuple of label keys declares a group over several dimensions.
+/// Each labeled field then maps to a tuple of label values, one per key:
+///
+/// ```rust
+/// use contatori::labeled_group;
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::counters::Observable;
+///
+/// labeled_group!(
+///     HttpResponses,
+///     "http_responses",
+///     ("method", "status"),
+///     value: Monotone,
+///     get_200: ("GET", "200"): Monotone,
+///     get_404: ("GET", "404"): Monotone,
+///     post_500: ("POST", "500"): Monotone,
+/// );
+///
+/// static HTTP: HttpResponses = HttpResponses::new();
+///
+/// HTTP.value.add(1);
+/// HTTP.get_200.add(1);
+///
+/// // http_responses{method="GET",status="200"} = get_200
+/// let entries = HTTP.expand();
+/// assert_eq!(entries.len(), 4);
+/// assert_eq!(entries[1].labels.len(), 2);
+/// ```
+///
+/// # Total Mode
+///
+/// Replacing the `value` field with `total: Type` and omitting the per-field
+/// types makes every labeled field a `Type` and computes the unlabeled entry
+/// at read time as the [`Aggregate`] of the labeled fields, so each event only
+/// touches one counter:
+///
+/// | Type | Total |
+/// |------|-------|
+/// | `Monotone`, `Unsigned`, `Signed` | sum |
+/// | `Maximum` | maximum |
+/// | `Minimum` | minimum |
+/// | `Average` | mean weighted by the number of observations |
+///
+/// ```rust
+/// use contatori::labeled_group;
+/// use contatori::counters::average::Average;
+/// use contatori::counters::Observable;
+///
+/// labeled_group!(
+///     Latency,
+///     "latency_ms",
+///     "method",
+///     total: Average,
+///     get: "GET",
+///     post: "POST",
+/// );
+///
+/// static LATENCY: Latency = Latency::new();
+///
+/// LATENCY.get.observe(10);
+/// LATENCY.get.observe(20);
+/// LATENCY.post.observe(60);
+///
+/// // (10 + 20 + 60) / 3, not the mean of the per-method means
+/// assert_eq!(LATENCY.value().as_u64(), 30);
+/// ```
+///
+/// Multi-key groups additionally emit one subtotal per distinct label value
+/// of each dimension, aggregating every field that shares that value. Each
+/// dimension gets its own metric, `<name>_by_<key>`, so that summing the
+/// labeled entries of the group does not count an event once per dimension:
+///
+/// ```rust
+/// use contatori::labeled_group;
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::counters::Observable;
+///
+/// labeled_group!(
+///     HttpResponses,
+///     "http_responses",
+///     ("method", "status"),
+///     total: Monotone,
+///     get_200: ("GET", "200"),
+///     get_404: ("GET", "404"),
+///     post_200: ("POST", "200"),
+/// );
+///
+/// static HTTP: HttpResponses = HttpResponses::new();
+///
+/// HTTP.get_200.add(3);
+/// HTTP.get_404.add(1);
+/// HTTP.post_200.add(2);
+///
+/// // http_responses                             6
+/// // http_responses_by_method{method="GET"}     4
+/// // http_responses_by_method{method="POST"}    2
+/// // http_responses_by_status{status="200"}     5
+/// // http_responses_by_status{status="404"}     1
+/// // http_responses{method="GET",status="200"}  3
+/// // ...
+/// assert_eq!(HTTP.value().as_u64(), 6);
+/// assert_eq!(HTTP.expand().len(), 8);
+/// ```
+///
+/// [`Aggregate`]: crate::counters::Aggregate
 #[macro_export]
 macro_rules! labeled_group {
+    // Multiple label keys, total mode: `total: Type,` followed by
+    // `field: ("v1", "v2"),` entries without types.
+    (
+        $name:ident,
+        $metric:literal,
+        ($($key:literal),+ $(,)?),
+        total : $ty:ty,
+        $($field:ident : ($($lval:literal),+ $(,)?)),+ $(,)?
+    ) => {
+        /// A labeled group of counters with computed totals.
+        ///
+        /// Generated by the `labeled_group!` macro.
+        pub struct $name {
+            $(pub $field: $ty,)+
+        }
+
+        impl $name {
+            const LABEL_KEYS: &'static [&'static str] = &[$($key),+];
+
+            /// Creates a new instance with all counters initialized.
+            pub const fn new() -> Self {
+                Self {
+                    $($field: <$ty>::new(),)+
+                }
+            }
+
+            /// Returns the metric name for this group.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn metric_name(&self) -> &'static str {
+                $metric
+            }
+
+            /// Returns the label keys for this group.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn label_keys(&self) -> &'static [&'static str] {
+                Self::LABEL_KEYS
+            }
+        }
+
+        $(
+            const _: () = assert!(
+                [$($lval),+].len() == $name::LABEL_KEYS.len(),
+                concat!("labeled_group!: `", stringify!($field), "` must have one label value per key"),
+            );
+        )+
+
+        impl $crate::counters::Observable for $name {
+            fn name(&self) -> &str {
+                $metric
+            }
+
+            fn value(&self) -> $crate::counters::CounterValue {
+                <$ty as $crate::counters::Aggregate>::aggregate([$(&self.$field),+])
+            }
+
+            fn metric_kind(&self) -> $crate::counters::MetricKind {
+                [$(&self.$field),+][0].metric_kind()
+            }
+
+            fn is_additive(&self) -> bool {
+                [$(&self.$field),+][0].is_additive()
+            }
+
+            fn expand(&self) -> Vec<$crate::counters::ObservableEntry> {
+                $crate::adapters::__private::expand_with_totals(
+                    $metric,
+                    Self::LABEL_KEYS,
+                    &[$((&[$($lval),+], &self.$field)),+],
+                )
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    $(.field(stringify!($field), &self.$field))+
+                    .finish()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+
+    // Multiple label keys: `field: ("v1", "v2"): Type,`
+    (
+        $name:ident,
+        $metric:literal,
+        ($($key:literal),+ $(,)?),
+        $main_field:ident : $value_ty:ty,
+        $($field:ident : ($($lval:literal),+ $(,)?) : $lty:ty),* $(,)?
+    ) => {
+        /// A labeled group of counters.
+        ///
+        /// Generated by the `labeled_group!` macro.
+        pub struct $name {
+            pub $main_field: $value_ty,
+            $(pub $field: $lty,)*
+        }
+
+        impl $name {
+            const LABEL_KEYS: &'static [&'static str] = &[$($key),+];
+
+            /// Creates a new instance with all counters initialized.
+            pub const fn new() -> Self {
+                Self {
+                    $main_field: <$value_ty>::new(),
+                    $($field: <$lty>::new(),)*
+                }
+            }
+
+            /// Returns the metric name for this group.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn metric_name(&self) -> &'static str {
+                $metric
+            }
+
+            /// Returns the label keys for this group.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn label_keys(&self) -> &'static [&'static str] {
+                Self::LABEL_KEYS
+            }
+        }
+
+        $(
+            const _: () = assert!(
+                [$($lval),+].len() == $name::LABEL_KEYS.len(),
+                concat!("labeled_group!: `", stringify!($field), "` must have one label value per key"),
+            );
+        )*
+
+        impl $crate::counters::Observable for $name {
+            fn name(&self) -> &str {
+                $metric
+            }
+
+            fn value(&self) -> $crate::counters::CounterValue {
+                self.$main_field.value()
+            }
+
+            fn metric_kind(&self) -> $crate::counters::MetricKind {
+                self.$main_field.metric_kind()
+            }
+
+            fn is_additive(&self) -> bool {
+                self.$main_field.is_additive()
+            }
+
+            fn expand(&self) -> Vec<$crate::counters::ObservableEntry> {
+                let mut entries = vec![$crate::counters::ObservableEntry {
+                    name: ::std::borrow::Cow::Borrowed($metric),
+                    labels: Vec::new(),
+                    value: self.$main_field.value(),
+                    metric_kind: self.$main_field.metric_kind(),
+                    distribution: $crate::counters::Observable::distribution(&self.$main_field),
+                }];
+
+                $(
+                    entries.push($crate::counters::ObservableEntry {
+                        name: ::std::borrow::Cow::Borrowed($metric),
+                        labels: $crate::adapters::__private::labels(Self::LABEL_KEYS, &[$($lval),+]),
+                        value: self.$field.value(),
+                        metric_kind: self.$field.metric_kind(),
+                        distribution: $crate::counters::Observable::distribution(&self.$field),
+                    });
+                )*
+
+                entries
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field(stringify!($main_field), &self.$main_field)
+                    $(.field(stringify!($field), &self.$field))*
+                    .finish()
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+    };
+
+    // Single label key, total mode: `total: Type,` followed by
+    // `field: "label",` entries without types.
+    (
+        $name:ident,
+        $metric:literal,
+        $label_key:literal,
+        total : $ty:ty,
+        $($field:ident : $lval:literal),+ $(,)?
+    ) => {
+        $crate::labeled_group!(
+            $name,
+            $metric,
+            ($label_key),
+            total: $ty,
+            $($field: ($lval)),+
+        );
+
+        impl $name {
+            /// Returns the label key for this group.
+            #[inline]
+            #[allow(dead_code)]
+            pub const fn label_key(&self) -> &'static str {
+                $label_key
+            }
+        }
+    };
+
     (
         $name:ident,
         $metric:literal,
@@ -185,7 +517,7 @@ macro_rules! labeled_group {
         }
 
         impl $crate::counters::Observable for $name {
-            fn name(&self) -> &'static str {
+            fn name(&self) -> &str {
                 $metric
             }
 
@@ -197,25 +529,31 @@ macro_rules! labeled_group {
                 $crate::counters::MetricKind::Gauge
             }
 
+            fn is_additive(&self) -> bool {
+                self.$main_field.is_additive()
+            }
+
             fn expand(&self) -> Vec<$crate::counters::ObservableEntry> {
                 const LABELS:  &[&'static str] = &[ $( $lval ),* ];
                 let mut entries = Vec::with_capacity(1 + LABELS.len());
 
                 // Add the mandatory unlabeled counter using the custom field name
                 entries.push($crate::counters::ObservableEntry {
-                    name: $metric,
-                    label: None,
+                    name: ::std::borrow::Cow::Borrowed($metric),
+                    labels: Vec::new(),
                     value: self.$main_field.value(),
                     metric_kind: self.$main_field.metric_kind(),
+                    distribution: $crate::counters::Observable::distribution(&self.$main_field),
                 });
 
                 // Add labeled counters
                 $(
                     entries.push($crate::counters::ObservableEntry {
-                        name: $metric,
-                        label: Some(($label_key, $lval)),
+                        name: ::std::borrow::Cow::Borrowed($metric),
+                        labels: vec![($label_key, ::std::borrow::Cow::Borrowed($lval))],
                         value: self.$lfield.value(),
                         metric_kind: self.$lfield.metric_kind(),
+                        distribution: $crate::counters::Observable::distribution(&self.$lfield),
                     });
                 )*
 
@@ -240,6 +578,87 @@ macro_rules! labeled_group {
     };
 }
 
+/// Support functions for the code generated by [`labeled_group!`].
+///
+/// Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use std::borrow::Cow;
+
+    use crate::counters::{Aggregate, ObservableEntry};
+
+    /// Pairs each label key with the corresponding value.
+    pub fn labels(
+        keys: &[&'static str],
+        values: &[&'static str],
+    ) -> Vec<(&'static str, Cow<'static, str>)> {
+        keys.iter()
+            .zip(values)
+            .map(|(key, value)| (*key, Cow::Borrowed(*value)))
+            .collect()
+    }
+
+    /// Expands a total-mode group.
+    ///
+    /// Emits the unlabeled aggregate first, then (for groups with two or more
+    /// keys) one `<name>_by_<key>` entry per distinct value of each dimension
+    /// in declaration order, and finally one entry per field.
+    pub fn expand_with_totals<A: Aggregate>(
+        name: &'static str,
+        keys: &[&'static str],
+        fields: &[(&[&'static str], &A)],
+    ) -> Vec<ObservableEntry> {
+        let metric_kind = fields
+            .first()
+            .map(|(_, counter)| counter.metric_kind())
+            .unwrap_or_default();
+        let mut entries = vec![ObservableEntry {
+            name: Cow::Borrowed(name),
+            labels: Vec::new(),
+            value: A::aggregate(fields.iter().map(|(_, counter)| *counter)),
+            metric_kind,
+            distribution: None,
+        }];
+
+        if keys.len() > 1 {
+            for (dim, key) in keys.iter().enumerate() {
+                let subtotal_name = format!("{}_by_{}", name, key);
+                let mut seen: Vec<&'static str> = Vec::new();
+                for (values, _) in fields {
+                    let value = values[dim];
+                    if seen.contains(&value) {
+                        continue;
+                    }
+                    seen.push(value);
+                    let partial = fields
+                        .iter()
+                        .filter(|(other, _)| other[dim] == value)
+                        .fold(A::IDENTITY, |acc, (_, counter)| {
+                            A::merge(acc, counter.partial())
+                        });
+                    entries.push(ObservableEntry {
+                        name: Cow::Owned(subtotal_name.clone()),
+                        labels: vec![(*key, Cow::Borrowed(value))],
+                        value: A::finish(partial),
+                        metric_kind,
+                        distribution: None,
+                    });
+                }
+            }
+        }
+
+        entries.extend(fields.iter().map(|(values, counter)| ObservableEntry {
+            name: Cow::Borrowed(name),
+            labels: labels(keys, values),
+            value: A::finish(counter.partial()),
+            metric_kind,
+            distribution: None,
+        }));
+
+        entries
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::counters::unsigned::Unsigned;
@@ -285,23 +704,23 @@ mod tests {
 
         // First entry: value (no label)
         assert_eq!(entries[0].name, "test_requests");
-        assert!(entries[0].label.is_none());
+        assert!(entries[0].labels.is_empty());
         assert_eq!(entries[0].value.as_u64(), 100);
 
         // Second entry: get with label
         assert_eq!(entries[1].name, "test_requests");
-        assert!(entries[1].label.is_some());
-        let (key, val) = entries[1].label.unwrap();
-        assert_eq!(key, "method");
-        assert_eq!(val, "GET");
+        assert_eq!(entries[1].labels.len(), 1);
+        let (key, val) = &entries[1].labels[0];
+        assert_eq!(*key, "method");
+        assert_eq!(*val, "GET");
         assert_eq!(entries[1].value.as_u64(), 60);
 
         // Third entry: post with label
         assert_eq!(entries[2].name, "test_requests");
-        assert!(entries[2].label.is_some());
-        let (key, val) = entries[2].label.unwrap();
-        assert_eq!(key, "method");
-        assert_eq!(val, "POST");
+        assert_eq!(entries[2].labels.len(), 1);
+        let (key, val) = &entries[2].labels[0];
+        assert_eq!(*key, "method");
+        assert_eq!(*val, "POST");
         assert_eq!(entries[2].value.as_u64(), 40);
     }
 
@@ -377,10 +796,270 @@ mod tests {
 
         let entries = counters.expand();
         assert_eq!(entries.len(), 1);
-        assert!(entries[0].label.is_none());
+        assert!(entries[0].labels.is_empty());
         assert_eq!(entries[0].value.as_u64(), 50);
 
         // Observable::value() returns the 'value' field
         assert_eq!(counters.value().as_u64(), 50);
     }
+
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+
+    labeled_group!(
+        TestResponses,
+        "test_responses",
+        ("method", "status"),
+        value: Monotone,
+        get_200: ("GET", "200"): Monotone,
+        post_500: ("POST", "500"): Monotone,
+    );
+
+    #[test]
+    fn test_multi_key_new() {
+        let responses = TestResponses::new();
+        assert_eq!(responses.metric_name(), "test_responses");
+        assert_eq!(responses.label_keys(), &["method", "status"]);
+    }
+
+    #[test]
+    fn test_multi_key_expand() {
+        let responses = TestResponses::new();
+        responses.value.add(10);
+        responses.get_200.add(7);
+        responses.post_500.add(2);
+
+        let entries = responses.expand();
+        assert_eq!(entries.len(), 3);
+
+        assert!(entries[0].labels.is_empty());
+        assert_eq!(entries[0].value.as_u64(), 10);
+
+        assert_eq!(entries[1].labels.len(), 2);
+        assert_eq!(entries[1].labels[0].0, "method");
+        assert_eq!(entries[1].labels[0].1, "GET");
+        assert_eq!(entries[1].labels[1].0, "status");
+        assert_eq!(entries[1].labels[1].1, "200");
+        assert_eq!(entries[1].value.as_u64(), 7);
+
+        assert_eq!(entries[2].labels[0].1, "POST");
+        assert_eq!(entries[2].labels[1].1, "500");
+        assert_eq!(entries[2].value.as_u64(), 2);
+
+        // Value mode keeps the main field independent
+        assert_eq!(responses.value().as_u64(), 10);
+        assert_eq!(
+            responses.metric_kind(),
+            crate::counters::MetricKind::Counter
+        );
+    }
+
+    labeled_group!(
+        TestResponseTotals,
+        "test_response_totals",
+        ("method", "status"),
+        total: Monotone,
+        get_200: ("GET", "200"),
+        get_404: ("GET", "404"),
+        post_200: ("POST", "200"),
+    );
+
+    #[test]
+    fn test_multi_key_total() {
+        static RESPONSES: TestResponseTotals = TestResponseTotals::new();
+        RESPONSES.get_200.add(3);
+        RESPONSES.get_404.add(1);
+        RESPONSES.post_200.add(2);
+
+        assert_eq!(RESPONSES.value().as_u64(), 6);
+        assert_eq!(
+            RESPONSES.metric_kind(),
+            crate::counters::MetricKind::Counter
+        );
+
+        let entries = RESPONSES.expand();
+        let rendered: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let labels: Vec<(&str, &str)> = entry
+                    .labels
+                    .iter()
+                    .map(|(key, value)| (*key, value.as_ref()))
+                    .collect();
+                (entry.name.as_ref(), labels, entry.value.as_u64())
+            })
+            .collect();
+
+        let name = "test_response_totals";
+        let by_method = "test_response_totals_by_method";
+        let by_status = "test_response_totals_by_status";
+        assert_eq!(
+            rendered,
+            vec![
+                (name, vec![], 6),
+                (by_method, vec![("method", "GET")], 4),
+                (by_method, vec![("method", "POST")], 2),
+                (by_status, vec![("status", "200")], 5),
+                (by_status, vec![("status", "404")], 1),
+                (name, vec![("method", "GET"), ("status", "200")], 3),
+                (name, vec![("method", "GET"), ("status", "404")], 1),
+                (name, vec![("method", "POST"), ("status", "200")], 2),
+            ]
+        );
+
+        // Each event is counted once under the group's own name
+        let leaves: u64 = entries
+            .iter()
+            .filter(|entry| entry.name == name && !entry.labels.is_empty())
+            .map(|entry| entry.value.as_u64())
+            .sum();
+        assert_eq!(leaves, RESPONSES.value().as_u64());
+        assert!(entries
+            .iter()
+            .all(|entry| entry.metric_kind == crate::counters::MetricKind::Counter));
+    }
+
+    labeled_group!(
+        TestBalances,
+        "test_balances",
+        ("region", "currency"),
+        total: Signed,
+        eu_eur: ("eu", "EUR"),
+        us_usd: ("us", "USD"),
+    );
+
+    #[test]
+    fn test_multi_key_total_signed() {
+        let balances = TestBalances::new();
+        balances.eu_eur.add(10);
+        balances.us_usd.sub(15);
+
+        assert_eq!(balances.value(), crate::counters::CounterValue::Signed(-5));
+
+        let entries = balances.expand();
+        assert_eq!(entries.len(), 7);
+        assert_eq!(entries[1].labels[0].1, "eu");
+        assert_eq!(entries[1].value, crate::counters::CounterValue::Signed(10));
+        assert_eq!(entries[4].labels[0].1, "USD");
+        assert_eq!(entries[4].value, crate::counters::CounterValue::Signed(-15));
+    }
+
+    use crate::counters::average::Average;
+    use crate::counters::maximum::Maximum;
+    use crate::counters::minimum::Minimum;
+
+    labeled_group!(
+        TestRequestTotal,
+        "test_request_total",
+        "method",
+        total: Monotone,
+        get: "GET",
+        post: "POST",
+    );
+
+    #[test]
+    fn test_total_sum() {
+        static REQUESTS: TestRequestTotal = TestRequestTotal::new();
+        assert_eq!(REQUESTS.label_key(), "method");
+        assert_eq!(REQUESTS.label_keys(), &["method"]);
+
+        REQUESTS.get.add(60);
+        REQUESTS.post.add(40);
+
+        // Only the labeled fields are touched, the total is computed
+        assert_eq!(REQUESTS.value().as_u64(), 100);
+
+        let entries = REQUESTS.expand();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].labels.is_empty());
+        assert_eq!(entries[0].value.as_u64(), 100);
+        assert_eq!(entries[1].labels[0].1, "GET");
+        assert_eq!(entries[1].value.as_u64(), 60);
+        assert_eq!(entries[2].labels[0].1, "POST");
+        assert_eq!(entries[2].value.as_u64(), 40);
+    }
+
+    labeled_group!(
+        TestLatencyMax,
+        "test_latency_max",
+        "method",
+        total: Maximum,
+        get: "GET",
+        post: "POST",
+    );
+
+    #[test]
+    fn test_total_max() {
+        let latency = TestLatencyMax::new();
+        assert_eq!(latency.value().as_u64(), 0);
+
+        latency.get.observe(120);
+        latency.post.observe(80);
+        assert_eq!(latency.value().as_u64(), 120);
+    }
+
+    labeled_group!(
+        TestLatencyMin,
+        "test_latency_min",
+        "method",
+        total: Minimum,
+        get: "GET",
+        post: "POST",
+    );
+
+    #[test]
+    fn test_total_min() {
+        let latency = TestLatencyMin::new();
+        latency.get.observe(120);
+        // post never observed a value and must not pull the minimum down
+        assert_eq!(latency.value().as_u64(), 120);
+
+        latency.post.observe(80);
+        assert_eq!(latency.value().as_u64(), 80);
+    }
+
+    labeled_group!(
+        TestLatencyAvg,
+        "test_latency_avg",
+        "method",
+        total: Average,
+        get: "GET",
+        post: "POST",
+    );
+
+    #[test]
+    fn test_total_weighted_average() {
+        let latency = TestLatencyAvg::new();
+        assert_eq!(latency.value().as_u64(), 0);
+
+        latency.get.observe(10);
+        latency.get.observe(20);
+        latency.get.observe(30);
+        latency.post.observe(100);
+
+        // (10 + 20 + 30 + 100) / 4, not (20 + 100) / 2
+        assert_eq!(latency.value().as_u64(), 40);
+
+        let entries = latency.expand();
+        assert_eq!(entries[0].value.as_u64(), 40);
+        assert_eq!(entries[1].value.as_u64(), 20);
+        assert_eq!(entries[2].value.as_u64(), 100);
+    }
+
+    // A value-mode group whose main field happens to be called `total`
+    labeled_group!(
+        TestNamedTotal,
+        "test_named_total",
+        "method",
+        total: Unsigned,
+        get: "GET": Unsigned,
+    );
+
+    #[test]
+    fn test_main_field_named_total() {
+        let requests = TestNamedTotal::new();
+        requests.total.add(5);
+        requests.get.add(1);
+        assert_eq!(requests.value().as_u64(), 5);
+    }
 }
@@ -20,7 +20,10 @@
 //! assert_eq!(counter.value().as_u64(), 0); // Reset to 0!
 //! ```
 
-use crate::counters::{sealed, CounterValue, MetricKind, Observable, ObservableEntry};
+use crate::counters::{
+    sealed, CounterValue, Distribution, MetricKind, Observable, ObservableEntry, Stability, Unit,
+};
+use std::borrow::Cow;
 use std::fmt::{self, Debug};
 use std::ops::Deref;
 
@@ -145,7 +148,7 @@ impl<T> Resettable<T> {
 
 impl<T: sealed::Resettable> Observable for Resettable<T> {
     /// Returns the name of the underlying counter.
-    fn name(&self) -> &'static str {
+    fn name(&self) -> &str {
         self.inner.name()
     }
 
@@ -157,6 +160,11 @@ impl<T: sealed::Resettable> Observable for Resettable<T> {
         self.inner.value_and_reset()
     }
 
+    /// Returns the distribution AND resets the counter, for histograms.
+    fn distribution(&self) -> Option<Distribution> {
+        self.inner.distribution_and_reset()
+    }
+
     /// Returns the metric kind of the underlying counter.
     ///
     /// Delegates to the inner counter's `metric_kind()` method.
@@ -164,16 +172,44 @@ impl<T: sealed::Resettable> Observable for Resettable<T> {
         self.inner.metric_kind()
     }
 
+    /// Returns the description of the underlying counter.
+    fn description(&self) -> Option<&'static str> {
+        self.inner.description()
+    }
+
+    /// Returns the unit of the underlying counter.
+    fn unit(&self) -> Option<Unit> {
+        self.inner.unit()
+    }
+
+    /// Returns the stability of the underlying counter.
+    fn stability(&self) -> Stability {
+        self.inner.stability()
+    }
+
+    /// Returns whether the underlying counter is additive.
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
     /// Expands this observable into entries, using reset values.
     ///
     /// For resettable counters, each entry's value is read-and-reset.
     fn expand(&self) -> Vec<ObservableEntry> {
-        // For a simple resettable counter, return one entry with the reset value
+        // For a simple resettable counter, return one entry with the reset
+        // value. Histograms are reset once, with the value taken from the
+        // distribution.
+        let distribution = self.inner.distribution_and_reset();
+        let value = match &distribution {
+            Some(distribution) => CounterValue::Unsigned(distribution.count),
+            None => self.inner.value_and_reset(),
+        };
         vec![ObservableEntry {
-            name: self.inner.name(),
-            label: None,
-            value: self.inner.value_and_reset(),
+            name: Cow::Owned(self.inner.name().to_owned()),
+            labels: Vec::new(),
+            value,
             metric_kind: self.inner.metric_kind(),
+            distribution,
         }]
     }
 }
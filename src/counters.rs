@@ -41,6 +41,7 @@
 //! - The assignment is deterministic and stable for the thread's lifetime
 
 pub mod average;
+pub mod histogram;
 pub mod maximum;
 pub mod minimum;
 pub mod monotone;
@@ -53,8 +54,10 @@ use num_traits::Zero;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
     fmt::Debug,
     fmt::Display,
+    ops::Deref,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
@@ -235,6 +238,8 @@ impl CounterValue {
 /// assert_eq!(connections.metric_kind(), MetricKind::Gauge);
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum MetricKind {
     /// A gauge is a metric that can go up and down.
     ///
@@ -251,11 +256,348 @@ pub enum MetricKind {
 
     /// A histogram samples observations and counts them in buckets.
     ///
-    /// Use for values like request latencies, response sizes.
-    /// **Not yet supported** - reserved for future use.
+    /// Use for values like request latencies, response sizes. The buckets are
+    /// reported by [`Observable::distribution()`], see
+    /// [`Histogram`](crate::counters::histogram::Histogram).
     Histogram,
+
+    /// A summary reports precomputed quantiles of the observations.
+    ///
+    /// The quantiles are reported by [`Observable::distribution()`].
+    Summary,
+}
+
+/// The distribution of the observations of a histogram or summary.
+///
+/// Reported by [`Observable::distribution()`] and carried by
+/// [`ObservableEntry::distribution`], next to the plain
+/// [`value`](ObservableEntry::value) used by observers that export a single
+/// number.
+///
+/// # Examples
+///
+/// ```rust
+/// use contatori::counters::histogram::Histogram;
+/// use contatori::counters::Observable;
+///
+/// let latency = Histogram::new(&[0.1, 0.5, 1.0]);
+/// latency.observe(0.05);
+/// latency.observe(0.3);
+///
+/// let distribution = latency.distribution().unwrap();
+/// assert_eq!(distribution.count, 2);
+/// assert_eq!(distribution.buckets, vec![(0.1, 1), (0.5, 2), (1.0, 2)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Distribution {
+    /// Number of observations.
+    pub count: u64,
+    /// Sum of the observations.
+    pub sum: f64,
+    /// Cumulative counts as `(upper bound, count)` pairs, by increasing
+    /// upper bound. The `+Inf` bucket is implied: its count is
+    /// [`count`](Self::count).
+    pub buckets: Vec<(f64, u64)>,
+    /// Precomputed quantiles as `(quantile, value)` pairs, with the
+    /// quantile between 0 and 1.
+    pub quantiles: Vec<(f64, f64)>,
+}
+
+/// The unit a metric is measured in.
+///
+/// Observers render it in their native form: Prometheus appends
+/// [`as_str()`](Unit::as_str) as a name suffix, OpenTelemetry uses the UCUM
+/// code from [`ucum()`](Unit::ucum).
+///
+/// # Examples
+///
+/// ```rust
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::counters::{Observable, Unit};
+///
+/// static SENT: Monotone = Monotone::new()
+///     .with_name("bytes_sent")
+///     .with_unit(Unit::Bytes);
+///
+/// assert_eq!(SENT.unit(), Some(Unit::Bytes));
+/// assert_eq!(Unit::Bytes.as_str(), "bytes");
+/// assert_eq!(Unit::Bytes.ucum(), "By");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    /// Seconds (`s`).
+    Seconds,
+    /// Milliseconds (`ms`).
+    Milliseconds,
+    /// Microseconds (`us`).
+    Microseconds,
+    /// Nanoseconds (`ns`).
+    Nanoseconds,
+    /// Bytes (`By`).
+    Bytes,
+    /// A dimensionless ratio, usually between 0 and 1 (`1`).
+    Ratio,
+    /// A percentage, between 0 and 100 (`%`).
+    Percent,
+    /// Any other unit, rendered verbatim by every observer.
+    Custom(&'static str),
+}
+
+impl Unit {
+    /// Returns the unit as a lowercase, plural word (e.g. `"seconds"`).
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Unit::Seconds => "seconds",
+            Unit::Milliseconds => "milliseconds",
+            Unit::Microseconds => "microseconds",
+            Unit::Nanoseconds => "nanoseconds",
+            Unit::Bytes => "bytes",
+            Unit::Ratio => "ratio",
+            Unit::Percent => "percent",
+            Unit::Custom(unit) => unit,
+        }
+    }
+
+    /// Returns the [UCUM](https://ucum.org) code of the unit (e.g. `"s"`).
+    pub const fn ucum(&self) -> &'static str {
+        match self {
+            Unit::Seconds => "s",
+            Unit::Milliseconds => "ms",
+            Unit::Microseconds => "us",
+            Unit::Nanoseconds => "ns",
+            Unit::Bytes => "By",
+            Unit::Ratio => "1",
+            Unit::Percent => "%",
+            Unit::Custom(unit) => unit,
+        }
+    }
+
+    /// Parses a unit from its [`as_str()`](Unit::as_str) or UCUM form.
+    ///
+    /// Unknown names become [`Unit::Custom`].
+    pub fn from_name(name: &'static str) -> Self {
+        match name {
+            "seconds" | "s" => Unit::Seconds,
+            "milliseconds" | "ms" => Unit::Milliseconds,
+            "microseconds" | "us" => Unit::Microseconds,
+            "nanoseconds" | "ns" => Unit::Nanoseconds,
+            "bytes" | "By" => Unit::Bytes,
+            "ratio" | "1" => Unit::Ratio,
+            "percent" | "%" => Unit::Percent,
+            other => Unit::Custom(other),
+        }
+    }
 }
 
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The maturity of a metric.
+///
+/// Lets consumers know whether a metric can be relied upon. JSON snapshots
+/// carry it for non-stable metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Stability {
+    /// The metric is stable and will not change (default).
+    #[default]
+    Stable,
+    /// The metric may change or disappear without notice.
+    Experimental,
+    /// The metric is still exported but will be removed.
+    Deprecated,
+}
+
+impl Stability {
+    /// Returns the stability as a lowercase word (e.g. `"deprecated"`).
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Stability::Stable => "stable",
+            Stability::Experimental => "experimental",
+            Stability::Deprecated => "deprecated",
+        }
+    }
+}
+
+impl Display for Stability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Descriptive metadata stored by every counter type.
+///
+/// Set through the `with_help()`, `with_unit()` and `with_stability()`
+/// builders and exposed by the matching [`Observable`] methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct Metadata {
+    pub(crate) help: Option<&'static str>,
+    pub(crate) unit: Option<Unit>,
+    pub(crate) stability: Stability,
+}
+
+impl Metadata {
+    /// Creates empty metadata: no help, no unit, stable.
+    pub(crate) const fn new() -> Self {
+        Self {
+            help: None,
+            unit: None,
+            stability: Stability::Stable,
+        }
+    }
+}
+
+/// The name stored by every counter type.
+///
+/// `with_name()` is a const fn and cannot drop a name set before by
+/// `with_owned_name()`: the literal takes precedence instead, and the owned
+/// name is freed with the counter.
+pub(crate) struct Name {
+    literal: Option<&'static str>,
+    owned: Option<Box<str>>,
+}
+
+impl Name {
+    /// Creates an empty name.
+    pub(crate) const fn new() -> Self {
+        Self {
+            literal: None,
+            owned: None,
+        }
+    }
+
+    /// Replaces the name with a string literal.
+    pub(crate) const fn set_literal(&mut self, name: &'static str) {
+        self.literal = Some(name);
+    }
+
+    /// Replaces the name with a name known only at runtime.
+    pub(crate) fn set(&mut self, name: Cow<'static, str>) {
+        match name {
+            Cow::Borrowed(name) => {
+                self.literal = Some(name);
+                self.owned = None;
+            }
+            Cow::Owned(name) => {
+                self.literal = None;
+                self.owned = Some(name.into_boxed_str());
+            }
+        }
+    }
+
+    /// Returns the name, borrowing it when it is a literal.
+    pub(crate) fn to_cow(&self) -> Cow<'static, str> {
+        match (self.literal, &self.owned) {
+            (Some(name), _) => Cow::Borrowed(name),
+            (None, Some(name)) => Cow::Owned(name.to_string()),
+            (None, None) => Cow::Borrowed(""),
+        }
+    }
+}
+
+impl Deref for Name {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match (self.literal, &self.owned) {
+            (Some(name), _) => name,
+            (None, Some(name)) => name,
+            (None, None) => "",
+        }
+    }
+}
+
+impl Display for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+/// Implements [`Observable::expand`] for a counter type storing its [`Name`]
+/// in a `name` field, yielding a single entry without copying a literal name.
+///
+/// Invoked inside the `impl Observable` block of the counter.
+macro_rules! single_entry {
+    () => {
+        fn expand(&self) -> Vec<$crate::counters::ObservableEntry> {
+            vec![$crate::counters::ObservableEntry {
+                name: self.name.to_cow(),
+                labels: Vec::new(),
+                value: self.value(),
+                metric_kind: self.metric_kind(),
+                distribution: self.distribution(),
+            }]
+        }
+    };
+}
+
+/// Implements the `with_help()`, `with_unit()` and `with_stability()`
+/// builders of a counter type storing its [`Metadata`] in a `meta` field.
+///
+/// Invoked inside the inherent `impl` block of the counter.
+macro_rules! metadata_builders {
+    () => {
+        /// Sets the description of this counter, returning `self` for method chaining.
+        ///
+        /// Observers export it as help text (e.g. the Prometheus `# HELP` line).
+        pub const fn with_help(self, help: &'static str) -> Self {
+            let mut counter = self;
+            counter.meta.help = Some(help);
+            counter
+        }
+
+        /// Sets the unit of this counter, returning `self` for method chaining.
+        pub const fn with_unit(self, unit: $crate::counters::Unit) -> Self {
+            let mut counter = self;
+            counter.meta.unit = Some(unit);
+            counter
+        }
+
+        /// Sets the stability of this counter, returning `self` for method chaining.
+        pub const fn with_stability(self, stability: $crate::counters::Stability) -> Self {
+            let mut counter = self;
+            counter.meta.stability = stability;
+            counter
+        }
+    };
+}
+
+/// Implements the [`Observable`] methods returning the [`Metadata`] of a
+/// counter type storing it in a `meta` field.
+///
+/// Invoked inside the `impl Observable` block of the counter.
+macro_rules! metadata_accessors {
+    () => {
+        #[inline]
+        fn description(&self) -> Option<&'static str> {
+            self.meta.help
+        }
+
+        #[inline]
+        fn unit(&self) -> Option<$crate::counters::Unit> {
+            self.meta.unit
+        }
+
+        #[inline]
+        fn stability(&self) -> $crate::counters::Stability {
+            self.meta.stability
+        }
+    };
+}
+
+pub(crate) use {metadata_accessors, metadata_builders, single_entry};
+
 /// Represents a single observable entry with its metadata.
 ///
 /// This struct is returned by [`Observable::expand()`] and contains all the
@@ -265,14 +607,24 @@ pub enum MetricKind {
 /// For labeled groups, `expand()` returns multiple entries (one per sub-counter).
 #[derive(Debug, Clone)]
 pub struct ObservableEntry {
-    /// The metric name (e.g., "http_requests")
-    pub name: &'static str,
-    /// Optional label as (key, value) pair (e.g., ("method", "GET"))
-    pub label: Option<(&'static str, &'static str)>,
+    /// The metric name (e.g., "http_requests").
+    ///
+    /// Borrowed for names given as literals, owned for names known only at
+    /// runtime.
+    pub name: Cow<'static, str>,
+    /// Labels as (key, value) pairs (e.g., `[("method", "GET"), ("status", "200")]`).
+    ///
+    /// Empty for unlabeled entries. Values are [`Cow`] so that label values
+    /// known only at runtime (see [`Family`](crate::adapters::Family)) can be
+    /// carried alongside static ones without leaking strings.
+    pub labels: Vec<(&'static str, Cow<'static, str>)>,
     /// The counter value
     pub value: CounterValue,
     /// The kind of metric (Counter, Gauge, etc.)
     pub metric_kind: MetricKind,
+    /// The distribution of histogram and summary entries, `None` for the
+    /// others.
+    pub distribution: Option<Distribution>,
 }
 
 /// A trait for types that can be observed to retrieve their current value.
@@ -318,10 +670,10 @@ pub struct ObservableEntry {
 pub trait Observable: Debug {
     /// Returns the name of this counter.
     ///
-    /// The name is typically a static string set at counter creation time
-    /// using the `with_name()` builder method. Returns an empty string if
-    /// no name was set.
-    fn name(&self) -> &'static str;
+    /// The name is typically a string literal set at counter creation time
+    /// using the `with_name()` builder method, or a runtime string set with
+    /// `with_owned_name()`. Returns an empty string if no name was set.
+    fn name(&self) -> &str;
 
     /// Returns the kind of metric this counter represents.
     ///
@@ -330,7 +682,8 @@ pub trait Observable: Debug {
     ///
     /// - [`MetricKind::Counter`] → monotonically increasing values
     /// - [`MetricKind::Gauge`] → values that can go up or down
-    /// - [`MetricKind::Histogram`] → distributions (not yet supported)
+    /// - [`MetricKind::Histogram`] / [`MetricKind::Summary`] → distributions,
+    ///   reported by [`distribution()`](Self::distribution)
     ///
     /// The default implementation returns [`MetricKind::Gauge`], which is
     /// appropriate for most counter types.
@@ -352,6 +705,42 @@ pub trait Observable: Debug {
         MetricKind::Gauge
     }
 
+    /// Returns a human-readable description of this metric, if any.
+    ///
+    /// Observers use it as help text (e.g. the Prometheus `# HELP` line).
+    /// Counters return the text set with `with_help()`. The default
+    /// implementation returns `None`.
+    fn description(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// Returns the unit this metric is measured in, if any.
+    ///
+    /// Counters return the unit set with `with_unit()`. The default
+    /// implementation returns `None`.
+    fn unit(&self) -> Option<Unit> {
+        None
+    }
+
+    /// Returns the stability of this metric.
+    ///
+    /// Counters return the stability set with `with_stability()`. The
+    /// default implementation returns [`Stability::Stable`].
+    fn stability(&self) -> Stability {
+        Stability::Stable
+    }
+
+    /// Returns `true` if the values of this gauge add up, like a balance or
+    /// a number of requests in flight, rather than being measurements, like
+    /// a temperature or a maximum.
+    ///
+    /// Observers with a distinct instrument for such gauges use it, e.g.
+    /// OpenTelemetry's up-down counter. [`Signed`](signed::Signed) counters
+    /// return `true`. The default implementation returns `false`.
+    fn is_additive(&self) -> bool {
+        false
+    }
+
     /// Returns the current aggregated value of the counter.
     ///
     /// This method reads all shards and computes the aggregate value
@@ -368,6 +757,16 @@ pub trait Observable: Debug {
     /// trade-off for counters where writes vastly outnumber reads.
     fn value(&self) -> CounterValue;
 
+    /// Returns the distribution of the observations, for histograms and
+    /// summaries.
+    ///
+    /// Like [`value()`](Self::value), this resets counters wrapped with
+    /// [`Resettable`](crate::adapters::Resettable). The default
+    /// implementation returns `None`.
+    fn distribution(&self) -> Option<Distribution> {
+        None
+    }
+
     /// Expands this observable into one or more entries.
     ///
     /// This is the primary method used by observers to collect metrics.
@@ -393,10 +792,11 @@ pub trait Observable: Debug {
     /// ```
     fn expand(&self) -> Vec<ObservableEntry> {
         vec![ObservableEntry {
-            name: self.name(),
-            label: None,
+            name: Cow::Owned(self.name().to_owned()),
+            labels: Vec::new(),
             value: self.value(),
             metric_kind: self.metric_kind(),
+            distribution: self.distribution(),
         }]
     }
 }
@@ -412,12 +812,66 @@ impl Display for dyn Observable + '_ {
     }
 }
 
+/// Counters whose values can be combined across several instances.
+///
+/// This is used by [`labeled_group!`](crate::labeled_group) in *total mode*
+/// to compute the unlabeled entry (and per-dimension totals) at read time
+/// from the labeled children, so the hot path only touches one counter.
+///
+/// Aggregation works on a [`Partial`](Aggregate::Partial) state rather than
+/// on [`CounterValue`] so that combinations which are not a plain fold of the
+/// reported values stay exact.
+///
+/// # Example
+///
+/// ```rust
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::counters::{Aggregate, CounterValue};
+///
+/// let get = Monotone::new();
+/// let post = Monotone::new();
+/// get.add(3);
+/// post.add(4);
+///
+/// assert_eq!(Monotone::aggregate([&get, &post]), CounterValue::Unsigned(7));
+/// ```
+pub trait Aggregate: Observable {
+    /// Intermediate state combined across instances.
+    type Partial: Copy;
+
+    /// The partial state of an aggregate over no instances.
+    const IDENTITY: Self::Partial;
+
+    /// Reads the partial state of this instance.
+    ///
+    /// This never resets the counter, even for types that support resetting.
+    fn partial(&self) -> Self::Partial;
+
+    /// Combines two partial states.
+    fn merge(a: Self::Partial, b: Self::Partial) -> Self::Partial;
+
+    /// Converts a partial state into the value reported to observers.
+    fn finish(partial: Self::Partial) -> CounterValue;
+
+    /// Aggregates the values of all the given instances.
+    fn aggregate<'a>(items: impl IntoIterator<Item = &'a Self>) -> CounterValue
+    where
+        Self: Sized + 'a,
+    {
+        Self::finish(
+            items
+                .into_iter()
+                .fold(Self::IDENTITY, |acc, item| Self::merge(acc, item.partial())),
+        )
+    }
+}
+
 /// Internal sealed module for reset functionality.
 ///
 /// This module contains the internal trait used by the `Resettable` adapter
 /// to access the reset functionality of counters. It is not part of the public API.
 pub(crate) mod sealed {
-    use super::{CounterValue, Observable};
+    use super::{CounterValue, Distribution, Observable};
 
     /// Internal trait for counters that support atomic read-and-reset.
     ///
@@ -437,6 +891,12 @@ pub(crate) mod sealed {
         /// either the returned value or the next collection period. For
         /// metrics and statistics, this is typically acceptable.
         fn value_and_reset(&self) -> CounterValue;
+
+        /// Returns the distribution and resets the counter, for histograms
+        /// and summaries. Returns `None` without resetting otherwise.
+        fn distribution_and_reset(&self) -> Option<Distribution> {
+            None
+        }
     }
 }
 
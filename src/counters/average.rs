@@ -7,9 +7,13 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
-use crate::counters::{sealed, CounterValue, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX};
+use crate::counters::{
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue, Metadata,
+    Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+};
 
 /// Internal component that stores sum and count for a single shard.
 ///
@@ -82,7 +86,8 @@ impl SumCount {
 /// assert_eq!(avg.average(), Some(100));
 /// ```
 pub struct Average {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<SumCount>; NUM_COMPONENTS],
 }
 
@@ -102,10 +107,12 @@ impl Average {
     /// assert_eq!(avg.average(), None); // No observations yet
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const ZERO: CachePadded<SumCount> = CachePadded::new(SumCount::new());
         Average {
             components: [ZERO; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -121,9 +128,22 @@ impl Average {
     /// assert_eq!(avg.name(), "response_time_avg");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Returns a reference to the current thread's shard.
     #[inline]
     fn get_local_component(&self) -> &SumCount {
@@ -268,13 +288,7 @@ impl Average {
     /// ```
     #[inline]
     pub fn average(&self) -> Option<usize> {
-        let total_sum = self.sum();
-        let total_count = self.count();
-        if total_count == 0 {
-            None
-        } else {
-            Some(total_sum / total_count)
-        }
+        self.sum().checked_div(self.count())
     }
 
     /// Computes the average as a floating-point number for higher precision.
@@ -366,11 +380,7 @@ impl Average {
     #[inline]
     pub fn average_and_reset(&self) -> Option<usize> {
         let (sum, count) = self.raw_value_and_reset();
-        if count == 0 {
-            None
-        } else {
-            Some(sum / count)
-        }
+        sum.checked_div(count)
     }
 }
 
@@ -385,9 +395,13 @@ impl Observable for Average {
 
     /// Returns the name of this counter.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
+
+    metadata_accessors!();
+
+    single_entry!();
 }
 
 impl sealed::Resettable for Average {
@@ -400,6 +414,30 @@ impl sealed::Resettable for Average {
     }
 }
 
+impl Aggregate for Average {
+    /// The `(sum, count)` pair, so that the aggregate is a weighted mean.
+    type Partial = (usize, usize);
+
+    const IDENTITY: (usize, usize) = (0, 0);
+
+    #[inline]
+    fn partial(&self) -> (usize, usize) {
+        (self.sum(), self.count())
+    }
+
+    /// Adds sums and counts separately.
+    #[inline]
+    fn merge(a: (usize, usize), b: (usize, usize)) -> (usize, usize) {
+        (a.0.wrapping_add(b.0), a.1.wrapping_add(b.1))
+    }
+
+    /// Returns the truncated mean, or `0` if nothing was observed.
+    #[inline]
+    fn finish((sum, count): (usize, usize)) -> CounterValue {
+        CounterValue::Unsigned(sum.checked_div(count).unwrap_or(0) as u64)
+    }
+}
+
 impl Default for Average {
     /// Creates a new average counter initialized to zero.
     fn default() -> Self {
@@ -676,4 +714,15 @@ mod tests {
         assert_eq!(counter.count(), 3);
         assert_eq!(counter.average(), Some(20));
     }
+
+    #[test]
+    fn test_aggregate_weighted_mean() {
+        use crate::counters::Aggregate;
+        let a = Average::new();
+        let b = Average::new();
+        assert_eq!(Average::aggregate([&a, &b]), CounterValue::Unsigned(0));
+        a.observe_many(30, 3);
+        b.observe(50);
+        assert_eq!(Average::aggregate([&a, &b]), CounterValue::Unsigned(20));
+    }
 }
@@ -0,0 +1,496 @@
+//! Histogram counter with sharded atomic storage.
+//!
+//! This counter type returns [`MetricKind::Histogram`] and reports its
+//! buckets through [`Observable::distribution()`], which observers export as
+//! `_bucket`, `_sum` and `_count` series.
+//!
+//! This module provides [`Histogram`], which counts observations in buckets
+//! with fixed upper bounds. Like the other counters, every thread updates its
+//! own cache-line-padded shard.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
+use std::fmt::Debug;
+
+use crate::counters::{
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue,
+    Distribution, Metadata, MetricKind, Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+};
+
+/// The default Prometheus buckets, in seconds: from 5ms to 10s.
+pub const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Internal component that stores the buckets of a single shard.
+///
+/// Bucket counts are not cumulative here; they are accumulated when the
+/// distribution is read. There is no separate count: it is the sum of the
+/// buckets, `+Inf` included, so it can never be lower than a cumulative
+/// bucket. The sum is stored as the bits of an `f64`.
+struct Buckets<const N: usize> {
+    counts: [AtomicU64; N],
+    inf: AtomicU64,
+    sum: AtomicU64,
+}
+
+impl<const N: usize> Buckets<N> {
+    const fn new() -> Self {
+        Buckets {
+            counts: [const { AtomicU64::new(0) }; N],
+            inf: AtomicU64::new(0),
+            sum: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the number of observations of this shard.
+    fn count(&self) -> u64 {
+        self.counts
+            .iter()
+            .chain(std::iter::once(&self.inf))
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+/// A high-performance histogram using sharded atomic storage.
+///
+/// `Histogram` counts observations in `N` buckets with the given upper
+/// bounds, plus an implicit `+Inf` bucket, and keeps their count and sum.
+/// An observation falls in the first bucket whose upper bound is greater
+/// than or equal to it. NaN observations are dropped.
+///
+/// # Memory
+///
+/// Each shard stores `N + 2` 64-bit atomics (the buckets, `+Inf` and the
+/// sum), padded to a cache line. On
+/// targets with 128-byte padding (x86-64, aarch64), a histogram with up to 14
+/// buckets uses as much memory as an
+/// [`Average`](crate::counters::average::Average).
+///
+/// # Examples
+///
+/// ```rust
+/// use contatori::counters::histogram::Histogram;
+/// use contatori::counters::{Observable, Unit};
+///
+/// static LATENCY: Histogram<3> = Histogram::new(&[0.1, 0.5, 1.0])
+///     .with_name("request_latency")
+///     .with_unit(Unit::Seconds);
+///
+/// LATENCY.observe(0.05);
+/// LATENCY.observe(0.3);
+/// LATENCY.observe(2.0);
+///
+/// let distribution = LATENCY.distribution().unwrap();
+/// assert_eq!(distribution.count, 3);
+/// assert_eq!(distribution.buckets, vec![(0.1, 1), (0.5, 2), (1.0, 2)]);
+///
+/// // Observers that export a single number get the count
+/// assert_eq!(LATENCY.value().as_u64(), 3);
+/// ```
+pub struct Histogram<const N: usize> {
+    name: Name,
+    meta: Metadata,
+    bounds: &'static [f64; N],
+    components: [CachePadded<Buckets<N>>; NUM_COMPONENTS],
+}
+
+impl<const N: usize> Histogram<N> {
+    /// Creates a new histogram with the given bucket upper bounds.
+    ///
+    /// # Panics
+    ///
+    /// Panics (at compile time in a `static`) if the bounds are not strictly
+    /// increasing or contain NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use contatori::counters::histogram::{Histogram, DEFAULT_BUCKETS};
+    ///
+    /// let latency = Histogram::new(&DEFAULT_BUCKETS);
+    /// assert_eq!(latency.bounds().len(), 11);
+    /// ```
+    pub const fn new(bounds: &'static [f64; N]) -> Self {
+        let mut i = 0;
+        while i < N {
+            assert!(!bounds[i].is_nan(), "histogram bounds cannot be NaN");
+            assert!(
+                i == 0 || bounds[i - 1] < bounds[i],
+                "histogram bounds must be strictly increasing"
+            );
+            i += 1;
+        }
+        Histogram {
+            name: Name::new(),
+            meta: Metadata::new(),
+            bounds,
+            components: [const { CachePadded::new(Buckets::new()) }; NUM_COMPONENTS],
+        }
+    }
+
+    /// Sets the name of this counter, returning `self` for method chaining.
+    pub const fn with_name(self, name: &'static str) -> Self {
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
+    }
+
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
+    /// Returns the upper bounds of the buckets, without `+Inf`.
+    pub const fn bounds(&self) -> &'static [f64; N] {
+        self.bounds
+    }
+
+    /// Returns a reference to the current thread's shard.
+    #[inline]
+    fn get_local_component(&self) -> &Buckets<N> {
+        THREAD_SLOT_INDEX.with(|idx| &*self.components[*idx])
+    }
+
+    /// Records an observation.
+    ///
+    /// NaN is dropped: it has no bucket and would turn the sum into NaN.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use contatori::counters::histogram::Histogram;
+    ///
+    /// let sizes = Histogram::new(&[512.0, 4096.0]);
+    /// sizes.observe(100.0);
+    /// sizes.observe(1000.0);
+    ///
+    /// assert_eq!(sizes.count(), 2);
+    /// assert_eq!(sizes.sum(), 1100.0);
+    /// ```
+    #[inline]
+    pub fn observe(&self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        let component = self.get_local_component();
+        match self.bounds.iter().position(|bound| value <= *bound) {
+            Some(bucket) => component.counts[bucket].fetch_add(1, Ordering::Relaxed),
+            None => component.inf.fetch_add(1, Ordering::Relaxed),
+        };
+        // The shard is only written by its threads, so this rarely retries
+        let _ = component
+            .sum
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            });
+    }
+
+    /// Records a duration, in seconds.
+    #[inline]
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+
+    /// Returns the number of observations across all shards.
+    ///
+    /// This reads every bucket of every shard.
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.components.iter().map(|c| c.count()).sum()
+    }
+
+    /// Returns the sum of the observations across all shards.
+    #[inline]
+    pub fn sum(&self) -> f64 {
+        self.components
+            .iter()
+            .map(|c| f64::from_bits(c.sum.load(Ordering::Relaxed)))
+            .sum()
+    }
+
+    /// Aggregates all shards into a distribution, resetting them if `reset`.
+    fn collect(&self, reset: bool) -> Distribution {
+        let read = |atomic: &AtomicU64| match reset {
+            true => atomic.swap(0, Ordering::Relaxed),
+            false => atomic.load(Ordering::Relaxed),
+        };
+        let mut counts = [0u64; N];
+        let mut inf = 0;
+        let mut sum = 0.0;
+        for component in self.components.iter() {
+            for (total, bucket) in counts.iter_mut().zip(&component.counts) {
+                *total += read(bucket);
+            }
+            inf += read(&component.inf);
+            sum += f64::from_bits(read(&component.sum));
+        }
+        let count = counts.iter().sum::<u64>() + inf;
+        let buckets = self
+            .bounds
+            .iter()
+            .zip(counts)
+            .scan(0, |cumulative, (bound, count)| {
+                *cumulative += count;
+                Some((*bound, *cumulative))
+            })
+            .collect();
+        Distribution {
+            count,
+            sum,
+            buckets,
+            quantiles: Vec::new(),
+        }
+    }
+}
+
+impl<const N: usize> Observable for Histogram<N> {
+    /// Returns the number of observations.
+    #[inline]
+    fn value(&self) -> CounterValue {
+        CounterValue::Unsigned(self.count())
+    }
+
+    /// Returns the cumulative buckets, count and sum.
+    fn distribution(&self) -> Option<Distribution> {
+        Some(self.collect(false))
+    }
+
+    /// Returns the name of this counter.
+    #[inline]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    metadata_accessors!();
+
+    single_entry!();
+
+    /// Returns [`MetricKind::Histogram`].
+    #[inline]
+    fn metric_kind(&self) -> MetricKind {
+        MetricKind::Histogram
+    }
+}
+
+impl<const N: usize> Aggregate for Histogram<N> {
+    /// The number of observations.
+    type Partial = u64;
+
+    const IDENTITY: u64 = 0;
+
+    #[inline]
+    fn partial(&self) -> u64 {
+        self.count()
+    }
+
+    #[inline]
+    fn merge(a: u64, b: u64) -> u64 {
+        a + b
+    }
+
+    /// Returns the total number of observations, like [`Observable::value`].
+    #[inline]
+    fn finish(partial: u64) -> CounterValue {
+        CounterValue::Unsigned(partial)
+    }
+}
+
+impl<const N: usize> sealed::Resettable for Histogram<N> {
+    /// Returns the number of observations and resets all buckets.
+    fn value_and_reset(&self) -> CounterValue {
+        CounterValue::Unsigned(self.collect(true).count)
+    }
+
+    /// Returns the distribution and resets all buckets.
+    ///
+    /// The buckets are reset one at a time: an observation recorded
+    /// meanwhile may be split between this period and the next, with its
+    /// bucket in one and its value in the sum of the other.
+    fn distribution_and_reset(&self) -> Option<Distribution> {
+        Some(self.collect(true))
+    }
+}
+
+impl Default for Histogram<11> {
+    /// Creates a histogram with the [`DEFAULT_BUCKETS`] and no name.
+    ///
+    /// This lets histograms be used as [`Family`](crate::adapters::Family)
+    /// children.
+    fn default() -> Self {
+        Self::new(&DEFAULT_BUCKETS)
+    }
+}
+
+impl<const N: usize> Debug for Histogram<N> {
+    /// Formats the histogram with its cumulative buckets.
+    ///
+    /// Output format: `name{ le=bound:count ... count:n sum:s }`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let distribution = self.collect(false);
+        write!(f, "{}{{", self.name)?;
+        for (bound, count) in &distribution.buckets {
+            write!(f, " le={bound}:{count}")?;
+        }
+        write!(
+            f,
+            " count:{} sum:{} }}",
+            distribution.count, distribution.sum
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Resettable;
+    use crate::counters::Unit;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_new() {
+        let histogram = Histogram::new(&[1.0, 2.0]);
+        assert_eq!(histogram.value(), CounterValue::Unsigned(0));
+        assert_eq!(
+            histogram.distribution(),
+            Some(Distribution {
+                count: 0,
+                sum: 0.0,
+                buckets: vec![(1.0, 0), (2.0, 0)],
+                quantiles: Vec::new(),
+            })
+        );
+        assert_eq!(histogram.metric_kind(), MetricKind::Histogram);
+    }
+
+    #[test]
+    fn test_observe_buckets() {
+        let histogram = Histogram::new(&[1.0, 2.0, 5.0]);
+        for value in [0.5, 1.0, 1.5, 3.0, 7.0, 9.0] {
+            histogram.observe(value);
+        }
+        let distribution = histogram.distribution().unwrap();
+        // Upper bounds are inclusive and counts are cumulative
+        assert_eq!(distribution.buckets, vec![(1.0, 2), (2.0, 3), (5.0, 4)]);
+        assert_eq!(distribution.count, 6);
+        assert_eq!(distribution.sum, 22.0);
+    }
+
+    #[test]
+    fn test_observe_nan() {
+        let histogram = Histogram::new(&[1.0]);
+        histogram.observe(0.5);
+        histogram.observe(f64::NAN);
+        histogram.observe(f64::INFINITY);
+
+        let distribution = histogram.distribution().unwrap();
+        assert_eq!(distribution.count, 2);
+        assert_eq!(distribution.buckets, vec![(1.0, 1)]);
+        assert_eq!(distribution.sum, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_observe_duration() {
+        let histogram = Histogram::new(&DEFAULT_BUCKETS);
+        histogram.observe_duration(Duration::from_millis(20));
+        assert_eq!(histogram.distribution().unwrap().buckets[1], (0.01, 0));
+        assert_eq!(histogram.distribution().unwrap().buckets[2], (0.025, 1));
+    }
+
+    #[test]
+    fn test_static_with_metadata() {
+        static LATENCY: Histogram<2> = Histogram::new(&[0.1, 1.0])
+            .with_name("latency")
+            .with_help("Request latency")
+            .with_unit(Unit::Seconds);
+        assert_eq!(LATENCY.name(), "latency");
+        assert_eq!(LATENCY.description(), Some("Request latency"));
+        assert_eq!(LATENCY.unit(), Some(Unit::Seconds));
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn test_unordered_bounds() {
+        Histogram::new(&[2.0, 1.0]);
+    }
+
+    #[test]
+    fn test_expand_carries_distribution() {
+        let histogram = Histogram::new(&[1.0]).with_name("h");
+        histogram.observe(0.5);
+        let entries = histogram.expand();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].value, CounterValue::Unsigned(1));
+        assert_eq!(
+            entries[0].distribution.as_ref().unwrap().buckets,
+            vec![(1.0, 1)]
+        );
+    }
+
+    #[test]
+    fn test_resettable() {
+        let histogram = Resettable::new(Histogram::new(&[1.0]));
+        histogram.observe(0.5);
+        histogram.observe(3.0);
+
+        let entries = histogram.expand();
+        assert_eq!(entries[0].value, CounterValue::Unsigned(2));
+        assert_eq!(entries[0].distribution.as_ref().unwrap().sum, 3.5);
+
+        let distribution = histogram.distribution().unwrap();
+        assert_eq!(distribution.count, 0);
+        assert_eq!(distribution.buckets, vec![(1.0, 0)]);
+    }
+
+    #[test]
+    fn test_multithreaded() {
+        let histogram = Arc::new(Histogram::new(&[10.0]));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let histogram = Arc::clone(&histogram);
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        histogram.observe((i % 20) as f64);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let distribution = histogram.distribution().unwrap();
+        assert_eq!(distribution.count, 4000);
+        assert_eq!(distribution.buckets, vec![(10.0, 2200)]);
+        assert_eq!(distribution.sum, 4.0 * 50.0 * 190.0);
+    }
+
+    #[test]
+    fn test_count_never_below_buckets() {
+        let histogram = Arc::new(Histogram::new(&[1.0]));
+        let writer = {
+            let histogram = Arc::clone(&histogram);
+            thread::spawn(move || {
+                for _ in 0..100_000 {
+                    histogram.observe(0.5);
+                }
+            })
+        };
+        while !writer.is_finished() {
+            let distribution = histogram.distribution().unwrap();
+            assert!(distribution.count >= distribution.buckets[0].1);
+        }
+        writer.join().unwrap();
+        assert_eq!(histogram.count(), 100_000);
+    }
+}
@@ -7,9 +7,13 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
-use crate::counters::{sealed, CounterValue, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX};
+use crate::counters::{
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue, Metadata,
+    Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+};
 
 /// A high-performance maximum value tracker using sharded atomic storage.
 ///
@@ -51,7 +55,8 @@ use crate::counters::{sealed, CounterValue, Observable, NUM_COMPONENTS, THREAD_S
 /// assert_eq!(max_latency.value(), contatori::counters::CounterValue::Unsigned(200));
 /// ```
 pub struct Maximum {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicUsize>; NUM_COMPONENTS],
 }
 
@@ -72,10 +77,12 @@ impl Maximum {
     /// assert_eq!(tracker.value(), contatori::counters::CounterValue::Unsigned(0));
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const MIN: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(usize::MIN));
         Maximum {
             components: [MIN; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -91,9 +98,22 @@ impl Maximum {
     /// assert_eq!(tracker.name(), "max_response_time");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Observes a value and updates the local maximum if necessary.
     ///
     /// This method uses a compare-and-swap loop to atomically update the
@@ -185,9 +205,13 @@ impl Observable for Maximum {
 
     /// Returns the name of this tracker.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
+
+    metadata_accessors!();
+
+    single_entry!();
 }
 
 impl sealed::Resettable for Maximum {
@@ -201,6 +225,30 @@ impl sealed::Resettable for Maximum {
     }
 }
 
+impl Aggregate for Maximum {
+    /// The maximum observed so far, or `None` if nothing was observed.
+    type Partial = Option<usize>;
+
+    const IDENTITY: Option<usize> = None;
+
+    #[inline]
+    fn partial(&self) -> Option<usize> {
+        self.raw_value()
+    }
+
+    /// Keeps the larger of the two maxima.
+    #[inline]
+    fn merge(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+        a.max(b)
+    }
+
+    /// Returns `0` if no instance observed a value, like [`Observable::value`].
+    #[inline]
+    fn finish(partial: Option<usize>) -> CounterValue {
+        CounterValue::Unsigned(partial.unwrap_or(0) as u64)
+    }
+}
+
 impl Debug for Maximum {
     /// Formats the tracker showing shards that have observed values.
     ///
@@ -371,4 +419,15 @@ mod tests {
         // 0 is a valid observation, should be returned
         assert_eq!(counter.value(), CounterValue::Unsigned(0));
     }
+
+    #[test]
+    fn test_aggregate() {
+        use crate::counters::Aggregate;
+        let a = Maximum::new();
+        let b = Maximum::new();
+        assert_eq!(Maximum::aggregate([&a, &b]), CounterValue::Unsigned(0));
+        a.observe(7);
+        b.observe(42);
+        assert_eq!(Maximum::aggregate([&a, &b]), CounterValue::Unsigned(42));
+    }
 }
@@ -7,9 +7,13 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
-use crate::counters::{sealed, CounterValue, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX};
+use crate::counters::{
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue, Metadata,
+    Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+};
 
 /// A high-performance minimum value tracker using sharded atomic storage.
 ///
@@ -51,7 +55,8 @@ use crate::counters::{sealed, CounterValue, Observable, NUM_COMPONENTS, THREAD_S
 /// assert_eq!(min_latency.value(), contatori::counters::CounterValue::Unsigned(85));
 /// ```
 pub struct Minimum {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicUsize>; NUM_COMPONENTS],
 }
 
@@ -72,10 +77,12 @@ impl Minimum {
     /// assert_eq!(tracker.value(), contatori::counters::CounterValue::Unsigned(u64::MAX));
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const MAX: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(usize::MAX));
         Minimum {
             components: [MAX; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -91,9 +98,22 @@ impl Minimum {
     /// assert_eq!(tracker.name(), "min_response_time");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Returns a reference to the current thread's shard.
     #[inline]
     fn get_component_counter(&self) -> &AtomicUsize {
@@ -188,9 +208,13 @@ impl Observable for Minimum {
 
     /// Returns the name of this tracker.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
+
+    metadata_accessors!();
+
+    single_entry!();
 }
 
 impl sealed::Resettable for Minimum {
@@ -203,6 +227,29 @@ impl sealed::Resettable for Minimum {
     }
 }
 
+impl Aggregate for Minimum {
+    /// The minimum observed so far, `usize::MAX` if nothing was observed.
+    type Partial = usize;
+
+    const IDENTITY: usize = usize::MAX;
+
+    #[inline]
+    fn partial(&self) -> usize {
+        self.raw_value()
+    }
+
+    /// Keeps the smaller of the two minima.
+    #[inline]
+    fn merge(a: usize, b: usize) -> usize {
+        a.min(b)
+    }
+
+    #[inline]
+    fn finish(partial: usize) -> CounterValue {
+        CounterValue::Unsigned(partial as u64)
+    }
+}
+
 impl Default for Minimum {
     /// Creates a new tracker with all shards set to `MAX`.
     fn default() -> Self {
@@ -401,4 +448,15 @@ mod tests {
         assert_eq!(counter.value(), CounterValue::Unsigned(u64::MAX));
         assert_eq!(counter.name(), "");
     }
+
+    #[test]
+    fn test_aggregate_ignores_unobserved() {
+        use crate::counters::Aggregate;
+        let a = Minimum::new();
+        let b = Minimum::new();
+        a.observe(42);
+        assert_eq!(Minimum::aggregate([&a, &b]), CounterValue::Unsigned(42));
+        b.observe(7);
+        assert_eq!(Minimum::aggregate([&a, &b]), CounterValue::Unsigned(7));
+    }
 }
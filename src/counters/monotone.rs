@@ -10,11 +10,12 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
 use crate::counters::{
-    sealed, CounterValue, GetComponentCounter, MetricKind, Observable, NUM_COMPONENTS,
-    THREAD_SLOT_INDEX,
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue,
+    GetComponentCounter, Metadata, MetricKind, Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
 };
 
 /// A high-performance monotone integer counter using sharded atomic storage.
@@ -76,7 +77,8 @@ use crate::counters::{
 /// assert_eq!(counter.value(), contatori::counters::CounterValue::Unsigned(4000));
 /// ```
 pub struct Monotone {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicUsize>; NUM_COMPONENTS],
 }
 
@@ -105,10 +107,12 @@ impl Monotone {
     /// assert_eq!(counter.value(), contatori::counters::CounterValue::Unsigned(0));
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const ZERO: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(0));
         Monotone {
             components: [ZERO; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -127,9 +131,22 @@ impl Monotone {
     /// assert_eq!(counter.name(), "http_requests");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Adds a value to the counter.
     ///
     /// This operation is lock-free and extremely fast due to sharding.
@@ -182,10 +199,14 @@ impl Observable for Monotone {
 
     /// Returns the name of this counter.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
 
+    metadata_accessors!();
+
+    single_entry!();
+
     /// Returns [`MetricKind::Counter`] because `Monotone` counters are monotonically increasing.
     ///
     /// This counter only supports `add()` operations and never decreases,
@@ -207,6 +228,29 @@ impl sealed::Resettable for Monotone {
     }
 }
 
+impl Aggregate for Monotone {
+    type Partial = u64;
+
+    const IDENTITY: u64 = 0;
+
+    /// Returns the total value across all shards.
+    #[inline]
+    fn partial(&self) -> u64 {
+        self.total_value() as u64
+    }
+
+    /// Sums the two totals.
+    #[inline]
+    fn merge(a: u64, b: u64) -> u64 {
+        a.wrapping_add(b)
+    }
+
+    #[inline]
+    fn finish(partial: u64) -> CounterValue {
+        CounterValue::Unsigned(partial)
+    }
+}
+
 impl Default for Monotone {
     /// Creates a new counter initialized to zero with no name.
     fn default() -> Self {
@@ -345,4 +389,15 @@ mod tests {
         assert_eq!(counter.value(), CounterValue::Unsigned(0));
         assert_eq!(counter.name(), "");
     }
+
+    #[test]
+    fn test_aggregate() {
+        use crate::counters::Aggregate;
+        let a = Monotone::new();
+        let b = Monotone::new();
+        a.add(3);
+        b.add(4);
+        assert_eq!(Monotone::aggregate([&a, &b]), CounterValue::Unsigned(7));
+        assert_eq!(Monotone::aggregate([]), CounterValue::Unsigned(0));
+    }
 }
@@ -40,13 +40,14 @@
 
 use atomic_time::AtomicOptionInstant;
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::Instant;
 
 use crate::counters::{
-    sealed, CounterValue, GetComponentCounter, MetricKind, Observable, ObservableEntry,
-    NUM_COMPONENTS, THREAD_SLOT_INDEX,
+    metadata_accessors, metadata_builders, sealed, single_entry, CounterValue, GetComponentCounter,
+    Metadata, MetricKind, Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
 };
 
 /// A high-performance rate counter using sharded atomic storage.
@@ -123,7 +124,8 @@ use crate::counters::{
 /// assert_eq!(counter.total_value(), 4000);
 /// ```
 pub struct Rate {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicUsize>; NUM_COMPONENTS],
     /// Last observed value for rate calculation
     last_value: AtomicU64,
@@ -157,9 +159,11 @@ impl Rate {
     /// assert_eq!(counter.total_value(), 0);
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const ZERO: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(0));
         Rate {
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
             components: [ZERO; NUM_COMPONENTS],
             last_value: AtomicU64::new(0),
             last_instant: AtomicOptionInstant::none(),
@@ -181,9 +185,22 @@ impl Rate {
     /// assert_eq!(counter.name(), "http_requests_rate");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Adds a value to the counter.
     ///
     /// This operation is lock-free and extremely fast due to sharding.
@@ -302,25 +319,19 @@ impl Observable for Rate {
 
     /// Returns the name of this counter.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
 
+    metadata_accessors!();
+
+    single_entry!();
+
     /// Returns [`MetricKind::Gauge`] because rates can increase or decrease.
     #[inline]
     fn metric_kind(&self) -> MetricKind {
         MetricKind::Gauge
     }
-
-    /// Expands this rate counter into observable entries.
-    fn expand(&self) -> Vec<ObservableEntry> {
-        vec![ObservableEntry {
-            name: self.name(),
-            label: None,
-            value: self.value(),
-            metric_kind: self.metric_kind(),
-        }]
-    }
 }
 
 impl sealed::Resettable for Rate {
@@ -499,7 +510,7 @@ mod tests {
 
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].name, "test_rate");
-        assert!(entries[0].label.is_none());
+        assert!(entries[0].labels.is_empty());
         assert_eq!(entries[0].metric_kind, MetricKind::Gauge);
     }
 
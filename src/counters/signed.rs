@@ -7,10 +7,12 @@
 use std::sync::atomic::{AtomicIsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
 use crate::counters::{
-    sealed, CounterValue, GetComponentCounter, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue,
+    GetComponentCounter, Metadata, Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
 };
 
 /// A high-performance signed integer counter using sharded atomic storage.
@@ -61,7 +63,8 @@ use crate::counters::{
 /// assert_eq!(balance.value(), contatori::counters::CounterValue::Signed(-50));
 /// ```
 pub struct Signed {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicIsize>; NUM_COMPONENTS],
 }
 
@@ -90,10 +93,12 @@ impl Signed {
     /// assert_eq!(counter.value(), contatori::counters::CounterValue::Signed(0));
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const ZERO: CachePadded<AtomicIsize> = CachePadded::new(AtomicIsize::new(0));
         Signed {
             components: [ZERO; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -109,9 +114,22 @@ impl Signed {
     /// assert_eq!(counter.name(), "temperature_delta");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Adds a value to the counter (can be negative).
     ///
     /// # Examples
@@ -192,9 +210,19 @@ impl Observable for Signed {
 
     /// Returns the name of this counter.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns `true`: the value is a running sum of increments.
+    #[inline]
+    fn is_additive(&self) -> bool {
+        true
     }
+
+    metadata_accessors!();
+
+    single_entry!();
 }
 
 impl sealed::Resettable for Signed {
@@ -205,6 +233,29 @@ impl sealed::Resettable for Signed {
     }
 }
 
+impl Aggregate for Signed {
+    type Partial = i64;
+
+    const IDENTITY: i64 = 0;
+
+    /// Returns the total value across all shards.
+    #[inline]
+    fn partial(&self) -> i64 {
+        self.total_value() as i64
+    }
+
+    /// Sums the two totals.
+    #[inline]
+    fn merge(a: i64, b: i64) -> i64 {
+        a.wrapping_add(b)
+    }
+
+    #[inline]
+    fn finish(partial: i64) -> CounterValue {
+        CounterValue::Signed(partial)
+    }
+}
+
 impl Default for Signed {
     /// Creates a new counter initialized to zero with no name.
     fn default() -> Self {
@@ -387,4 +438,14 @@ mod tests {
         assert_eq!(counter.value(), CounterValue::Signed(0));
         assert_eq!(counter.name(), "");
     }
+
+    #[test]
+    fn test_aggregate() {
+        use crate::counters::Aggregate;
+        let a = Signed::new();
+        let b = Signed::new();
+        a.add(5);
+        b.sub(8);
+        assert_eq!(Signed::aggregate([&a, &b]), CounterValue::Signed(-3));
+    }
 }
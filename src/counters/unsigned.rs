@@ -7,10 +7,12 @@
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crossbeam_utils::CachePadded;
+use std::borrow::Cow;
 use std::fmt::Debug;
 
 use crate::counters::{
-    sealed, CounterValue, GetComponentCounter, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
+    metadata_accessors, metadata_builders, sealed, single_entry, Aggregate, CounterValue,
+    GetComponentCounter, Metadata, Name, Observable, NUM_COMPONENTS, THREAD_SLOT_INDEX,
 };
 
 /// A high-performance unsigned integer counter using sharded atomic storage.
@@ -72,7 +74,8 @@ use crate::counters::{
 /// assert_eq!(counter.value(), contatori::counters::CounterValue::Unsigned(4000));
 /// ```
 pub struct Unsigned {
-    name: &'static str,
+    name: Name,
+    meta: Metadata,
     components: [CachePadded<AtomicUsize>; NUM_COMPONENTS],
 }
 
@@ -101,10 +104,12 @@ impl Unsigned {
     /// assert_eq!(counter.value(), contatori::counters::CounterValue::Unsigned(0));
     /// ```
     pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
         const ZERO: CachePadded<AtomicUsize> = CachePadded::new(AtomicUsize::new(0));
         Unsigned {
             components: [ZERO; NUM_COMPONENTS],
-            name: "",
+            name: Name::new(),
+            meta: Metadata::new(),
         }
     }
 
@@ -123,9 +128,22 @@ impl Unsigned {
     /// assert_eq!(counter.name(), "http_requests");
     /// ```
     pub const fn with_name(self, name: &'static str) -> Self {
-        Self { name, ..self }
+        let mut counter = self;
+        counter.name.set_literal(name);
+        counter
     }
 
+    /// Sets a name known only at runtime (e.g. read from a configuration
+    /// file), returning `self` for method chaining.
+    ///
+    /// Use [`with_name()`](Self::with_name) for string literals.
+    pub fn with_owned_name(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.name.set(name.into());
+        self
+    }
+
+    metadata_builders!();
+
     /// Adds a value to the counter.
     ///
     /// This operation is lock-free and extremely fast due to sharding.
@@ -226,9 +244,13 @@ impl Observable for Unsigned {
 
     /// Returns the name of this counter.
     #[inline]
-    fn name(&self) -> &'static str {
-        self.name
+    fn name(&self) -> &str {
+        &self.name
     }
+
+    metadata_accessors!();
+
+    single_entry!();
 }
 
 impl sealed::Resettable for Unsigned {
@@ -241,6 +263,29 @@ impl sealed::Resettable for Unsigned {
     }
 }
 
+impl Aggregate for Unsigned {
+    type Partial = u64;
+
+    const IDENTITY: u64 = 0;
+
+    /// Returns the total value across all shards.
+    #[inline]
+    fn partial(&self) -> u64 {
+        self.total_value() as u64
+    }
+
+    /// Sums the two totals.
+    #[inline]
+    fn merge(a: u64, b: u64) -> u64 {
+        a.wrapping_add(b)
+    }
+
+    #[inline]
+    fn finish(partial: u64) -> CounterValue {
+        CounterValue::Unsigned(partial)
+    }
+}
+
 impl Default for Unsigned {
     /// Creates a new counter initialized to zero with no name.
     fn default() -> Self {
@@ -392,6 +437,31 @@ mod tests {
         assert_eq!(counter.name(), "my_counter");
     }
 
+    #[test]
+    fn test_with_owned_name() {
+        let prefix = "tenant";
+        let counter = Unsigned::new().with_owned_name(format!("{}_requests", prefix));
+        counter.add(3);
+        assert_eq!(counter.name(), "tenant_requests");
+
+        let entries = counter.expand();
+        assert_eq!(entries[0].name, "tenant_requests");
+        assert_eq!(entries[0].value.as_u64(), 3);
+    }
+
+    #[test]
+    fn test_replace_name() {
+        let counter = Unsigned::new()
+            .with_owned_name("runtime".to_string())
+            .with_name("literal");
+        assert_eq!(counter.name(), "literal");
+        assert!(matches!(counter.expand()[0].name, Cow::Borrowed("literal")));
+
+        let counter = counter.with_owned_name("runtime".to_string());
+        assert_eq!(counter.name(), "runtime");
+        assert!(matches!(counter.expand()[0].name, Cow::Owned(_)));
+    }
+
     #[test]
     fn test_with_name_preserves_value() {
         let counter = Unsigned::new().with_name("test");
@@ -407,4 +477,15 @@ mod tests {
         assert_eq!(counter.value(), CounterValue::Unsigned(0));
         assert_eq!(counter.name(), "");
     }
+
+    #[test]
+    fn test_aggregate() {
+        use crate::counters::Aggregate;
+        let a = Unsigned::new();
+        let b = Unsigned::new();
+        a.add(5);
+        b.add(6);
+        b.sub(1);
+        assert_eq!(Unsigned::aggregate([&a, &b]), CounterValue::Unsigned(10));
+    }
 }
@@ -0,0 +1,980 @@
+//! Built-in `/metrics` HTTP endpoint.
+//!
+//! This module provides [`MetricsServer`], a minimal blocking HTTP/1.1 server
+//! on [`std::net::TcpListener`] that exports counters on every request. It
+//! is meant for scraping, not as a general-purpose web server: each
+//! connection is served on a short-lived thread, up to
+//! [`MAX_CONNECTIONS`] at a time, and every response closes it.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `http` feature, which also enables
+//! `exposition`. The JSON output additionally requires the `json` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["http"] }
+//! ```
+//!
+//! # Formats
+//!
+//! The metrics path (`/metrics` by default) serves:
+//!
+//! | Format | Content type | `?format=` |
+//! |--------|--------------|------------|
+//! | Prometheus text 0.0.4 | `text/plain; version=0.0.4` | `prometheus` |
+//! | OpenMetrics 1.0 | `application/openmetrics-text; version=1.0.0` | `openmetrics` |
+//! | JSON (`json` feature) | `application/json` | `json` |
+//! | HTML table | `text/html` | `html` |
+//!
+//! The format is negotiated from the `Accept` header (Prometheus scrapers
+//! get OpenMetrics, browsers the HTML table, anything else the text
+//! format), unless the `format` query parameter selects one. Responses are
+//! gzip-compressed when the client accepts it.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::http::MetricsServer;
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("requests");
+//!
+//! let server = MetricsServer::bind("0.0.0.0:9100", || {
+//!     vec![&REQUESTS as &'static dyn Observable]
+//! })?;
+//! let handle = server.spawn()?;
+//! // ... the endpoint is served until `handle` is dropped
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use crate::counters::{MetricKind, Observable};
+use crate::observers::exposition::{ExpositionObserver, Format};
+use crate::observers::Result;
+#[cfg(feature = "registry")]
+use crate::registry::Registry;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Maximum size of a request head (request line and headers).
+const MAX_HEAD: usize = 8 * 1024;
+
+/// Maximum number of connections served at the same time.
+///
+/// Further connections are closed without a response until one finishes.
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// Provides the counters exported on each request.
+///
+/// Implemented for closures returning the counters and, with the `registry`
+/// feature, for `&'static` [`Registry`].
+pub trait Source: Send + Sync + 'static {
+    /// Returns the counters to export.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the counters cannot be collected; the request is
+    /// answered with `500 Internal Server Error`.
+    fn observables(&self) -> Result<Vec<&'static dyn Observable>>;
+}
+
+impl<F> Source for F
+where
+    F: Fn() -> Vec<&'static dyn Observable> + Send + Sync + 'static,
+{
+    fn observables(&self) -> Result<Vec<&'static dyn Observable>> {
+        Ok(self())
+    }
+}
+
+#[cfg(feature = "registry")]
+impl Source for &'static Registry {
+    fn observables(&self) -> Result<Vec<&'static dyn Observable>> {
+        Ok(self.iter()?.collect())
+    }
+}
+
+/// Output format of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResponseFormat {
+    Prometheus,
+    OpenMetrics,
+    #[cfg(feature = "json")]
+    Json,
+    Html,
+}
+
+impl ResponseFormat {
+    /// All formats, in order of preference when the client has none.
+    const ALL: &'static [ResponseFormat] = &[
+        ResponseFormat::Prometheus,
+        ResponseFormat::OpenMetrics,
+        #[cfg(feature = "json")]
+        ResponseFormat::Json,
+        ResponseFormat::Html,
+    ];
+
+    /// Parses a `format` query parameter.
+    fn from_query(name: &str) -> Option<Self> {
+        match name {
+            "prometheus" | "text" => Some(ResponseFormat::Prometheus),
+            "openmetrics" => Some(ResponseFormat::OpenMetrics),
+            #[cfg(feature = "json")]
+            "json" => Some(ResponseFormat::Json),
+            "html" => Some(ResponseFormat::Html),
+            _ => None,
+        }
+    }
+
+    /// Returns the media type matched against `Accept`.
+    fn media_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Prometheus => "text/plain",
+            ResponseFormat::OpenMetrics => "application/openmetrics-text",
+            #[cfg(feature = "json")]
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Html => "text/html",
+        }
+    }
+
+    /// Returns the `Content-Type` of responses in this format.
+    fn content_type(&self) -> &'static str {
+        match self {
+            ResponseFormat::Prometheus => Format::Prometheus.content_type(),
+            ResponseFormat::OpenMetrics => Format::OpenMetrics.content_type(),
+            #[cfg(feature = "json")]
+            ResponseFormat::Json => "application/json",
+            ResponseFormat::Html => "text/html; charset=utf-8",
+        }
+    }
+
+    /// Picks the format preferred by an `Accept` header.
+    ///
+    /// Each format gets the quality of the most specific media range
+    /// matching it; the highest quality wins, ties going to the earliest
+    /// format in [`ALL`](Self::ALL). Returns `None` if no format is
+    /// acceptable.
+    fn negotiate(accept: Option<&str>) -> Option<Self> {
+        let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+            return Some(ResponseFormat::Prometheus);
+        };
+        let ranges: Vec<(&str, f32)> = accept.split(',').filter_map(parse_media_range).collect();
+
+        let mut best: Option<(ResponseFormat, f32)> = None;
+        for &format in Self::ALL {
+            let media_type = format.media_type();
+            let (kind, _) = media_type.split_once('/').unwrap_or((media_type, ""));
+            // (specificity, quality) of the best matching range
+            let quality = ranges
+                .iter()
+                .filter_map(|&(range, q)| match range.split_once('/') {
+                    _ if range.eq_ignore_ascii_case(media_type) => Some((2, q)),
+                    Some((k, "*")) if k.eq_ignore_ascii_case(kind) => Some((1, q)),
+                    Some(("*", "*")) => Some((0, q)),
+                    _ => None,
+                })
+                .max_by(|a, b| a.0.cmp(&b.0))
+                .map_or(0.0, |(_, q)| q);
+            if quality > 0.0 && best.is_none_or(|(_, q)| quality > q) {
+                best = Some((format, quality));
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+}
+
+/// Parses one element of an `Accept` or `Accept-Encoding` header into its
+/// value and quality.
+fn parse_media_range(range: &str) -> Option<(&str, f32)> {
+    let mut parts = range.split(';');
+    let value = parts.next()?.trim();
+    if value.is_empty() {
+        return None;
+    }
+    let quality = parts
+        .filter_map(|param| param.trim().strip_prefix("q="))
+        .find_map(|q| q.trim().parse::<f32>().ok())
+        .unwrap_or(1.0);
+    Some((value, quality))
+}
+
+/// Returns `true` if an `Accept-Encoding` header accepts gzip.
+fn accepts_gzip(accept_encoding: Option<&str>) -> bool {
+    accept_encoding
+        .into_iter()
+        .flat_map(|header| header.split(','))
+        .filter_map(parse_media_range)
+        .any(|(coding, q)| (coding.eq_ignore_ascii_case("gzip") || coding == "*") && q > 0.0)
+}
+
+/// A parsed request head.
+struct Request {
+    method: String,
+    path: String,
+    query: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// Returns the value of a header, matched case-insensitively.
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the value of a query parameter.
+    fn query_param(&self, name: &str) -> Option<&str> {
+        self.query
+            .as_deref()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| v)
+    }
+
+    /// Reads and parses a request head from `stream`.
+    ///
+    /// Returns `Ok(None)` for malformed or oversized requests.
+    fn read(stream: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut head = Vec::with_capacity(1024);
+        let mut buf = [0u8; 1024];
+        let end = loop {
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            head.extend_from_slice(&buf[..n]);
+            if let Some(pos) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos;
+            }
+            if head.len() > MAX_HEAD {
+                return Ok(None);
+            }
+        };
+        let Ok(head) = std::str::from_utf8(&head[..end]) else {
+            return Ok(None);
+        };
+
+        let mut lines = head.split("\r\n");
+        let mut request_line = lines.next().unwrap_or("").split(' ');
+        let (Some(method), Some(target), Some(version)) = (
+            request_line.next(),
+            request_line.next(),
+            request_line.next(),
+        ) else {
+            return Ok(None);
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Ok(None);
+        }
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        let mut headers = Vec::new();
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                return Ok(None);
+            };
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+        Ok(Some(Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query,
+            headers,
+        }))
+    }
+}
+
+/// A response about to be written.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    headers: Vec<(&'static str, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Response {
+            status,
+            content_type,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    /// A plain text error response.
+    fn error(status: &'static str, message: impl std::fmt::Display) -> Self {
+        Self::new(
+            status,
+            "text/plain; charset=utf-8",
+            format!("{}\n", message),
+        )
+    }
+
+    /// Writes the response, without body for `HEAD` requests.
+    fn write(&self, stream: &mut impl Write, head_only: bool) -> io::Result<()> {
+        let mut head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            let _ = write!(head, "{}: {}\r\n", name, value);
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        if !head_only {
+            stream.write_all(&self.body)?;
+        }
+        stream.flush()
+    }
+}
+
+/// Settings shared with the serving thread.
+struct Settings {
+    source: Box<dyn Source>,
+    observer: ExpositionObserver,
+    path: String,
+    gzip: bool,
+    timeout: Duration,
+}
+
+/// A minimal blocking HTTP server exporting counters.
+///
+/// Created with [`bind()`](Self::bind) and configured with the `with_*`
+/// builders, then run with [`serve()`](Self::serve) on the current thread or
+/// [`spawn()`](Self::spawn) on a background thread.
+///
+/// # Examples
+///
+/// Serving the global registry (`registry` feature) with a namespace:
+///
+/// ```rust,ignore
+/// use contatori::http::MetricsServer;
+/// use contatori::observers::exposition::ExpositionObserver;
+/// use contatori::registry::Registry;
+///
+/// let handle = MetricsServer::bind("127.0.0.1:9100", Registry::global())?
+///     .with_observer(ExpositionObserver::new().with_namespace("myapp"))
+///     .spawn()?;
+/// ```
+pub struct MetricsServer {
+    listener: TcpListener,
+    settings: Settings,
+}
+
+impl MetricsServer {
+    /// Binds the server to `addr`, exporting the counters of `source`.
+    ///
+    /// Bind to port 0 to let the OS pick a free port, then read it with
+    /// [`local_addr()`](Self::local_addr).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be bound.
+    pub fn bind(addr: impl ToSocketAddrs, source: impl Source) -> Result<Self> {
+        Ok(MetricsServer {
+            listener: TcpListener::bind(addr)?,
+            settings: Settings {
+                source: Box::new(source),
+                observer: ExpositionObserver::new(),
+                path: "/metrics".to_string(),
+                gzip: true,
+                timeout: Duration::from_secs(10),
+            },
+        })
+    }
+
+    /// Sets the observer used for the Prometheus and OpenMetrics formats
+    /// (namespace, constant labels, metric configurations...).
+    ///
+    /// Its format is chosen per request.
+    pub fn with_observer(mut self, observer: ExpositionObserver) -> Self {
+        self.settings.observer = observer;
+        self
+    }
+
+    /// Sets the path of the metrics endpoint (default: `/metrics`).
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.settings.path = path.to_string();
+        self
+    }
+
+    /// Enables or disables gzip compression (default: enabled).
+    pub fn with_gzip(mut self, enabled: bool) -> Self {
+        self.settings.gzip = enabled;
+        self
+    }
+
+    /// Sets the read and write timeout of connections (default: 10s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.settings.timeout = timeout;
+        self
+    }
+
+    /// Returns the address the server is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the address cannot be read from the socket.
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serves requests on the current thread, forever.
+    ///
+    /// Errors on individual connections are ignored.
+    pub fn serve(self) {
+        self.run(&AtomicBool::new(false));
+    }
+
+    /// Serves requests on a background thread until the returned handle is
+    /// shut down or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the thread cannot be spawned.
+    pub fn spawn(self) -> Result<ServerHandle> {
+        let addr = self.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&shutdown);
+        let thread = thread::Builder::new()
+            .name("contatori-http".to_string())
+            .spawn(move || self.run(&flag))?;
+        Ok(ServerHandle {
+            addr,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    /// Accepts connections until `shutdown` is set, serving each one on its
+    /// own thread so that a slow client does not hold up the others.
+    fn run(self, shutdown: &AtomicBool) {
+        let settings = Arc::new(self.settings);
+        let active = Arc::new(AtomicUsize::new(0));
+        for stream in self.listener.incoming() {
+            if shutdown.load(Ordering::Acquire) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            if active.fetch_add(1, Ordering::AcqRel) >= MAX_CONNECTIONS {
+                active.fetch_sub(1, Ordering::AcqRel);
+                continue;
+            }
+            let connection = Connection(Arc::clone(&active));
+            let settings = Arc::clone(&settings);
+            // If the thread cannot be spawned, the connection is dropped
+            let _ = thread::Builder::new()
+                .name("contatori-http-conn".to_string())
+                .spawn(move || {
+                    let _connection = connection;
+                    let _ = settings.handle(stream);
+                });
+        }
+    }
+}
+
+/// A connection being served, counted in the active connections until it is
+/// dropped.
+struct Connection(Arc<AtomicUsize>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl Settings {
+    /// Answers one request.
+    fn handle(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let Some(request) = Request::read(&mut stream)? else {
+            return Response::error("400 Bad Request", "bad request").write(&mut stream, false);
+        };
+        let head_only = request.method == "HEAD";
+        let response = self.respond(&request);
+        response.write(&mut stream, head_only)
+    }
+
+    /// Builds the response to a request.
+    fn respond(&self, request: &Request) -> Response {
+        if request.path != self.path {
+            return Response::error("404 Not Found", "not found");
+        }
+        if request.method != "GET" && request.method != "HEAD" {
+            let mut response = Response::error("405 Method Not Allowed", "method not allowed");
+            response.headers.push(("Allow", "GET, HEAD".to_string()));
+            return response;
+        }
+
+        let format = match request.query_param("format") {
+            Some(name) => match ResponseFormat::from_query(name) {
+                Some(format) => format,
+                None => {
+                    return Response::error(
+                        "400 Bad Request",
+                        format_args!("unknown format: {}", name),
+                    )
+                }
+            },
+            None => match ResponseFormat::negotiate(request.header("Accept")) {
+                Some(format) => format,
+                None => return Response::error("406 Not Acceptable", "no acceptable format"),
+            },
+        };
+
+        let body = match self.render(format) {
+            Ok(body) => body,
+            Err(e) => return Response::error("500 Internal Server Error", e),
+        };
+        let mut response = Response::new("200 OK", format.content_type(), body);
+        response
+            .headers
+            .push(("Vary", "Accept, Accept-Encoding".to_string()));
+        if self.gzip && accepts_gzip(request.header("Accept-Encoding")) {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            let compressed = encoder
+                .write_all(&response.body)
+                .and_then(|()| encoder.finish());
+            if let Ok(compressed) = compressed {
+                response.body = compressed;
+                response
+                    .headers
+                    .push(("Content-Encoding", "gzip".to_string()));
+            }
+        }
+        response
+    }
+
+    /// Renders the counters of the source in `format`.
+    fn render(&self, format: ResponseFormat) -> Result<Vec<u8>> {
+        let counters = self.source.observables()?;
+        let counters = counters.iter().copied();
+        match format {
+            ResponseFormat::Prometheus | ResponseFormat::OpenMetrics => {
+                let format = match format {
+                    ResponseFormat::OpenMetrics => Format::OpenMetrics,
+                    _ => Format::Prometheus,
+                };
+                self.observer
+                    .clone()
+                    .with_format(format)
+                    .render_bytes(counters)
+            }
+            #[cfg(feature = "json")]
+            ResponseFormat::Json => {
+                Ok(crate::observers::json::JsonObserver::new().to_json_bytes(counters)?)
+            }
+            ResponseFormat::Html => Ok(render_html(counters).into_bytes()),
+        }
+    }
+}
+
+/// Renders counters as an HTML page with one table row per entry.
+fn render_html<'a>(counters: impl Iterator<Item = &'a dyn Observable>) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Metrics</title></head>\n\
+         <body>\n<table>\n<tr><th>Name</th><th>Labels</th><th>Value</th><th>Type</th>\
+         <th>Unit</th><th>Help</th></tr>\n",
+    );
+    for counter in counters {
+        let unit = counter.unit().map(|u| u.as_str()).unwrap_or("");
+        let help = counter.description().unwrap_or("");
+        for entry in counter.expand() {
+            let labels = entry
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let kind = match entry.metric_kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+                MetricKind::Histogram => "histogram",
+                MetricKind::Summary => "summary",
+            };
+            html.push_str("<tr>");
+            for cell in [
+                entry.name.as_ref(),
+                &labels,
+                &entry.value.to_string(),
+                kind,
+                unit,
+                help,
+            ] {
+                html.push_str("<td>");
+                push_escaped(&mut html, cell);
+                html.push_str("</td>");
+            }
+            html.push_str("</tr>\n");
+        }
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Appends `text` with HTML special characters escaped.
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+/// Handle to a server running on a background thread.
+///
+/// Dropping the handle shuts the server down, like
+/// [`shutdown()`](Self::shutdown).
+#[derive(Debug)]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Returns the address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops the server and waits for its thread to exit.
+    ///
+    /// The accept loop is woken up by connecting to the server. If that
+    /// connection fails (e.g. the address is filtered), the thread is not
+    /// joined: it exits on the next incoming connection instead. Connections
+    /// being served finish on their own threads.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        self.shutdown.store(true, Ordering::Release);
+        // Wake up the blocking accept; an unspecified address is reached
+        // through the loopback interface
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr.ip() {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            });
+        }
+        if TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+    use flate2::read::GzDecoder;
+
+    static REQUESTS: Monotone = Monotone::new()
+        .with_name("requests")
+        .with_help("Total <requests>");
+    static QUEUE: Unsigned = Unsigned::new().with_name("queue");
+
+    fn counters() -> Vec<&'static dyn Observable> {
+        vec![&REQUESTS, &QUEUE]
+    }
+
+    /// A response as seen by the client.
+    struct Reply {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl Reply {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+
+        fn text(&self) -> String {
+            String::from_utf8(self.body.clone()).unwrap()
+        }
+    }
+
+    /// Sends a raw request and reads the whole response.
+    fn send(addr: SocketAddr, request: &str) -> Reply {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+
+        let end = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = std::str::from_utf8(&raw[..end]).unwrap();
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap().split(' ').nth(1).unwrap();
+        let headers = lines
+            .map(|line| {
+                let (k, v) = line.split_once(": ").unwrap();
+                (k.to_string(), v.to_string())
+            })
+            .collect();
+        Reply {
+            status: status.parse().unwrap(),
+            headers,
+            body: raw[end + 4..].to_vec(),
+        }
+    }
+
+    fn get(addr: SocketAddr, target: &str, headers: &[(&str, &str)]) -> Reply {
+        let mut request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n", target);
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        send(addr, &request)
+    }
+
+    fn spawn() -> ServerHandle {
+        MetricsServer::bind("127.0.0.1:0", counters)
+            .unwrap()
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_prometheus_by_default() {
+        REQUESTS.add(1);
+        let server = spawn();
+        let reply = get(server.local_addr(), "/metrics", &[]);
+        assert_eq!(reply.status, 200);
+        assert_eq!(
+            reply.header("Content-Type"),
+            Some("text/plain; version=0.0.4; charset=utf-8")
+        );
+        assert_eq!(
+            reply.header("Content-Length"),
+            Some(reply.body.len().to_string().as_str())
+        );
+        assert!(reply.text().contains("# TYPE requests counter\n"));
+        assert!(reply.text().contains("# TYPE queue gauge\n"));
+    }
+
+    #[test]
+    fn test_openmetrics_for_prometheus_scrapers() {
+        let server = spawn();
+        let accept =
+            "application/openmetrics-text;version=1.0.0,text/plain;version=0.0.4;q=0.5,*/*;q=0.1";
+        let reply = get(server.local_addr(), "/metrics", &[("Accept", accept)]);
+        assert_eq!(reply.status, 200);
+        assert_eq!(
+            reply.header("Content-Type"),
+            Some(Format::OpenMetrics.content_type())
+        );
+        assert!(reply.text().contains("requests_total "));
+        assert!(reply.text().ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_html_for_browsers() {
+        let server = spawn();
+        let accept = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        let reply = get(server.local_addr(), "/metrics", &[("Accept", accept)]);
+        assert_eq!(
+            reply.header("Content-Type"),
+            Some("text/html; charset=utf-8")
+        );
+        let html = reply.text();
+        assert!(html.contains("<td>requests</td>"));
+        assert!(html.contains("<td>Total &lt;requests&gt;</td>"));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let server = spawn();
+        let reply = get(
+            server.local_addr(),
+            "/metrics",
+            &[("Accept", "application/json")],
+        );
+        assert_eq!(reply.header("Content-Type"), Some("application/json"));
+        let json: serde_json::Value = serde_json::from_slice(&reply.body).unwrap();
+        assert_eq!(json[1]["name"], "queue");
+    }
+
+    #[test]
+    fn test_format_query_overrides_accept() {
+        let server = spawn();
+        let reply = get(
+            server.local_addr(),
+            "/metrics?format=openmetrics",
+            &[("Accept", "text/html")],
+        );
+        assert!(reply.text().ends_with("# EOF\n"));
+
+        let reply = get(server.local_addr(), "/metrics?format=xml", &[]);
+        assert_eq!(reply.status, 400);
+    }
+
+    #[test]
+    fn test_gzip() {
+        let server = spawn();
+        let reply = get(
+            server.local_addr(),
+            "/metrics",
+            &[("Accept-Encoding", "deflate, gzip;q=0.8")],
+        );
+        assert_eq!(reply.header("Content-Encoding"), Some("gzip"));
+        let mut text = String::new();
+        GzDecoder::new(&reply.body[..])
+            .read_to_string(&mut text)
+            .unwrap();
+        assert!(text.contains("# TYPE requests counter\n"));
+
+        // Refused or disabled gzip
+        let reply = get(
+            server.local_addr(),
+            "/metrics",
+            &[("Accept-Encoding", "gzip;q=0")],
+        );
+        assert_eq!(reply.header("Content-Encoding"), None);
+
+        let server = MetricsServer::bind("127.0.0.1:0", counters)
+            .unwrap()
+            .with_gzip(false)
+            .spawn()
+            .unwrap();
+        let reply = get(
+            server.local_addr(),
+            "/metrics",
+            &[("Accept-Encoding", "gzip")],
+        );
+        assert_eq!(reply.header("Content-Encoding"), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = spawn();
+        let addr = server.local_addr();
+        assert_eq!(get(addr, "/", &[]).status, 404);
+        assert_eq!(
+            send(addr, "POST /metrics HTTP/1.1\r\nContent-Length: 0\r\n\r\n").status,
+            405
+        );
+        assert_eq!(send(addr, "garbage\r\n\r\n").status, 400);
+        assert_eq!(
+            get(addr, "/metrics", &[("Accept", "image/png")]).status,
+            406
+        );
+    }
+
+    #[test]
+    fn test_head() {
+        let server = spawn();
+        let reply = send(server.local_addr(), "HEAD /metrics HTTP/1.1\r\n\r\n");
+        assert_eq!(reply.status, 200);
+        assert!(reply.body.is_empty());
+        assert_ne!(reply.header("Content-Length"), Some("0"));
+    }
+
+    #[test]
+    fn test_custom_path_and_observer() {
+        let server = MetricsServer::bind("127.0.0.1:0", counters)
+            .unwrap()
+            .with_path("/internal/metrics")
+            .with_observer(ExpositionObserver::new().with_namespace("app"))
+            .spawn()
+            .unwrap();
+        let reply = get(server.local_addr(), "/internal/metrics", &[]);
+        assert!(reply.text().contains("# TYPE app_queue gauge\n"));
+        assert_eq!(get(server.local_addr(), "/metrics", &[]).status, 404);
+    }
+
+    #[cfg(feature = "registry")]
+    #[test]
+    fn test_registry_source() {
+        static REGISTRY: Registry = Registry::new();
+        static HITS: Monotone = Monotone::new().with_name("hits");
+        REGISTRY.register(&HITS).unwrap();
+
+        let server = MetricsServer::bind("127.0.0.1:0", &REGISTRY)
+            .unwrap()
+            .spawn()
+            .unwrap();
+        let reply = get(server.local_addr(), "/metrics", &[]);
+        assert!(reply.text().contains("hits 0\n"));
+    }
+
+    #[test]
+    fn test_slow_client_does_not_block() {
+        let server = spawn();
+        // Connected but never sends its request
+        let _idle = TcpStream::connect(server.local_addr()).unwrap();
+        let reply = get(server.local_addr(), "/metrics", &[]);
+        assert_eq!(reply.status, 200);
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let server = spawn();
+        let addr = server.local_addr();
+        server.shutdown();
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        use ResponseFormat::*;
+
+        assert_eq!(ResponseFormat::negotiate(None), Some(Prometheus));
+        assert_eq!(ResponseFormat::negotiate(Some("*/*")), Some(Prometheus));
+        assert_eq!(ResponseFormat::negotiate(Some("text/*")), Some(Prometheus));
+        assert_eq!(
+            ResponseFormat::negotiate(Some("text/*, text/plain;q=0")),
+            Some(Html)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("application/openmetrics-text; version=1.0.0")),
+            Some(OpenMetrics)
+        );
+        assert_eq!(
+            ResponseFormat::negotiate(Some("text/plain;q=0.2, text/html;q=0.3")),
+            Some(Html)
+        );
+        assert_eq!(ResponseFormat::negotiate(Some("image/png")), None);
+    }
+}
@@ -75,6 +75,7 @@
 //! | [`Minimum`](counters::minimum::Minimum) | Tracks minimum observed value | Latency minimums |
 //! | [`Maximum`](counters::maximum::Maximum) | Tracks maximum observed value | Latency maximums, peak values |
 //! | [`Average`](counters::average::Average) | Computes running average | Average latency, mean values |
+//! | [`Histogram`](counters::histogram::Histogram) | Counts observations in fixed buckets | Latency and size distributions |
 //!
 //! ## Quick Start
 //!
@@ -244,9 +245,23 @@
 //! // Counters are now exported via OpenTelemetry
 //! ```
 
+// Lets the code generated by `contatori-derive` refer to `::contatori` from
+// within this crate too.
+extern crate self as contatori;
+
 pub mod adapters;
 pub mod counters;
+pub mod metrics;
 pub mod observers;
 
+#[cfg(feature = "registry")]
+pub mod registry;
+
+#[cfg(feature = "http")]
+pub mod http;
+
 #[cfg(feature = "serde")]
 pub mod snapshot;
+
+#[cfg(test)]
+mod testing;
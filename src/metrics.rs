@@ -0,0 +1,522 @@
+//! Structs of counters exported as a whole.
+//!
+//! This module provides the [`Metrics`] trait, implemented by structs that
+//! group many counters, and [`MetricSet`], the list of their fields as
+//! [`Observable`]s. A `MetricSet` can be handed directly to any observer,
+//! removing the need for hand-maintained `&dyn Observable` lists.
+//!
+//! # Derive
+//!
+//! With the `derive` feature enabled, `#[derive(Metrics)]` implements the
+//! trait for a struct with named fields:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["derive"] }
+//! ```
+//!
+//! ```rust,ignore
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::signed::Signed;
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::metrics::Metrics;
+//! use contatori::observers::prometheus::PrometheusObserver;
+//!
+//! #[derive(Metrics)]
+//! struct DbMetrics {
+//!     queries: Monotone,
+//! }
+//!
+//! #[derive(Metrics)]
+//! #[metrics(prefix = "http_")]
+//! struct HttpMetrics {
+//!     #[metric(help = "Total requests served", unit = "requests")]
+//!     requests: Monotone,
+//!     #[metric(name = "in_flight")]
+//!     active: Signed,
+//!     #[metric(skip)]
+//!     scratch: Unsigned,
+//!     #[metric(nested, prefix = "db_")]
+//!     db: DbMetrics,
+//! }
+//!
+//! static METRICS: HttpMetrics = HttpMetrics {
+//!     requests: Monotone::new(),
+//!     active: Signed::new(),
+//!     scratch: Unsigned::new(),
+//!     db: DbMetrics { queries: Monotone::new() },
+//! };
+//!
+//! // http_requests, http_in_flight, http_db_queries
+//! let output = PrometheusObserver::new().render(METRICS.metrics().iter())?;
+//! ```
+//!
+//! # Attributes
+//!
+//! | Attribute | Level | Effect |
+//! |-----------|-------|--------|
+//! | `#[metrics(prefix = "...")]` | struct | Prepended to the name of every field |
+//! | `#[metric(name = "...")]` | field | Metric name (defaults to the field name) |
+//! | `#[metric(prefix = "...")]` | field | Prepended to the name of this field, or of every metric of a nested field |
+//! | `#[metric(help = "...")]` | field | [`description()`](Observable::description) of the metric |
+//! | `#[metric(unit = "...")]` | field | [`unit()`](Observable::unit) of the metric, see [`Unit::from_name`] |
+//! | `#[metric(stability = "...")]` | field | `"stable"`, `"experimental"` or `"deprecated"` |
+//! | `#[metric(skip)]` | field | Leaves the field out |
+//! | `#[metric(nested)]` | field | Inlines the metrics of a field that implements [`Metrics`] |
+//!
+//! The name set on a counter with `with_name()` is ignored: the struct
+//! decides how its fields are exported. The metrics of a nested struct are
+//! named by that struct, then prefixed with the prefixes of the enclosing
+//! struct and field, so several instances of the same struct can be told
+//! apart.
+
+use crate::counters::{
+    CounterValue, Distribution, MetricKind, Observable, ObservableEntry, Stability, Unit,
+};
+use std::borrow::Cow;
+use std::fmt::{self, Debug};
+
+#[cfg(feature = "derive")]
+pub use contatori_derive::Metrics;
+
+/// A struct whose fields are exported as metrics.
+///
+/// Usually implemented with `#[derive(Metrics)]` (see the
+/// [module documentation](self)), but it can be implemented by hand:
+///
+/// ```rust
+/// use contatori::counters::unsigned::Unsigned;
+/// use contatori::metrics::{Metric, MetricSet, Metrics};
+///
+/// struct Cache {
+///     hits: Unsigned,
+///     misses: Unsigned,
+/// }
+///
+/// impl Metrics for Cache {
+///     fn collect_metrics<'a>(&'a self, set: &mut MetricSet<'a>) {
+///         set.push(Metric::new(&self.hits, "cache_hits"));
+///         set.push(Metric::new(&self.misses, "cache_misses"));
+///     }
+/// }
+///
+/// let cache = Cache { hits: Unsigned::new(), misses: Unsigned::new() };
+/// cache.hits.add(3);
+///
+/// let metrics = cache.metrics();
+/// let names: Vec<_> = metrics.iter().map(|m| m.name()).collect();
+/// assert_eq!(names, ["cache_hits", "cache_misses"]);
+/// ```
+pub trait Metrics {
+    /// Appends every metric of `self` to `set`, in declaration order.
+    fn collect_metrics<'a>(&'a self, set: &mut MetricSet<'a>);
+
+    /// Returns every metric of `self`, in declaration order.
+    fn metrics(&self) -> MetricSet<'_> {
+        let mut set = MetricSet::new();
+        self.collect_metrics(&mut set);
+        set
+    }
+}
+
+/// A counter exported under the name chosen by a [`Metrics`] struct.
+///
+/// Values are read from the wrapped counter, so [`Resettable`](crate::adapters::Resettable)
+/// counters keep resetting on observation. Every entry produced by
+/// [`expand()`](Observable::expand) is renamed, labels are kept.
+pub struct Metric<'a> {
+    inner: &'a dyn Observable,
+    name: Cow<'static, str>,
+    help: Option<&'static str>,
+    unit: Option<Unit>,
+    stability: Option<Stability>,
+}
+
+impl<'a> Metric<'a> {
+    /// Wraps `inner` so that it is exported as `name`.
+    pub const fn new(inner: &'a dyn Observable, name: &'static str) -> Self {
+        Self {
+            inner,
+            name: Cow::Borrowed(name),
+            help: None,
+            unit: None,
+            stability: None,
+        }
+    }
+
+    /// Sets the description of this metric, returning `self` for method chaining.
+    pub const fn with_help(self, help: &'static str) -> Self {
+        let mut metric = self;
+        metric.help = Some(help);
+        metric
+    }
+
+    /// Sets the unit of this metric, returning `self` for method chaining.
+    pub const fn with_unit(self, unit: Unit) -> Self {
+        let mut metric = self;
+        metric.unit = Some(unit);
+        metric
+    }
+
+    /// Sets the stability of this metric, returning `self` for method chaining.
+    pub const fn with_stability(self, stability: Stability) -> Self {
+        let mut metric = self;
+        metric.stability = Some(stability);
+        metric
+    }
+
+    /// Returns the wrapped counter.
+    pub fn inner(&self) -> &'a dyn Observable {
+        self.inner
+    }
+}
+
+impl Observable for Metric<'_> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metric_kind(&self) -> MetricKind {
+        self.inner.metric_kind()
+    }
+
+    /// Returns the help set on this metric, or the wrapped counter's description.
+    fn description(&self) -> Option<&'static str> {
+        self.help.or_else(|| self.inner.description())
+    }
+
+    /// Returns the unit set on this metric, or the wrapped counter's unit.
+    fn unit(&self) -> Option<Unit> {
+        self.unit.or_else(|| self.inner.unit())
+    }
+
+    /// Returns the stability set on this metric, or the wrapped counter's stability.
+    fn stability(&self) -> Stability {
+        self.stability.unwrap_or_else(|| self.inner.stability())
+    }
+
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
+    fn value(&self) -> CounterValue {
+        self.inner.value()
+    }
+
+    fn distribution(&self) -> Option<Distribution> {
+        self.inner.distribution()
+    }
+
+    fn expand(&self) -> Vec<ObservableEntry> {
+        let mut entries = self.inner.expand();
+        for entry in &mut entries {
+            entry.name = self.name.clone();
+        }
+        entries
+    }
+}
+
+impl Debug for Metric<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metric")
+            .field("name", &self.name)
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// The metrics of a [`Metrics`] struct, in declaration order.
+///
+/// Use [`iter()`](Self::iter) to pass them to an observer.
+#[derive(Debug, Default)]
+pub struct MetricSet<'a> {
+    metrics: Vec<Metric<'a>>,
+    prefix: String,
+}
+
+impl<'a> MetricSet<'a> {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self {
+            metrics: Vec::new(),
+            prefix: String::new(),
+        }
+    }
+
+    /// Appends a metric, prefixing its name with the current prefix (see
+    /// [`with_prefix()`](Self::with_prefix)).
+    pub fn push(&mut self, mut metric: Metric<'a>) {
+        if !self.prefix.is_empty() {
+            metric.name = Cow::Owned(format!("{}{}", self.prefix, metric.name));
+        }
+        self.metrics.push(metric);
+    }
+
+    /// Calls `collect` with `prefix` appended to the current prefix, so that
+    /// the metrics it pushes are namespaced.
+    ///
+    /// This is how nested [`Metrics`] structs are collected:
+    ///
+    /// ```rust
+    /// use contatori::counters::unsigned::Unsigned;
+    /// use contatori::metrics::{Metric, MetricSet, Metrics};
+    ///
+    /// struct Pool {
+    ///     idle: Unsigned,
+    /// }
+    ///
+    /// impl Metrics for Pool {
+    ///     fn collect_metrics<'a>(&'a self, set: &mut MetricSet<'a>) {
+    ///         set.push(Metric::new(&self.idle, "idle"));
+    ///     }
+    /// }
+    ///
+    /// struct Db {
+    ///     primary: Pool,
+    ///     replica: Pool,
+    /// }
+    ///
+    /// impl Metrics for Db {
+    ///     fn collect_metrics<'a>(&'a self, set: &mut MetricSet<'a>) {
+    ///         set.with_prefix("db_primary_", |set| self.primary.collect_metrics(set));
+    ///         set.with_prefix("db_replica_", |set| self.replica.collect_metrics(set));
+    ///     }
+    /// }
+    ///
+    /// let db = Db {
+    ///     primary: Pool { idle: Unsigned::new() },
+    ///     replica: Pool { idle: Unsigned::new() },
+    /// };
+    /// let metrics = db.metrics();
+    /// let names: Vec<_> = metrics.iter().map(|m| m.name()).collect();
+    /// assert_eq!(names, ["db_primary_idle", "db_replica_idle"]);
+    /// ```
+    pub fn with_prefix(&mut self, prefix: &str, collect: impl FnOnce(&mut Self)) {
+        let len = self.prefix.len();
+        self.prefix.push_str(prefix);
+        collect(self);
+        self.prefix.truncate(len);
+    }
+
+    /// Returns the number of metrics.
+    pub fn len(&self) -> usize {
+        self.metrics.len()
+    }
+
+    /// Returns `true` if there are no metrics.
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+
+    /// Iterates over the metrics as [`Observable`]s.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Observable> {
+        self.metrics.iter().map(|metric| metric as &dyn Observable)
+    }
+}
+
+impl<'s> IntoIterator for &'s MetricSet<'_> {
+    type Item = &'s dyn Observable;
+    type IntoIter = Box<dyn Iterator<Item = &'s dyn Observable> + 's>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Resettable;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+
+    struct Manual {
+        hits: Unsigned,
+        misses: Resettable<Unsigned>,
+    }
+
+    impl Metrics for Manual {
+        fn collect_metrics<'a>(&'a self, set: &mut MetricSet<'a>) {
+            set.push(Metric::new(&self.hits, "cache_hits").with_help("Cache hits"));
+            set.push(Metric::new(&self.misses, "cache_misses").with_unit(Unit::Custom("misses")));
+        }
+    }
+
+    #[test]
+    fn test_manual_impl() {
+        let cache = Manual {
+            hits: Unsigned::new().with_name("ignored"),
+            misses: Resettable::new(Unsigned::new()),
+        };
+        cache.hits.add(3);
+        cache.misses.add(1);
+
+        let metrics = cache.metrics();
+        assert_eq!(metrics.len(), 2);
+
+        let observed: Vec<_> = metrics.iter().collect();
+        assert_eq!(observed[0].name(), "cache_hits");
+        assert_eq!(observed[0].description(), Some("Cache hits"));
+        assert_eq!(observed[0].unit(), None);
+        assert_eq!(observed[0].value().as_u64(), 3);
+        assert_eq!(observed[1].name(), "cache_misses");
+        assert_eq!(observed[1].unit(), Some(Unit::Custom("misses")));
+
+        // Values are read through, so resettable counters still reset
+        assert_eq!(observed[1].value().as_u64(), 1);
+        assert_eq!(observed[1].value().as_u64(), 0);
+    }
+
+    #[test]
+    fn test_metric_expand_renames_entries() {
+        crate::labeled_group!(
+            Requests,
+            "original_name",
+            "method",
+            total: Monotone,
+            get: "GET",
+            post: "POST",
+        );
+
+        let requests = Requests::new();
+        requests.get.add(2);
+
+        let metric = Metric::new(&requests, "renamed");
+        let entries = metric.expand();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|entry| entry.name == "renamed"));
+        assert_eq!(entries[1].labels[0].1, "GET");
+        assert_eq!(metric.metric_kind(), MetricKind::Counter);
+    }
+
+    #[test]
+    fn test_metric_set_into_iter() {
+        let set = MetricSet::new();
+        assert!(set.is_empty());
+        assert_eq!((&set).into_iter().count(), 0);
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use crate::counters::monotone::Monotone;
+        use crate::counters::signed::Signed;
+        use crate::counters::unsigned::Unsigned;
+        use crate::counters::{Observable, Stability, Unit};
+        use crate::metrics::Metrics;
+
+        #[derive(Metrics)]
+        #[metrics(prefix = "db_")]
+        struct DbMetrics {
+            #[metric(help = "Queries executed")]
+            queries: Monotone,
+        }
+
+        #[derive(Metrics)]
+        #[metrics(prefix = "http_")]
+        struct HttpMetrics {
+            #[metric(help = "Total requests served", unit = "requests")]
+            requests: Monotone,
+            #[metric(name = "in_flight", stability = "experimental")]
+            active: Signed,
+            #[metric(prefix = "tls_")]
+            handshakes: Unsigned,
+            #[metric(skip)]
+            #[allow(dead_code)]
+            scratch: Unsigned,
+            #[metric(nested)]
+            db: DbMetrics,
+            #[metric(nested, prefix = "replica_")]
+            replica: DbMetrics,
+        }
+
+        static METRICS: HttpMetrics = HttpMetrics {
+            requests: Monotone::new(),
+            active: Signed::new(),
+            handshakes: Unsigned::new(),
+            scratch: Unsigned::new(),
+            db: DbMetrics {
+                queries: Monotone::new(),
+            },
+            replica: DbMetrics {
+                queries: Monotone::new(),
+            },
+        };
+
+        #[test]
+        fn test_derive_names_and_metadata() {
+            let metrics = METRICS.metrics();
+            let names: Vec<_> = metrics.iter().map(|m| m.name()).collect();
+            assert_eq!(
+                names,
+                [
+                    "http_requests",
+                    "http_in_flight",
+                    "http_tls_handshakes",
+                    "http_db_queries",
+                    "http_replica_db_queries"
+                ]
+            );
+
+            let observed: Vec<_> = metrics.iter().collect();
+            assert_eq!(observed[0].description(), Some("Total requests served"));
+            assert_eq!(observed[0].unit(), Some(Unit::Custom("requests")));
+            assert_eq!(observed[0].stability(), Stability::Stable);
+            assert_eq!(observed[1].description(), None);
+            assert_eq!(observed[1].stability(), Stability::Experimental);
+            assert_eq!(observed[3].description(), Some("Queries executed"));
+            assert_eq!(observed[4].description(), Some("Queries executed"));
+        }
+
+        #[test]
+        fn test_derive_values() {
+            let metrics = HttpMetrics {
+                requests: Monotone::new(),
+                active: Signed::new(),
+                handshakes: Unsigned::new(),
+                scratch: Unsigned::new(),
+                db: DbMetrics {
+                    queries: Monotone::new(),
+                },
+                replica: DbMetrics {
+                    queries: Monotone::new(),
+                },
+            };
+            metrics.requests.add(5);
+            metrics.active.sub(2);
+            metrics.db.queries.add(7);
+
+            let values: Vec<_> = metrics.metrics().iter().map(|m| m.value()).collect();
+            assert_eq!(values[0].as_u64(), 5);
+            assert_eq!(values[1].as_i64(), -2);
+            assert_eq!(values[3].as_u64(), 7);
+        }
+
+        #[derive(Metrics)]
+        struct Generic<C: Observable> {
+            inner: C,
+        }
+
+        #[test]
+        fn test_derive_generic() {
+            let generic = Generic {
+                inner: Unsigned::new(),
+            };
+            generic.inner.add(1);
+            let metrics = generic.metrics();
+            assert_eq!(metrics.iter().next().unwrap().name(), "inner");
+        }
+
+        #[cfg(feature = "json")]
+        #[test]
+        fn test_derive_with_json_observer() {
+            use crate::observers::json::JsonObserver;
+
+            let json = JsonObserver::new()
+                .to_json(METRICS.metrics().iter())
+                .unwrap();
+            assert!(json.contains("\"http_in_flight\""));
+            assert!(json.contains("\"http_db_queries\""));
+            assert!(!json.contains("scratch"));
+        }
+    }
+}
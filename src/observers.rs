@@ -5,13 +5,27 @@
 //! - [`table`] - Pretty-print counters as tables using the `tabled` crate
 //! - [`json`] - Serialize counters to JSON format
 //! - [`prometheus`] - Export counters in Prometheus exposition format
+//! - [`exposition`] - Native Prometheus text encoder, without the `prometheus` crate
 //! - [`opentelemetry`] - Export counters via OpenTelemetry
+//! - [`pushgateway`] - Push counters to a Prometheus Pushgateway
+//! - [`statsd`] - Send counters to a StatsD / DogStatsD agent over UDP
+//! - [`influx`] - Render counters in the InfluxDB line protocol
+//! - [`graphite`] - Send counters to Graphite in the plaintext or pickle protocol
+//! - [`csv`] - Append counter values to CSV / TSV files at every tick
+//! - [`ndjson`] - Append one JSON snapshot per line, with size-based rotation
+//! - [`otlp`] - Export snapshots as OTLP/HTTP protobuf, without the OpenTelemetry SDK
 //!
 //! # Unified Error Handling
 //!
 //! All observers use a unified [`ObserverError`] type, allowing you to switch
 //! between observers without changing error handling code.
 //!
+//! # Common Interface
+//!
+//! Exporters also implement the [`Observer`] trait, directly or through
+//! [`Render`] for the ones producing text, so that they can be picked at
+//! runtime and fed by a single [`Fanout`].
+//!
 //! # Feature Flags
 //!
 //! Each observer is gated behind a feature flag to minimize dependencies:
@@ -19,7 +33,15 @@
 //! - `table` - Enables the [`table`] module
 //! - `json` - Enables the [`json`] module
 //! - `prometheus` - Enables the [`prometheus`] module
+//! - `exposition` - Enables the [`exposition`] module
 //! - `opentelemetry` - Enables the [`opentelemetry`] module
+//! - `pushgateway` - Enables the [`pushgateway`] module
+//! - `statsd` - Enables the [`statsd`] module
+//! - `influx` - Enables the [`influx`] module
+//! - `graphite` - Enables the [`graphite`] module
+//! - `csv` - Enables the [`csv`] module
+//! - `ndjson` - Enables the [`ndjson`] module
+//! - `otlp` - Enables the [`otlp`] module
 //! - `full` - Enables all observer modules
 //!
 //! # Example
@@ -55,8 +77,10 @@
 //! ```
 
 mod error;
+mod observer;
 
 pub use error::{ObserverError, Result};
+pub use observer::{Fanout, Observer, Output, Render};
 
 #[cfg(feature = "prometheus")]
 pub use error::PrometheusError;
@@ -70,8 +94,53 @@ pub mod table;
 #[cfg(feature = "json")]
 pub mod json;
 
+#[cfg(feature = "exposition")]
+pub mod exposition;
+
 #[cfg(feature = "prometheus")]
 pub mod prometheus;
 
 #[cfg(feature = "opentelemetry")]
-pub mod opentelemetry;
\ No newline at end of file
+pub mod opentelemetry;
+
+#[cfg(feature = "pushgateway")]
+pub mod pushgateway;
+
+#[cfg(feature = "statsd")]
+pub mod statsd;
+
+#[cfg(feature = "influx")]
+pub mod influx;
+
+#[cfg(feature = "graphite")]
+pub mod graphite;
+
+#[cfg(feature = "csv")]
+pub mod csv;
+
+#[cfg(feature = "ndjson")]
+pub mod ndjson;
+
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+#[cfg(any(feature = "pushgateway", feature = "otlp"))]
+mod http_client;
+
+#[cfg(any(
+    feature = "statsd",
+    feature = "otlp",
+    feature = "opentelemetry",
+    feature = "graphite",
+    feature = "influx"
+))]
+mod delta;
+
+#[cfg(any(
+    feature = "statsd",
+    feature = "otlp",
+    feature = "opentelemetry",
+    feature = "graphite",
+    feature = "influx"
+))]
+pub use delta::Temporality;
\ No newline at end of file
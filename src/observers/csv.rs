@@ -0,0 +1,577 @@
+//! CSV / TSV time-series observer for offline analysis.
+//!
+//! This module provides [`CsvObserver`], which appends the values of a set of
+//! counters to a CSV (or TSV) file at every tick, ready to be loaded into
+//! pandas, R or a spreadsheet.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `csv` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["csv"] }
+//! ```
+//!
+//! # Layouts
+//!
+//! In the [`Layout::Long`] layout (the default), every tick appends one row
+//! per counter entry, so counters can come and go:
+//!
+//! ```text
+//! timestamp,name,labels,value
+//! 1700000000.000,requests,,10
+//! 1700000000.000,http,method=GET,7
+//! ```
+//!
+//! In the [`Layout::Wide`] layout, the header is derived from the entries of
+//! the first tick, one column per counter entry (the name, with labels in
+//! braces), and every tick appends one row:
+//!
+//! ```text
+//! timestamp,requests,http{method=GET},http{method=POST}
+//! 1700000000.000,10,7,3
+//! 1700000001.000,25,18,7
+//! ```
+//!
+//! Entries missing from a later tick leave their cell empty. An entry that
+//! did not exist at the first tick has no column: it is written in the long
+//! layout to the sink given to [`with_overflow()`](CsvObserver::with_overflow),
+//! so the wide file stays a single table. Without one, the entry is dropped
+//! and the tick that first drops it returns an error naming it.
+//!
+//! Histograms and summaries are written as several series, named like their
+//! Prometheus counterparts: one `_bucket` per bucket with an `le` label
+//! (`+Inf` included) or one sample per quantile with a `quantile` label,
+//! then `_sum` and `_count`.
+//!
+//! Timestamps are seconds since the Unix epoch, with millisecond precision.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::counters::Observable;
+//! use contatori::observers::csv::{CsvObserver, Layout};
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let requests = Unsigned::new().with_name("requests");
+//! let mut csv = CsvObserver::new(Vec::new()).with_layout(Layout::Wide);
+//!
+//! for second in 0..2 {
+//!     requests.add(10);
+//!     let time = UNIX_EPOCH + Duration::from_secs(second);
+//!     csv.record_at([&requests as &dyn Observable].into_iter(), time)?;
+//! }
+//!
+//! let output = String::from_utf8(csv.into_inner()).unwrap();
+//! assert_eq!(output, "timestamp,requests\n0.000,10\n1.000,20\n");
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::{Observer, ObserverError, Result};
+use crate::counters::{Observable, ObservableEntry};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Header of the long layout.
+const LONG_HEADER: [&str; 4] = ["timestamp", "name", "labels", "value"];
+
+/// Row layout of a [`CsvObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// One row per entry and tick: `timestamp,name,labels,value` (default).
+    #[default]
+    Long,
+    /// One row per tick, one column per entry of the first tick.
+    Wide,
+}
+
+/// Observer appending counter values to a CSV or TSV sink at every tick.
+///
+/// Rows are flushed after every tick, so the file can be followed while it
+/// is written.
+#[derive(Debug)]
+pub struct CsvObserver<W: io::Write> {
+    /// Destination of the rows.
+    out: W,
+    /// Destination of the entries without a wide column, in the long layout.
+    overflow: Option<W>,
+    /// Field delimiter.
+    delimiter: char,
+    /// Layout of `out`.
+    layout: Layout,
+    /// Column index of each series, once the wide header is written.
+    columns: Option<HashMap<String, usize>>,
+    /// Whether the long header has been written to `out`.
+    long_header: bool,
+    /// Whether the long header has been written to `overflow`.
+    overflow_header: bool,
+    /// Columns already reported as dropped, without an overflow sink.
+    dropped: HashSet<String>,
+}
+
+impl<W: io::Write> CsvObserver<W> {
+    /// Creates a comma-separated observer writing to `out`.
+    ///
+    /// Wrap unbuffered sinks such as files in a
+    /// [`BufWriter`](std::io::BufWriter).
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            overflow: None,
+            delimiter: ',',
+            layout: Layout::Long,
+            columns: None,
+            long_header: false,
+            overflow_header: false,
+            dropped: HashSet::new(),
+        }
+    }
+
+    /// Creates a tab-separated observer writing to `out`.
+    pub fn tsv(out: W) -> Self {
+        Self::new(out).with_delimiter('\t')
+    }
+
+    /// Sets the field delimiter (default: `,`).
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the layout (default: [`Layout::Long`]).
+    pub fn with_layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Sets the sink of the entries that have no column in the
+    /// [`Layout::Wide`] layout, written in the long layout with a header of
+    /// their own. Without one, such entries are dropped, and the first tick
+    /// that drops each of them returns an error.
+    pub fn with_overflow(mut self, overflow: W) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// Returns the layout.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns a reference to the sink.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Consumes the observer and returns the sink.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Appends the values of `counters` at the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sink.
+    pub fn record<'a>(&mut self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<()> {
+        self.record_at(counters, SystemTime::now())
+    }
+
+    /// Appends the values of `counters` at `time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sinks. In the [`Layout::Wide`]
+    /// layout without an overflow sink, returns [`ObserverError::Metric`]
+    /// the first time an entry without a column is dropped, once the row is
+    /// written.
+    pub fn record_at<'a>(
+        &mut self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Result<()> {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let timestamp = format!("{}.{:03}", elapsed.as_secs(), elapsed.subsec_millis());
+        let series: Vec<Series> = counters
+            .flat_map(|c| c.expand())
+            .flat_map(|entry| Series::of(&entry))
+            .collect();
+
+        if self.layout == Layout::Long {
+            if !self.long_header {
+                self.long_header = true;
+                write_row(&mut self.out, self.delimiter, LONG_HEADER.into_iter())?;
+            }
+            write_long(&mut self.out, self.delimiter, &timestamp, series.iter())?;
+            return Ok(self.out.flush()?);
+        }
+
+        let columns = match &mut self.columns {
+            Some(columns) => columns,
+            columns @ None => {
+                columns.insert(write_wide_header(&mut self.out, self.delimiter, &series)?)
+            }
+        };
+        let mut row = vec![String::new(); columns.len() + 1];
+        row[0] = timestamp.clone();
+        let mut new = Vec::new();
+        for series in &series {
+            match columns.get(&series.column()) {
+                Some(&column) => row[column + 1] = series.value.clone(),
+                None => new.push(series),
+            }
+        }
+        write_row(
+            &mut self.out,
+            self.delimiter,
+            row.iter().map(String::as_str),
+        )?;
+        self.out.flush()?;
+
+        if new.is_empty() {
+            return Ok(());
+        }
+        let Some(overflow) = &mut self.overflow else {
+            let dropped: Vec<String> = new
+                .iter()
+                .map(|series| series.column())
+                .filter(|column| self.dropped.insert(column.clone()))
+                .collect();
+            if dropped.is_empty() {
+                return Ok(());
+            }
+            return Err(ObserverError::Metric(format!(
+                "no wide column for {}, dropped without an overflow sink",
+                dropped.join(", ")
+            )));
+        };
+        if !self.overflow_header {
+            self.overflow_header = true;
+            write_row(overflow, self.delimiter, LONG_HEADER.into_iter())?;
+        }
+        write_long(overflow, self.delimiter, &timestamp, new.into_iter())?;
+        overflow.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: io::Write> Observer for CsvObserver<W> {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        self.record_at(counters.iter().copied(), now)
+    }
+}
+
+/// A single value written to the file: a counter entry, or one of the
+/// series of a histogram or summary entry.
+struct Series {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: String,
+}
+
+impl Series {
+    /// Returns the series of an entry.
+    fn of(entry: &ObservableEntry) -> Vec<Series> {
+        let labels: Vec<(String, String)> = entry
+            .labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let Some(distribution) = &entry.distribution else {
+            return vec![Series {
+                name: entry.name.to_string(),
+                labels,
+                value: entry.value.to_string(),
+            }];
+        };
+
+        let with = |key: &str, value: String| {
+            let mut labels = labels.clone();
+            labels.push((key.to_string(), value));
+            labels
+        };
+        let mut series = Vec::new();
+        if distribution.quantiles.is_empty() {
+            let name = format!("{}_bucket", entry.name);
+            for (bound, count) in &distribution.buckets {
+                series.push(Series {
+                    name: name.clone(),
+                    labels: with("le", bound.to_string()),
+                    value: count.to_string(),
+                });
+            }
+            series.push(Series {
+                name,
+                labels: with("le", "+Inf".to_string()),
+                value: distribution.count.to_string(),
+            });
+        } else {
+            for (quantile, value) in &distribution.quantiles {
+                series.push(Series {
+                    name: entry.name.to_string(),
+                    labels: with("quantile", quantile.to_string()),
+                    value: value.to_string(),
+                });
+            }
+        }
+        series.push(Series {
+            name: format!("{}_sum", entry.name),
+            labels: labels.clone(),
+            value: distribution.sum.to_string(),
+        });
+        series.push(Series {
+            name: format!("{}_count", entry.name),
+            labels,
+            value: distribution.count.to_string(),
+        });
+        series
+    }
+
+    /// Returns the wide column name: the name, with labels in braces.
+    fn column(&self) -> String {
+        if self.labels.is_empty() {
+            return self.name.clone();
+        }
+        format!("{}{{{}}}", self.name, self.joined_labels(","))
+    }
+
+    /// Returns the labels as `key=value` pairs joined by `separator`.
+    fn joined_labels(&self, separator: &str) -> String {
+        self.labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+/// Writes the wide header from the series of the first tick, returning the
+/// column index of each series.
+fn write_wide_header(
+    out: &mut impl io::Write,
+    delimiter: char,
+    series: &[Series],
+) -> io::Result<HashMap<String, usize>> {
+    let mut keys: Vec<String> = Vec::with_capacity(series.len());
+    for series in series {
+        let key = series.column();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    write_row(
+        out,
+        delimiter,
+        std::iter::once("timestamp").chain(keys.iter().map(String::as_str)),
+    )?;
+    Ok(keys.into_iter().enumerate().map(|(i, k)| (k, i)).collect())
+}
+
+/// Writes one long row per series.
+fn write_long<'a>(
+    out: &mut impl io::Write,
+    delimiter: char,
+    timestamp: &str,
+    series: impl Iterator<Item = &'a Series>,
+) -> io::Result<()> {
+    for series in series {
+        let labels = series.joined_labels(";");
+        let fields = [timestamp, &series.name, &labels, &series.value];
+        write_row(out, delimiter, fields.into_iter())?;
+    }
+    Ok(())
+}
+
+/// Writes one row, quoting fields as needed.
+fn write_row<'a>(
+    out: &mut impl io::Write,
+    delimiter: char,
+    fields: impl Iterator<Item = &'a str>,
+) -> io::Result<()> {
+    let mut line = String::new();
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            line.push(delimiter);
+        }
+        let quote = field
+            .chars()
+            .any(|c| c == delimiter || matches!(c, '"' | '\n' | '\r'));
+        if quote {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(field);
+        }
+    }
+    line.push('\n');
+    out.write_all(line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Family;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::unsigned::Unsigned;
+    use crate::testing::{at_millis as at, HttpRequests};
+
+    fn output(out: &[u8]) -> &str {
+        std::str::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_wide() {
+        let requests = Unsigned::new().with_name("requests");
+        let http = HttpRequests::new();
+        let mut csv = CsvObserver::new(Vec::new()).with_layout(Layout::Wide);
+
+        requests.add(10);
+        http.get.add(7);
+        csv.record_at([&requests as &dyn Observable, &http].into_iter(), at(1500))
+            .unwrap();
+        http.post.add(3);
+        csv.record_at([&requests as &dyn Observable, &http].into_iter(), at(2000))
+            .unwrap();
+
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,requests,http_requests,http_requests{method=GET},http_requests{method=POST}\n\
+             1.500,10,0,7,0\n\
+             2.000,10,0,7,3\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_entries_leave_empty_cells() {
+        let a = Unsigned::new().with_name("a");
+        let b = Unsigned::new().with_name("b");
+        let mut csv = CsvObserver::tsv(Vec::new()).with_layout(Layout::Wide);
+        csv.record_at([&a as &dyn Observable, &b].into_iter(), at(0))
+            .unwrap();
+        csv.record_at([&b as &dyn Observable].into_iter(), at(1000))
+            .unwrap();
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp\ta\tb\n0.000\t0\t0\n1.000\t\t0\n"
+        );
+    }
+
+    #[test]
+    fn test_new_entries_go_to_overflow() {
+        let family: Family<Unsigned> = Family::new("jobs", "queue");
+        family.get_or_create("fast").add(1);
+        let mut csv = CsvObserver::new(Vec::new())
+            .with_layout(Layout::Wide)
+            .with_overflow(Vec::new());
+        for millis in [0, 1000, 2000] {
+            if millis == 1000 {
+                family.get_or_create("slow").add(2);
+            }
+            csv.record_at([&family as &dyn Observable].into_iter(), at(millis))
+                .unwrap();
+        }
+
+        // The wide table keeps its columns
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,jobs{queue=fast}\n\
+             0.000,1\n\
+             1.000,1\n\
+             2.000,1\n"
+        );
+        assert_eq!(
+            output(csv.overflow.as_ref().unwrap()),
+            "timestamp,name,labels,value\n\
+             1.000,jobs,queue=slow,2\n\
+             2.000,jobs,queue=slow,2\n"
+        );
+    }
+
+    #[test]
+    fn test_new_entries_without_overflow_are_reported_once() {
+        let family: Family<Unsigned> = Family::new("jobs", "queue");
+        family.get_or_create("fast").add(1);
+        let mut csv = CsvObserver::new(Vec::new()).with_layout(Layout::Wide);
+        csv.record_at([&family as &dyn Observable].into_iter(), at(0))
+            .unwrap();
+
+        family.get_or_create("slow").add(2);
+        let err = csv
+            .record_at([&family as &dyn Observable].into_iter(), at(1000))
+            .unwrap_err();
+        assert!(matches!(err, ObserverError::Metric(_)));
+        assert!(err.to_string().contains("jobs{queue=slow}"));
+        csv.record_at([&family as &dyn Observable].into_iter(), at(2000))
+            .unwrap();
+
+        // The row is written even when an entry is dropped
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,jobs{queue=fast}\n\
+             0.000,1\n\
+             1.000,1\n\
+             2.000,1\n"
+        );
+    }
+
+    #[test]
+    fn test_long() {
+        let http = HttpRequests::new();
+        http.get.add(1);
+        let mut csv = CsvObserver::new(Vec::new());
+        csv.record_at([&http as &dyn Observable].into_iter(), at(0))
+            .unwrap();
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,name,labels,value\n\
+             0.000,http_requests,,0\n\
+             0.000,http_requests,method=GET,1\n\
+             0.000,http_requests,method=POST,0\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_series() {
+        let latency = Histogram::new(&[0.1, 1.0]).with_name("latency");
+        latency.observe(0.05);
+        latency.observe(2.0);
+
+        let mut csv = CsvObserver::new(Vec::new());
+        csv.record_at([&latency as &dyn Observable].into_iter(), at(0))
+            .unwrap();
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,name,labels,value\n\
+             0.000,latency_bucket,le=0.1,1\n\
+             0.000,latency_bucket,le=1,1\n\
+             0.000,latency_bucket,le=+Inf,2\n\
+             0.000,latency_sum,,2.05\n\
+             0.000,latency_count,,2\n"
+        );
+
+        let mut csv = CsvObserver::new(Vec::new()).with_layout(Layout::Wide);
+        csv.record_at([&latency as &dyn Observable].into_iter(), at(0))
+            .unwrap();
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,latency_bucket{le=0.1},latency_bucket{le=1},latency_bucket{le=+Inf},latency_sum,latency_count\n\
+             0.000,1,1,2,2.05,2\n"
+        );
+    }
+
+    #[test]
+    fn test_quoting() {
+        let odd = Unsigned::new().with_name("say \"hi\", twice");
+        let mut csv = CsvObserver::new(Vec::new()).with_layout(Layout::Wide);
+        csv.record_at([&odd as &dyn Observable].into_iter(), at(0))
+            .unwrap();
+        assert_eq!(
+            output(csv.get_ref()),
+            "timestamp,\"say \"\"hi\"\", twice\"\n0.000,0\n"
+        );
+    }
+}
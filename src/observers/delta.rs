@@ -0,0 +1,252 @@
+//! Last-seen tables turning cumulative values into deltas.
+//!
+//! Push exporters that report deltas remember the last value sent for every
+//! entry and send the difference. A value lower than the last one means the
+//! counter was reset in between, and is sent as it is. See [`Temporality`].
+
+use crate::counters::{CounterValue, Distribution};
+#[cfg(any(feature = "graphite", feature = "influx"))]
+use crate::counters::{MetricKind, ObservableEntry};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+
+/// Aggregation temporality of exported counters and histograms.
+///
+/// Gauges and summaries are always exported as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Temporality {
+    /// Values accumulate since the counter was created (default).
+    #[default]
+    Cumulative,
+    /// Values cover the interval since the previous export.
+    ///
+    /// Exporters compute the deltas from a table of the last values they
+    /// exported. The counters are only read, never reset, so other observers
+    /// of the same counters keep seeing cumulative values, and there is no
+    /// need to wrap them in [`Resettable`](crate::adapters::Resettable),
+    /// which would defeat the table.
+    ///
+    /// Every export closes an interval: exporting the same collection twice
+    /// reports no change the second time, so export each collection once.
+    Delta,
+}
+
+/// Last value sent for an entry.
+#[derive(Debug, Clone)]
+enum Sent {
+    Value(CounterValue),
+    Distribution(Distribution),
+}
+
+/// Current values of entries whose deltas are being exported, to be
+/// [committed](DeltaTracker::commit) once the export succeeds.
+#[derive(Debug, Default)]
+pub(crate) struct Pending(Vec<(String, Sent)>);
+
+/// Per-entry table of the last values sent.
+#[derive(Debug, Default)]
+pub(crate) struct DeltaTracker {
+    last_seen: Mutex<HashMap<String, Sent>>,
+}
+
+impl Clone for DeltaTracker {
+    fn clone(&self) -> Self {
+        let last_seen = self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        Self {
+            last_seen: Mutex::new(last_seen.clone()),
+        }
+    }
+}
+
+impl DeltaTracker {
+    /// Replaces the value or distribution of a counter or histogram entry
+    /// with its difference from the previous call; entries of other kinds
+    /// are left as they are.
+    #[cfg(any(feature = "graphite", feature = "influx"))]
+    pub(crate) fn update_entry(&self, entry: &mut ObservableEntry) {
+        if matches!(
+            entry.metric_kind,
+            MetricKind::Counter | MetricKind::Histogram
+        ) {
+            let key = entry_key(
+                &entry.name,
+                entry.labels.iter().map(|(k, v)| (*k, v.as_ref())),
+            );
+            self.update(key, &mut entry.value, &mut entry.distribution);
+        }
+    }
+
+    /// Replaces the distribution, if any, or else the value of the entry
+    /// identified by `key` with its difference from the previous call, and
+    /// remembers the current one.
+    ///
+    /// The first call for a key leaves the value as it is.
+    #[cfg(any(
+        feature = "opentelemetry",
+        feature = "graphite",
+        feature = "influx",
+        test
+    ))]
+    pub(crate) fn update(
+        &self,
+        key: String,
+        value: &mut CounterValue,
+        distribution: &mut Option<Distribution>,
+    ) {
+        let mut pending = Pending::default();
+        self.stage(key, value, distribution, &mut pending);
+        self.commit(pending);
+    }
+
+    /// Like [`update`](Self::update), but the current value is added to
+    /// `pending` instead of being remembered, so the next call computes the
+    /// same delta until `pending` is [committed](Self::commit).
+    pub(crate) fn stage(
+        &self,
+        key: String,
+        value: &mut CounterValue,
+        distribution: &mut Option<Distribution>,
+        pending: &mut Pending,
+    ) {
+        let last_seen = self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        match distribution.take() {
+            Some(current) => {
+                *distribution = Some(match last_seen.get(&key) {
+                    Some(Sent::Distribution(last)) => distribution_delta(&current, last),
+                    _ => current.clone(),
+                });
+                pending.0.push((key, Sent::Distribution(current)));
+            }
+            None => {
+                let current = *value;
+                if let Some(Sent::Value(last)) = last_seen.get(&key) {
+                    *value = value_delta(current, *last);
+                }
+                pending.0.push((key, Sent::Value(current)));
+            }
+        }
+    }
+
+    /// Remembers the values of `pending`, once their deltas were exported.
+    pub(crate) fn commit(&self, pending: Pending) {
+        let mut last_seen = self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        last_seen.extend(pending.0);
+    }
+}
+
+/// Returns a key identifying an entry by name and labels.
+pub(crate) fn entry_key<'a>(
+    name: &str,
+    labels: impl Iterator<Item = (&'a str, &'a str)>,
+) -> String {
+    let mut key = name.to_string();
+    for (k, v) in labels {
+        let _ = write!(key, "\0{}={}", k, v);
+    }
+    key
+}
+
+/// Returns `current - last`, or `current` if the counter was reset.
+pub(crate) fn value_delta(current: CounterValue, last: CounterValue) -> CounterValue {
+    match (current, last) {
+        (CounterValue::Unsigned(c), CounterValue::Unsigned(l)) if c >= l => {
+            CounterValue::Unsigned(c - l)
+        }
+        (CounterValue::Signed(c), CounterValue::Signed(l)) if c >= l => {
+            CounterValue::Signed(c.wrapping_sub(l))
+        }
+        (CounterValue::Float(c), CounterValue::Float(l)) if c >= l => CounterValue::Float(c - l),
+        (current, _) => current,
+    }
+}
+
+/// Returns the observations recorded between `last` and `current`, or
+/// `current` if the histogram was reset or its buckets changed.
+pub(crate) fn distribution_delta(current: &Distribution, last: &Distribution) -> Distribution {
+    let comparable = current.count >= last.count
+        && current.buckets.len() == last.buckets.len()
+        && current
+            .buckets
+            .iter()
+            .zip(&last.buckets)
+            .all(|(c, l)| c.0 == l.0 && c.1 >= l.1);
+    if !comparable {
+        return current.clone();
+    }
+    Distribution {
+        count: current.count - last.count,
+        sum: current.sum - last.sum,
+        buckets: current
+            .buckets
+            .iter()
+            .zip(&last.buckets)
+            .map(|(c, l)| (c.0, c.1 - l.1))
+            .collect(),
+        quantiles: current.quantiles.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_delta() {
+        use CounterValue::*;
+        assert_eq!(value_delta(Unsigned(7), Unsigned(5)), Unsigned(2));
+        // Reset
+        assert_eq!(value_delta(Unsigned(3), Unsigned(5)), Unsigned(3));
+        assert_eq!(value_delta(Signed(-1), Signed(-4)), Signed(3));
+        assert_eq!(value_delta(Float(2.5), Float(1.0)), Float(1.5));
+    }
+
+    #[test]
+    fn test_tracker() {
+        let tracker = DeltaTracker::default();
+        let update = |key: &str, value: u64| {
+            let mut value = CounterValue::Unsigned(value);
+            tracker.update(key.to_string(), &mut value, &mut None);
+            value
+        };
+
+        assert_eq!(update("a", 5), CounterValue::Unsigned(5));
+        assert_eq!(update("b", 1), CounterValue::Unsigned(1));
+        assert_eq!(update("a", 8), CounterValue::Unsigned(3));
+        assert_eq!(update("a", 8), CounterValue::Unsigned(0));
+
+        let histogram = |count, buckets| Distribution {
+            count,
+            sum: count as f64,
+            buckets,
+            quantiles: Vec::new(),
+        };
+        let mut distribution = Some(histogram(3, vec![(1.0, 2)]));
+        let mut value = CounterValue::Unsigned(3);
+        tracker.update("h".to_string(), &mut value, &mut distribution);
+        let mut distribution = Some(histogram(5, vec![(1.0, 3)]));
+        tracker.update("h".to_string(), &mut value, &mut distribution);
+        assert_eq!(distribution, Some(histogram(2, vec![(1.0, 1)])));
+    }
+
+    #[test]
+    fn test_stage_and_commit() {
+        let tracker = DeltaTracker::default();
+        let stage = |value: u64, pending: &mut Pending| {
+            let mut value = CounterValue::Unsigned(value);
+            tracker.stage("a".to_string(), &mut value, &mut None, pending);
+            value
+        };
+
+        tracker.update("a".to_string(), &mut CounterValue::Unsigned(5), &mut None);
+        // Until committed, the delta keeps covering the same interval
+        assert_eq!(stage(8, &mut Pending::default()), CounterValue::Unsigned(3));
+        let mut pending = Pending::default();
+        assert_eq!(stage(9, &mut pending), CounterValue::Unsigned(4));
+        tracker.commit(pending);
+        assert_eq!(
+            stage(10, &mut Pending::default()),
+            CounterValue::Unsigned(1)
+        );
+    }
+}
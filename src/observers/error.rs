@@ -39,10 +39,31 @@ pub enum ObserverError {
     #[error("opentelemetry error: {0}")]
     OpenTelemetry(#[from] OtelError),
 
+    /// Error writing to a formatter.
+    #[error("format error: {0}")]
+    Fmt(#[from] std::fmt::Error),
+
+    /// Error writing to an I/O sink.
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Error encoding to UTF-8.
     #[error("utf8 error: {0}")]
     Utf8(#[from] std::string::FromUtf8Error),
 
+    /// An HTTP endpoint answered with a non-success status.
+    #[error("http error: status {status}: {message}")]
+    Http {
+        /// Response status code.
+        status: u16,
+        /// Response body, trimmed.
+        message: String,
+    },
+
+    /// Two counters registered under the same name.
+    #[error("duplicate metric name: {0}")]
+    DuplicateMetric(String),
+
     /// Generic metric error.
     #[error("metric error: {0}")]
     Metric(String),
@@ -89,4 +110,4 @@ pub enum OtelError {
     /// Error creating or registering a metric.
     #[error("metric error: {0}")]
     MetricError(String),
-}
\ No newline at end of file
+}
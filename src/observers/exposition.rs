@@ -0,0 +1,1563 @@
+//! Native Prometheus text exposition encoder.
+//!
+//! This module provides [`ExpositionObserver`], which writes counters in the
+//! Prometheus [text exposition format 0.0.4] without depending on the
+//! `prometheus` crate. Entries are streamed from [`Observable::expand`]
+//! straight into any [`fmt::Write`] or [`io::Write`] sink: no registry or
+//! metric objects are built on each scrape.
+//!
+//! Metric names follow the same rules as
+//! [`PrometheusObserver`](super::prometheus::PrometheusObserver): they are
+//! sanitized, prefixed with the namespace and subsystem, and suffixed with
+//! the [unit](Observable::unit) of the counter.
+//!
+//! With [`Format::OpenMetrics`], the same entries are written in the
+//! [OpenMetrics 1.0] format instead: counters get a `_total` suffix, units
+//! are declared with `# UNIT`, the output ends with `# EOF`, and counters can
+//! carry `_created` timestamps and [exemplars](Exemplar).
+//!
+//! [text exposition format 0.0.4]: https://prometheus.io/docs/instrumenting/exposition_formats/
+//! [OpenMetrics 1.0]: https://github.com/OpenObservability/OpenMetrics/blob/main/specification/OpenMetrics.md
+//!
+//! # Feature Flag
+//!
+//! This module requires the `exposition` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["exposition"] }
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::exposition::ExpositionObserver;
+//!
+//! let requests = Monotone::new().with_name("http_requests_total");
+//! requests.add(100);
+//!
+//! let observer = ExpositionObserver::new()
+//!     .with_namespace("myapp")
+//!     .with_const_label("instance", "localhost:8080");
+//!
+//! let counters: Vec<&dyn Observable> = vec![&requests];
+//!
+//! // Into a String...
+//! let output = observer.render(counters.iter().copied())?;
+//!
+//! // ...or straight into a socket, file or buffer
+//! let mut stdout = std::io::stdout().lock();
+//! observer.write(counters.iter().copied(), &mut stdout)?;
+//! ```
+
+use crate::counters::{CounterValue, Distribution, MetricKind, Observable, ObservableEntry, Unit};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{ObserverError, Render, Result};
+
+/// Content type of the text exposition format 0.0.4, for HTTP responses.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Content type of the OpenMetrics 1.0 text format, for HTTP responses.
+pub const OPENMETRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Output format of an [`ExpositionObserver`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Prometheus text exposition format 0.0.4.
+    #[default]
+    Prometheus,
+    /// OpenMetrics 1.0 text format.
+    OpenMetrics,
+}
+
+impl Format {
+    /// Returns the HTTP content type of this format.
+    pub const fn content_type(&self) -> &'static str {
+        match self {
+            Format::Prometheus => CONTENT_TYPE,
+            Format::OpenMetrics => OPENMETRICS_CONTENT_TYPE,
+        }
+    }
+}
+
+/// An OpenMetrics exemplar: a sample of the event that contributed to a
+/// counter, typically carrying a trace id.
+///
+/// Exemplars are written on `_total` samples in [`Format::OpenMetrics`] only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exemplar {
+    /// Labels of the exemplar (e.g. `[("trace_id", "4bf92f35")]`).
+    pub labels: Vec<(String, String)>,
+    /// Value of the exemplar.
+    pub value: f64,
+    /// When the exemplar was observed, if known.
+    pub timestamp: Option<SystemTime>,
+}
+
+impl Exemplar {
+    /// Creates an exemplar with a single `trace_id` label and no timestamp.
+    pub fn trace(trace_id: impl Into<String>, value: f64) -> Self {
+        Self {
+            labels: vec![("trace_id".to_string(), trace_id.into())],
+            value,
+            timestamp: None,
+        }
+    }
+}
+
+/// Callback providing the exemplar of an entry, if any.
+pub(super) type ExemplarFn = dyn Fn(&ObservableEntry) -> Option<Exemplar> + Send + Sync;
+
+/// Prometheus metric type.
+///
+/// Determines how the metric is registered and displayed in Prometheus.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetricType {
+    /// A counter is a cumulative metric that only ever goes up.
+    /// Use for metrics like total requests, errors, bytes sent.
+    #[default]
+    Counter,
+    /// A gauge can go up and down.
+    /// Use for metrics like current connections, temperature, queue size.
+    Gauge,
+    /// A histogram counts observations in buckets, exported as `_bucket`,
+    /// `_sum` and `_count` series.
+    /// Use for metrics like request latencies, response sizes.
+    Histogram,
+    /// A summary exports precomputed quantiles, with `_sum` and `_count`.
+    Summary,
+}
+
+impl MetricType {
+    /// Returns the type as written on `# TYPE` lines.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram => "histogram",
+            MetricType::Summary => "summary",
+        }
+    }
+
+    /// Returns the type of a family from its configured type, if any, and
+    /// its first entry.
+    ///
+    /// Histograms and summaries fall back to gauges when the entry carries no
+    /// [`Distribution`], so that their plain value is still exported.
+    pub(crate) fn resolve(configured: Option<MetricType>, entry: &ObservableEntry) -> MetricType {
+        let detected = match entry.metric_kind {
+            MetricKind::Counter => MetricType::Counter,
+            MetricKind::Gauge => MetricType::Gauge,
+            MetricKind::Histogram => MetricType::Histogram,
+            MetricKind::Summary => MetricType::Summary,
+        };
+        match configured.unwrap_or(detected) {
+            MetricType::Histogram | MetricType::Summary if entry.distribution.is_none() => {
+                MetricType::Gauge
+            }
+            metric_type => metric_type,
+        }
+    }
+
+    /// Returns the label added to the samples of this type (`le` for
+    /// histogram buckets, `quantile` for summaries), which other labels
+    /// cannot use.
+    pub(crate) const fn reserved_label(&self) -> Option<&'static str> {
+        match self {
+            MetricType::Histogram => Some("le"),
+            MetricType::Summary => Some("quantile"),
+            MetricType::Counter | MetricType::Gauge => None,
+        }
+    }
+}
+
+/// Configuration for a specific metric.
+#[derive(Debug, Clone, Default)]
+pub struct MetricConfig {
+    /// The type of metric (Counter, Gauge, Histogram or Summary).
+    /// If `None`, the type is auto-detected based on the counter's `metric_kind` method.
+    pub metric_type: Option<MetricType>,
+    /// Help text describing the metric.
+    pub help: Option<String>,
+    /// Additional labels specific to this metric.
+    pub labels: HashMap<String, String>,
+    /// When the metric was created, written as `_created` for counters in
+    /// OpenMetrics output. Overrides [`ExpositionObserver::with_created`].
+    pub created: Option<SystemTime>,
+}
+
+/// Observer that writes counters in the Prometheus text exposition format.
+///
+/// It is configured like [`PrometheusObserver`](super::prometheus::PrometheusObserver)
+/// and produces equivalent output, but encodes the entries directly instead
+/// of going through a `prometheus::Registry`.
+///
+/// Metric families are written in the order their name is first seen; all
+/// entries sharing a name are grouped under a single `# HELP`/`# TYPE`
+/// header.
+#[derive(Clone, Default)]
+pub struct ExpositionObserver {
+    /// Output format.
+    format: Format,
+    /// Namespace (prefix) for all metrics.
+    namespace: Option<String>,
+    /// Subsystem for all metrics.
+    subsystem: Option<String>,
+    /// Constant labels applied to all metrics, in insertion order.
+    const_labels: Vec<(String, String)>,
+    /// Per-metric configuration.
+    metric_configs: HashMap<String, MetricConfig>,
+    /// Creation time of counters, for OpenMetrics `_created` samples.
+    created: Option<SystemTime>,
+    /// Exemplar provider, for OpenMetrics counters.
+    exemplars: Option<Arc<ExemplarFn>>,
+}
+
+impl fmt::Debug for ExpositionObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExpositionObserver")
+            .field("format", &self.format)
+            .field("namespace", &self.namespace)
+            .field("subsystem", &self.subsystem)
+            .field("const_labels", &self.const_labels)
+            .field("metric_configs", &self.metric_configs)
+            .field("created", &self.created)
+            .field("exemplars", &self.exemplars.is_some())
+            .finish()
+    }
+}
+
+impl ExpositionObserver {
+    /// Creates a new `ExpositionObserver` with no namespace and no labels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the output format.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Returns the output format.
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Sets the namespace (prefix) for all metrics.
+    ///
+    /// The namespace is prepended to metric names with an underscore.
+    /// For example, namespace "myapp" + metric "requests" = "myapp_requests".
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Sets the subsystem for all metrics.
+    ///
+    /// The subsystem appears between namespace and metric name.
+    pub fn with_subsystem(mut self, subsystem: &str) -> Self {
+        self.subsystem = Some(subsystem.to_string());
+        self
+    }
+
+    /// Adds a constant label to all metrics.
+    ///
+    /// Adding a label twice replaces its value.
+    pub fn with_const_label(mut self, name: &str, value: &str) -> Self {
+        match self.const_labels.iter_mut().find(|(k, _)| k == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self
+                .const_labels
+                .push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Configures a specific metric.
+    pub fn with_metric_config(mut self, name: &str, config: MetricConfig) -> Self {
+        self.metric_configs.insert(name.to_string(), config);
+        self
+    }
+
+    /// Sets the metric type for a specific metric.
+    ///
+    /// This overrides the auto-detection based on `metric_kind()`.
+    pub fn with_type(mut self, name: &str, metric_type: MetricType) -> Self {
+        self.metric_configs
+            .entry(name.to_string())
+            .or_default()
+            .metric_type = Some(metric_type);
+        self
+    }
+
+    /// Sets the help text for a specific metric.
+    pub fn with_help(mut self, name: &str, help: &str) -> Self {
+        self.metric_configs
+            .entry(name.to_string())
+            .or_default()
+            .help = Some(help.to_string());
+        self
+    }
+
+    /// Sets the creation time of all counters, usually the process start.
+    ///
+    /// In [`Format::OpenMetrics`], every counter sample is followed by a
+    /// `_created` sample with this timestamp, unless
+    /// [`MetricConfig::created`] sets one for the metric.
+    pub fn with_created(mut self, created: SystemTime) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Sets a callback providing the [`Exemplar`] of each counter entry.
+    ///
+    /// Used in [`Format::OpenMetrics`] only; returning `None` writes the
+    /// sample without exemplar.
+    pub fn with_exemplars<F>(mut self, exemplars: F) -> Self
+    where
+        F: Fn(&ObservableEntry) -> Option<Exemplar> + Send + Sync + 'static,
+    {
+        self.exemplars = Some(Arc::new(exemplars));
+        self
+    }
+
+    /// Renders counters to a `String`.
+    ///
+    /// # Errors
+    ///
+    /// Never fails in practice; the `Result` mirrors the other observers.
+    pub fn render<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<String> {
+        let mut output = String::new();
+        self.encode(counters, &mut output)?;
+        Ok(output)
+    }
+
+    /// Renders counters to bytes (useful for HTTP responses).
+    pub fn render_bytes<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<Vec<u8>> {
+        Ok(self.render(counters)?.into_bytes())
+    }
+
+    /// Writes counters to an [`io::Write`] sink.
+    ///
+    /// Output is written in many small pieces: wrap unbuffered sinks such as
+    /// files and sockets in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sink.
+    pub fn write<'a, W: io::Write + ?Sized>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        out: &mut W,
+    ) -> Result<()> {
+        let mut adapter = IoAdapter {
+            inner: out,
+            error: None,
+        };
+        match self.encode(counters, &mut adapter) {
+            Err(_) if adapter.error.is_some() => Err(adapter.error.take().unwrap().into()),
+            result => result,
+        }
+    }
+
+    /// Encodes counters into a [`fmt::Write`] sink.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sink fails.
+    pub fn encode<'a, W: fmt::Write + ?Sized>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        out: &mut W,
+    ) -> Result<()> {
+        for family in collect_families(counters) {
+            self.encode_family(&family, out)?;
+        }
+        if self.format == Format::OpenMetrics {
+            out.write_str("# EOF\n")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the header and samples of one metric family.
+    fn encode_family<W: fmt::Write + ?Sized>(
+        &self,
+        family: &Family<'_>,
+        out: &mut W,
+    ) -> Result<()> {
+        let raw_name = family.raw_name();
+        let config = self.metric_configs.get(raw_name);
+        let openmetrics = self.format == Format::OpenMetrics;
+
+        let metric_type =
+            MetricType::resolve(config.and_then(|c| c.metric_type), &family.entries[0]);
+        let is_counter = metric_type == MetricType::Counter;
+
+        let mut full_name = build_full_name(
+            self.namespace.as_deref(),
+            self.subsystem.as_deref(),
+            raw_name,
+        );
+        // OpenMetrics counter families are named without `_total`, which
+        // is added back on the samples
+        if openmetrics && is_counter {
+            if let Some(stripped) = full_name.strip_suffix("_total") {
+                full_name.truncate(stripped.len());
+            }
+        }
+        let unit = family.source.unit();
+        let full_name = with_unit_suffix(full_name, unit);
+
+        out.write_str("# HELP ")?;
+        out.write_str(&full_name)?;
+        out.write_char(' ')?;
+        match config
+            .and_then(|c| c.help.as_deref())
+            .or(family.source.description())
+        {
+            Some(help) => write_escaped(out, help, openmetrics)?,
+            None => write_escaped(out, &format!("{} metric", raw_name), openmetrics)?,
+        }
+        writeln!(out)?;
+        writeln!(out, "# TYPE {} {}", full_name, metric_type.as_str())?;
+        if let (true, Some(unit)) = (openmetrics, unit) {
+            writeln!(out, "# UNIT {} {}", full_name, sanitize_name(unit.as_str()))?;
+        }
+
+        let total = openmetrics && is_counter;
+        let created = config
+            .and_then(|c| c.created)
+            .or(self.created)
+            .filter(|_| openmetrics && metric_type != MetricType::Gauge);
+        let reserved = metric_type.reserved_label();
+        let no_distribution = Distribution::default();
+
+        // Config labels are kept sorted for a stable output
+        let mut config_labels: Vec<(&str, &str)> = config
+            .map(|c| {
+                c.labels
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        config_labels.sort_unstable();
+
+        let mut labels: Vec<(&str, &str)> = Vec::new();
+        for entry in &family.entries {
+            labels.clear();
+            let pairs = self
+                .const_labels
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .chain(config_labels.iter().copied())
+                .chain(entry.labels.iter().map(|(k, v)| (*k, v.as_ref())));
+            // Later labels override earlier ones with the same key
+            for (key, value) in pairs {
+                match labels.iter_mut().find(|(k, _)| *k == key) {
+                    Some(label) => label.1 = value,
+                    None => labels.push((key, value)),
+                }
+            }
+
+            if let Some(reserved) = reserved {
+                if labels.iter().any(|(k, _)| *k == reserved) {
+                    return Err(ObserverError::Metric(format!(
+                        "label `{}` is reserved in {} {}",
+                        reserved,
+                        metric_type.as_str(),
+                        full_name
+                    )));
+                }
+            }
+
+            match metric_type {
+                MetricType::Histogram | MetricType::Summary => {
+                    let distribution = entry.distribution.as_ref().unwrap_or(&no_distribution);
+                    self.write_distribution(out, &full_name, &labels, metric_type, distribution)?;
+                }
+                MetricType::Counter | MetricType::Gauge => {
+                    out.write_str(&full_name)?;
+                    if total {
+                        out.write_str("_total")?;
+                    }
+                    write_labels(out, &labels)?;
+                    out.write_char(' ')?;
+                    write_value(out, entry.value, metric_type)?;
+                    if total {
+                        let exemplar = self.exemplars.as_ref().and_then(|f| f(entry));
+                        if let Some(exemplar) = exemplar {
+                            write_exemplar(out, &exemplar)?;
+                        }
+                    }
+                    out.write_char('\n')?;
+                }
+            }
+
+            if let Some(created) = created {
+                out.write_str(&full_name)?;
+                out.write_str("_created")?;
+                write_labels(out, &labels)?;
+                out.write_char(' ')?;
+                write_timestamp(out, created)?;
+                out.write_char('\n')?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the `_bucket` or `quantile` samples of an entry, then its
+    /// `_sum` and `_count`.
+    fn write_distribution<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        full_name: &str,
+        labels: &[(&str, &str)],
+        metric_type: MetricType,
+        distribution: &Distribution,
+    ) -> fmt::Result {
+        let openmetrics = self.format == Format::OpenMetrics;
+        if metric_type == MetricType::Histogram {
+            let finite = distribution
+                .buckets
+                .iter()
+                .filter(|(bound, _)| *bound != f64::INFINITY);
+            for &(bound, count) in finite {
+                out.write_str(full_name)?;
+                out.write_str("_bucket")?;
+                write_labels_with_bound(out, labels, "le", bound, openmetrics)?;
+                writeln!(out, " {}", count)?;
+            }
+            out.write_str(full_name)?;
+            out.write_str("_bucket")?;
+            write_labels_with_bound(out, labels, "le", f64::INFINITY, openmetrics)?;
+            writeln!(out, " {}", distribution.count)?;
+        } else {
+            for &(quantile, value) in &distribution.quantiles {
+                out.write_str(full_name)?;
+                write_labels_with_bound(out, labels, "quantile", quantile, openmetrics)?;
+                out.write_char(' ')?;
+                write_float(out, value)?;
+                out.write_char('\n')?;
+            }
+        }
+
+        out.write_str(full_name)?;
+        out.write_str("_sum")?;
+        write_labels(out, labels)?;
+        out.write_char(' ')?;
+        write_float(out, distribution.sum)?;
+        out.write_char('\n')?;
+
+        out.write_str(full_name)?;
+        out.write_str("_count")?;
+        write_labels(out, labels)?;
+        writeln!(out, " {}", distribution.count)
+    }
+}
+
+impl Render for ExpositionObserver {
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        _now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()> {
+        self.write(counters.iter().copied(), out)
+    }
+}
+
+/// The entries sharing a metric name, with the first observable that
+/// produced them (which provides the metadata).
+struct Family<'a> {
+    source: &'a dyn Observable,
+    entries: Vec<ObservableEntry>,
+}
+
+impl Family<'_> {
+    fn raw_name(&self) -> &str {
+        match self.entries[0].name.as_ref() {
+            "" => "unnamed",
+            name => name,
+        }
+    }
+}
+
+/// Groups expanded entries by metric name, in the order names are first seen.
+fn collect_families<'a>(counters: impl Iterator<Item = &'a dyn Observable>) -> Vec<Family<'a>> {
+    let mut families: Vec<Family<'a>> = Vec::new();
+    let mut index: HashMap<Cow<'static, str>, usize> = HashMap::new();
+    for counter in counters {
+        for entry in counter.expand() {
+            match index.get(&entry.name) {
+                Some(&i) => families[i].entries.push(entry),
+                None => {
+                    index.insert(entry.name.clone(), families.len());
+                    families.push(Family {
+                        source: counter,
+                        entries: vec![entry],
+                    });
+                }
+            }
+        }
+    }
+    families
+}
+
+/// Sanitizes a metric name to be Prometheus-compatible.
+///
+/// Prometheus metric names must match `[a-zA-Z_:][a-zA-Z0-9_:]*`.
+pub(crate) fn sanitize_name(name: &str) -> String {
+    let mut result = sanitize_chars(name);
+    // Ensure name doesn't start with a digit
+    if result
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_digit())
+        .unwrap_or(false)
+    {
+        result.insert(0, '_');
+    }
+    result
+}
+
+/// Maps a name to `[a-zA-Z0-9_:]*` characters like
+/// [`sanitize_name()`], but allows a leading digit.
+pub(crate) fn sanitize_chars(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+            result.push(c);
+        } else if c == '-' || c == '.' || c == ' ' {
+            result.push('_');
+        } else if c.is_alphabetic() {
+            result.push('_');
+            result.push(c);
+        }
+    }
+    if result.is_empty() {
+        result.push_str("unnamed");
+    }
+    result
+}
+
+/// Builds the full metric name with namespace and subsystem.
+pub(crate) fn build_full_name(
+    namespace: Option<&str>,
+    subsystem: Option<&str>,
+    name: &str,
+) -> String {
+    let sanitized = sanitize_name(name);
+    match (namespace, subsystem) {
+        (Some(ns), Some(ss)) => format!("{}_{}_{}", ns, ss, sanitized),
+        (Some(ns), None) => format!("{}_{}", ns, sanitized),
+        (None, Some(ss)) => format!("{}_{}", ss, sanitized),
+        (None, None) => sanitized,
+    }
+}
+
+/// Appends the unit to a metric name, following the Prometheus naming
+/// convention (`request_duration` + seconds = `request_duration_seconds`).
+///
+/// Names that already end with the unit are left untouched.
+pub(crate) fn with_unit_suffix(full_name: String, unit: Option<Unit>) -> String {
+    let Some(unit) = unit else {
+        return full_name;
+    };
+    let suffix = sanitize_name(unit.as_str());
+    if full_name.ends_with(&format!("_{}", suffix)) {
+        full_name
+    } else {
+        format!("{}_{}", full_name, suffix)
+    }
+}
+
+/// Writes a label name, replacing characters outside `[a-zA-Z0-9_]`.
+fn write_label_name<W: fmt::Write + ?Sized>(out: &mut W, name: &str) -> fmt::Result {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        out.write_char('_')?;
+    }
+    for c in name.chars() {
+        out.write_char(if c.is_ascii_alphanumeric() { c } else { '_' })?;
+    }
+    Ok(())
+}
+
+/// Writes `{k="v",...}`, or nothing if there are no labels.
+fn write_labels<W: fmt::Write + ?Sized>(out: &mut W, labels: &[(&str, &str)]) -> fmt::Result {
+    if labels.is_empty() {
+        return Ok(());
+    }
+    out.write_char('{')?;
+    write_label_pairs(out, labels)?;
+    out.write_char('}')
+}
+
+/// Writes `{k="v",...,key="bound"}`: the labels, then the `le` bound of a
+/// bucket or the `quantile` of a summary sample.
+fn write_labels_with_bound<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    labels: &[(&str, &str)],
+    key: &str,
+    bound: f64,
+    openmetrics: bool,
+) -> fmt::Result {
+    out.write_char('{')?;
+    write_label_pairs(out, labels)?;
+    if !labels.is_empty() {
+        out.write_char(',')?;
+    }
+    out.write_str(key)?;
+    out.write_str("=\"")?;
+    // OpenMetrics expects canonical floats, e.g. `1.0` rather than `1`
+    if openmetrics && bound.is_finite() && bound.fract() == 0.0 {
+        write!(out, "{:.1}", bound)?;
+    } else {
+        write_float(out, bound)?;
+    }
+    out.write_str("\"}")
+}
+
+/// Writes `k="v",...` without braces.
+fn write_label_pairs<W: fmt::Write + ?Sized>(out: &mut W, labels: &[(&str, &str)]) -> fmt::Result {
+    for (i, (key, value)) in labels.iter().enumerate() {
+        if i > 0 {
+            out.write_char(',')?;
+        }
+        write_label_name(out, key)?;
+        out.write_str("=\"")?;
+        write_escaped(out, value, true)?;
+        out.write_char('"')?;
+    }
+    Ok(())
+}
+
+/// Escapes backslashes and line feeds, and double quotes in label values.
+fn write_escaped<W: fmt::Write + ?Sized>(out: &mut W, text: &str, quotes: bool) -> fmt::Result {
+    for c in text.chars() {
+        match c {
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '"' if quotes => out.write_str("\\\"")?,
+            c => out.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a sample value. Counters are clamped to zero as they cannot be
+/// negative.
+fn write_value<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    value: CounterValue,
+    metric_type: MetricType,
+) -> fmt::Result {
+    match (value, metric_type) {
+        (CounterValue::Unsigned(v), _) => write!(out, "{}", v),
+        (CounterValue::Signed(v), MetricType::Counter) => write!(out, "{}", v.max(0)),
+        (CounterValue::Signed(v), _) => write!(out, "{}", v),
+        (CounterValue::Float(v), MetricType::Counter) => write_float(out, v.max(0.0)),
+        (CounterValue::Float(v), _) => write_float(out, v),
+    }
+}
+
+/// Writes a float using the Prometheus spelling of special values.
+fn write_float<W: fmt::Write + ?Sized>(out: &mut W, v: f64) -> fmt::Result {
+    if v.is_nan() {
+        out.write_str("NaN")
+    } else if v.is_infinite() {
+        out.write_str(if v > 0.0 { "+Inf" } else { "-Inf" })
+    } else {
+        write!(out, "{}", v)
+    }
+}
+
+/// Writes ` # {k="v",...} value [timestamp]` after a sample.
+fn write_exemplar<W: fmt::Write + ?Sized>(out: &mut W, exemplar: &Exemplar) -> fmt::Result {
+    let labels: Vec<(&str, &str)> = exemplar
+        .labels
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    // Unlike samples, exemplars always have braces
+    out.write_str(" # ")?;
+    if labels.is_empty() {
+        out.write_str("{}")?;
+    } else {
+        write_labels(out, &labels)?;
+    }
+    out.write_char(' ')?;
+    write_float(out, exemplar.value)?;
+    if let Some(timestamp) = exemplar.timestamp {
+        out.write_char(' ')?;
+        write_timestamp(out, timestamp)?;
+    }
+    Ok(())
+}
+
+/// Writes a timestamp as seconds since the Unix epoch.
+fn write_timestamp<W: fmt::Write + ?Sized>(out: &mut W, time: SystemTime) -> fmt::Result {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    match since.subsec_millis() {
+        0 => write!(out, "{}", since.as_secs()),
+        millis => write!(out, "{}.{:03}", since.as_secs(), millis),
+    }
+}
+
+/// Bridges [`io::Write`] to [`fmt::Write`], keeping the underlying I/O error.
+struct IoAdapter<'w, W: ?Sized> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write + ?Sized> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Family;
+    use crate::counters::average::Average;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+    use std::time::Duration;
+
+    fn render(observer: &ExpositionObserver, counters: &[&dyn Observable]) -> String {
+        observer.render(counters.iter().copied()).unwrap()
+    }
+
+    #[test]
+    fn test_render_empty() {
+        assert!(render(&ExpositionObserver::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_render_counter_and_gauge() {
+        let requests = Monotone::new().with_name("requests_total");
+        let connections = Signed::new().with_name("connections");
+        requests.add(100);
+        connections.sub(3);
+
+        let output = render(&ExpositionObserver::new(), &[&requests, &connections]);
+        assert_eq!(
+            output,
+            "# HELP requests_total requests_total metric\n\
+             # TYPE requests_total counter\n\
+             requests_total 100\n\
+             # HELP connections connections metric\n\
+             # TYPE connections gauge\n\
+             connections -3\n"
+        );
+    }
+
+    #[test]
+    fn test_render_naming_rules() {
+        let counter = Unsigned::new()
+            .with_name("http.latency")
+            .with_unit(Unit::Seconds);
+        counter.add(1);
+
+        let observer = ExpositionObserver::new()
+            .with_namespace("myapp")
+            .with_subsystem("api");
+        let output = render(&observer, &[&counter]);
+        assert!(output.contains("# TYPE myapp_api_http_latency_seconds gauge\n"));
+        assert!(output.contains("myapp_api_http_latency_seconds 1\n"));
+    }
+
+    #[test]
+    fn test_render_labels_merge() {
+        crate::labeled_group!(
+            Requests,
+            "http_requests",
+            "method",
+            value: Monotone,
+            get: "GET": Monotone,
+        );
+        let requests = Requests::new();
+        requests.value.add(1);
+        requests.get.add(5);
+
+        let observer = ExpositionObserver::new()
+            .with_const_label("instance", "a")
+            .with_metric_config(
+                "http_requests",
+                MetricConfig {
+                    labels: [("region".to_string(), "eu".to_string())].into(),
+                    ..Default::default()
+                },
+            );
+        let output = render(&observer, &[&requests]);
+        assert!(output.contains("http_requests{instance=\"a\",region=\"eu\"} 1\n"));
+        assert!(output.contains("http_requests{instance=\"a\",region=\"eu\",method=\"GET\"} 5\n"));
+        // One header for the whole family
+        assert_eq!(output.matches("# TYPE").count(), 1);
+    }
+
+    #[test]
+    fn test_render_entry_label_overrides_const() {
+        let family: crate::adapters::Family<Unsigned> =
+            crate::adapters::Family::new("jobs", "instance");
+        family.get_or_create("worker-1").add(2);
+
+        let observer = ExpositionObserver::new().with_const_label("instance", "default");
+        let output = render(&observer, &[&family]);
+        assert!(output.contains("jobs{instance=\"worker-1\"} 2\n"));
+    }
+
+    #[test]
+    fn test_render_escaping() {
+        let family: crate::adapters::Family<Unsigned> =
+            crate::adapters::Family::new("paths", "path");
+        family.get_or_create("a\"b\\c\nd").add(1);
+
+        let observer = ExpositionObserver::new().with_help("paths", "Line one\nback\\slash");
+        let output = render(&observer, &[&family]);
+        assert!(output.contains("# HELP paths Line one\\nback\\\\slash\n"));
+        assert!(output.contains("paths{path=\"a\\\"b\\\\c\\nd\"} 1\n"));
+    }
+
+    #[test]
+    fn test_render_type_override_and_help() {
+        let counter = Unsigned::new()
+            .with_name("processed")
+            .with_help("Items processed");
+        counter.add(7);
+
+        let observer = ExpositionObserver::new().with_type("processed", MetricType::Counter);
+        let output = render(&observer, &[&counter]);
+        assert!(output.contains("# HELP processed Items processed\n"));
+        assert!(output.contains("# TYPE processed counter\n"));
+    }
+
+    #[test]
+    fn test_render_float_and_clamped_counter() {
+        let avg = Average::new().with_name("latency");
+        avg.observe(3);
+        let signed = Signed::new().with_name("balance");
+        signed.sub(5);
+
+        let observer = ExpositionObserver::new().with_type("balance", MetricType::Counter);
+        let output = render(&observer, &[&avg, &signed]);
+        assert!(output.contains("latency 3\n"));
+        assert!(output.contains("balance 0\n"));
+
+        let mut value = String::new();
+        write_float(&mut value, f64::INFINITY).unwrap();
+        assert_eq!(value, "+Inf");
+    }
+
+    #[test]
+    fn test_write_io() {
+        let counter = Unsigned::new().with_name("bytes");
+        counter.add(9);
+
+        let mut buffer = Vec::new();
+        ExpositionObserver::new()
+            .write(std::iter::once(&counter as &dyn Observable), &mut buffer)
+            .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().ends_with("bytes 9\n"));
+    }
+
+    #[test]
+    fn test_write_io_error() {
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let counter = Unsigned::new().with_name("bytes");
+        let err = ExpositionObserver::new()
+            .write(std::iter::once(&counter as &dyn Observable), &mut Broken)
+            .unwrap_err();
+        assert!(
+            matches!(err, super::super::ObserverError::Io(ref e) if e.kind() == io::ErrorKind::BrokenPipe)
+        );
+    }
+
+    #[test]
+    fn test_sanitize_name() {
+        assert_eq!(sanitize_name("valid_name"), "valid_name");
+        assert_eq!(sanitize_name("with-dash"), "with_dash");
+        assert_eq!(sanitize_name(""), "unnamed");
+        assert_eq!(sanitize_name("123starts"), "_123starts");
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_matches_prometheus_observer() {
+        use crate::observers::prometheus::PrometheusObserver;
+
+        let requests = Monotone::new().with_name("requests");
+        let queue = Unsigned::new().with_name("queue");
+        requests.add(3);
+        queue.add(4);
+        let counters: Vec<&dyn Observable> = vec![&requests, &queue];
+
+        let native = ExpositionObserver::new()
+            .with_namespace("app")
+            .render(counters.iter().copied())
+            .unwrap();
+        let reference = PrometheusObserver::new()
+            .with_namespace("app")
+            .render(counters.iter().copied())
+            .unwrap();
+
+        let mut native: Vec<_> = native.lines().collect();
+        let mut reference: Vec<_> = reference.lines().collect();
+        native.sort_unstable();
+        reference.sort_unstable();
+        assert_eq!(native, reference);
+    }
+
+    /// Checks `text` against the OpenMetrics 1.0 text format ABNF, plus the
+    /// family rules the tests rely on: contiguous unique families, metadata
+    /// before samples, `# UNIT` matching the name suffix, counter samples
+    /// using `_total` / `_created`, unique samples and exactly one `# EOF`.
+    fn validate_openmetrics(text: &str) -> std::result::Result<(), String> {
+        use std::collections::HashSet;
+
+        fn is_name(s: &str, colons: bool) -> bool {
+            let mut chars = s.chars();
+            let valid_first = |c: char| c.is_ascii_alphabetic() || c == '_' || (colons && c == ':');
+            chars.next().is_some_and(valid_first)
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || (colons && c == ':'))
+        }
+
+        fn is_number(s: &str) -> bool {
+            matches!(s, "+Inf" | "-Inf" | "NaN")
+                || (!s.is_empty()
+                    && s.chars()
+                        .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+                    && s.parse::<f64>().is_ok())
+        }
+
+        /// Parses `{...}` at the start of `s`, returning the labels and the rest.
+        fn parse_labels(s: &str) -> std::result::Result<(String, &str), String> {
+            let mut rest = s.strip_prefix('{').ok_or("expected '{'")?;
+            let mut names = HashSet::new();
+            let start = rest;
+            loop {
+                if let Some(after) = rest.strip_prefix('}') {
+                    let consumed = start.len() - rest.len();
+                    return Ok((start[..consumed].to_string(), after));
+                }
+                if !names.is_empty() {
+                    rest = rest.strip_prefix(',').ok_or("expected ','")?;
+                }
+                let eq = rest.find('=').ok_or("expected '='")?;
+                let name = &rest[..eq];
+                if !is_name(name, false) || !names.insert(name.to_string()) {
+                    return Err(format!("bad label name {:?}", name));
+                }
+                rest = rest[eq + 1..].strip_prefix('"').ok_or("expected '\"'")?;
+                let mut chars = rest.char_indices();
+                let end = loop {
+                    match chars.next() {
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, '\\' | '"' | 'n')) => {}
+                            _ => return Err("bad escape in label value".into()),
+                        },
+                        Some((i, '"')) => break i,
+                        Some((_, '\n')) | None => return Err("unterminated label value".into()),
+                        Some(_) => {}
+                    }
+                };
+                rest = &rest[end + 1..];
+            }
+        }
+
+        let body = text
+            .strip_suffix("# EOF\n")
+            .ok_or("missing trailing # EOF")?;
+        if body.contains("# EOF") {
+            return Err("# EOF before the end".into());
+        }
+
+        let mut seen_families = HashSet::new();
+        let mut seen_samples = HashSet::new();
+        let mut family: Option<String> = None;
+        let mut family_type = "unknown".to_string();
+        let mut has_samples = false;
+
+        for line in body.lines() {
+            if let Some(descriptor) = line.strip_prefix("# ") {
+                let mut parts = descriptor.splitn(3, ' ');
+                let (kind, name) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+                let value = parts
+                    .next()
+                    .ok_or(format!("incomplete descriptor: {}", line))?;
+                if !is_name(name, true) {
+                    return Err(format!("bad family name: {}", line));
+                }
+                if family.as_deref() != Some(name) {
+                    if !seen_families.insert(name.to_string()) {
+                        return Err(format!("family {} is not contiguous", name));
+                    }
+                    family = Some(name.to_string());
+                    family_type = "unknown".to_string();
+                    has_samples = false;
+                } else if has_samples {
+                    return Err(format!("metadata after samples: {}", line));
+                }
+                match kind {
+                    "TYPE" => {
+                        if !matches!(
+                            value,
+                            "counter"
+                                | "gauge"
+                                | "histogram"
+                                | "gaugehistogram"
+                                | "stateset"
+                                | "info"
+                                | "summary"
+                                | "unknown"
+                        ) {
+                            return Err(format!("bad type: {}", line));
+                        }
+                        family_type = value.to_string();
+                    }
+                    "UNIT" => {
+                        if !name.ends_with(&format!("_{}", value)) {
+                            return Err(format!("unit is not a name suffix: {}", line));
+                        }
+                    }
+                    "HELP" => {
+                        let mut chars = value.chars();
+                        while let Some(c) = chars.next() {
+                            if c == '\\' && !matches!(chars.next(), Some('\\' | '"' | 'n')) {
+                                return Err(format!("bad escape in help: {}", line));
+                            }
+                        }
+                    }
+                    _ => return Err(format!("unknown descriptor: {}", line)),
+                }
+                continue;
+            }
+
+            // sample = metricname [labels] SP number [SP timestamp] [exemplar]
+            let name_end = line
+                .find(['{', ' '])
+                .ok_or(format!("bad sample: {}", line))?;
+            let name = &line[..name_end];
+            let (labels, rest) = match line[name_end..].starts_with('{') {
+                true => parse_labels(&line[name_end..])?,
+                false => (String::new(), &line[name_end..]),
+            };
+            let (sample, exemplar) = match rest.split_once(" # ") {
+                Some((sample, exemplar)) => (sample, Some(exemplar)),
+                None => (rest, None),
+            };
+            let mut fields = sample.strip_prefix(' ').ok_or("expected ' '")?.split(' ');
+            if !fields.next().is_some_and(is_number) {
+                return Err(format!("bad value: {}", line));
+            }
+            if let Some(ts) = fields.next() {
+                if !is_number(ts) {
+                    return Err(format!("bad timestamp: {}", line));
+                }
+            }
+            if fields.next().is_some() {
+                return Err(format!("trailing fields: {}", line));
+            }
+
+            let family_name = family
+                .as_deref()
+                .ok_or(format!("sample without family: {}", line))?;
+            let suffix = name
+                .strip_prefix(family_name)
+                .ok_or(format!("sample outside its family: {}", line))?;
+            let allowed: &[&str] = match family_type.as_str() {
+                "counter" => &["_total", "_created"],
+                "gauge" | "unknown" => &[""],
+                "histogram" => &["_bucket", "_count", "_sum", "_created"],
+                "summary" => &["", "_count", "_sum", "_created"],
+                _ => &[
+                    "", "_bucket", "_count", "_sum", "_created", "_gcount", "_gsum",
+                ],
+            };
+            if !allowed.contains(&suffix) {
+                return Err(format!(
+                    "bad suffix {:?} for {}: {}",
+                    suffix, family_type, line
+                ));
+            }
+            let required = match (family_type.as_str(), suffix) {
+                ("histogram", "_bucket") => Some("le=\""),
+                ("summary", "") => Some("quantile=\""),
+                _ => None,
+            };
+            if let Some(label) = required {
+                if !labels.contains(label) {
+                    return Err(format!("missing {}: {}", label, line));
+                }
+            }
+            if let Some(exemplar) = exemplar {
+                if suffix != "_total" && suffix != "_bucket" {
+                    return Err(format!("exemplar not allowed here: {}", line));
+                }
+                let (_, rest) = parse_labels(exemplar)?;
+                let mut fields = rest.strip_prefix(' ').ok_or("expected ' '")?.split(' ');
+                if !fields.next().is_some_and(is_number) || !fields.all(is_number) {
+                    return Err(format!("bad exemplar: {}", line));
+                }
+            }
+            if !seen_samples.insert(format!("{}{}", name, labels)) {
+                return Err(format!("duplicate sample: {}", line));
+            }
+            has_samples = true;
+        }
+        Ok(())
+    }
+
+    fn openmetrics() -> ExpositionObserver {
+        ExpositionObserver::new().with_format(Format::OpenMetrics)
+    }
+
+    #[test]
+    fn test_openmetrics_counter_and_gauge() {
+        let requests = Monotone::new()
+            .with_name("http_requests_total")
+            .with_help("Total \"HTTP\" requests");
+        let queue = Signed::new().with_name("queue_depth");
+        requests.add(3);
+        queue.sub(2);
+
+        let output = render(&openmetrics(), &[&requests, &queue]);
+        validate_openmetrics(&output).unwrap();
+        assert_eq!(
+            output,
+            "# HELP http_requests Total \\\"HTTP\\\" requests\n\
+             # TYPE http_requests counter\n\
+             http_requests_total 3\n\
+             # HELP queue_depth queue_depth metric\n\
+             # TYPE queue_depth gauge\n\
+             queue_depth -2\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_openmetrics_empty() {
+        let output = render(&openmetrics(), &[]);
+        assert_eq!(output, "# EOF\n");
+        validate_openmetrics(&output).unwrap();
+    }
+
+    #[test]
+    fn test_openmetrics_unit() {
+        let sent = Monotone::new().with_name("sent").with_unit(Unit::Bytes);
+        let latency = Average::new().with_name("latency").with_unit(Unit::Seconds);
+        sent.add(512);
+        latency.observe(2);
+
+        let output = render(&openmetrics(), &[&sent, &latency]);
+        validate_openmetrics(&output).unwrap();
+        assert!(output.contains("# TYPE sent_bytes counter\n# UNIT sent_bytes bytes\n"));
+        assert!(output.contains("sent_bytes_total 512\n"));
+        assert!(output.contains("# UNIT latency_seconds seconds\n"));
+        assert!(output.contains("latency_seconds 2\n"));
+
+        // Units are not declared in the Prometheus format
+        assert!(!render(&ExpositionObserver::new(), &[&sent]).contains("# UNIT"));
+    }
+
+    #[test]
+    fn test_openmetrics_created() {
+        let created = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        let requests = Monotone::new().with_name("requests");
+        let connections = Monotone::new().with_name("connections");
+        let queue = Unsigned::new().with_name("queue");
+        requests.add(1);
+
+        let observer = openmetrics()
+            .with_namespace("app")
+            .with_created(UNIX_EPOCH + Duration::from_secs(1_600_000_000))
+            .with_metric_config(
+                "requests",
+                MetricConfig {
+                    created: Some(created),
+                    ..Default::default()
+                },
+            );
+        let output = render(&observer, &[&requests, &connections, &queue]);
+        validate_openmetrics(&output).unwrap();
+        assert!(output.contains("app_requests_total 1\napp_requests_created 1700000000.250\n"));
+        assert!(output.contains("app_connections_created 1600000000\n"));
+        // Gauges have no creation time
+        assert!(!output.contains("app_queue_created"));
+    }
+
+    #[test]
+    fn test_openmetrics_exemplars() {
+        crate::labeled_group!(
+            Requests,
+            "requests",
+            "method",
+            value: Monotone,
+            get: "GET": Monotone,
+        );
+        let requests = Requests::new();
+        requests.value.add(1);
+        requests.get.add(5);
+        let queue = Unsigned::new().with_name("queue");
+
+        let observer = openmetrics()
+            .with_const_label("instance", "a")
+            .with_exemplars(|entry| match entry.labels.is_empty() {
+                true => None,
+                false => Some(Exemplar {
+                    timestamp: Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+                    ..Exemplar::trace("4bf92f35", 0.5)
+                }),
+            });
+        let output = render(&observer, &[&requests, &queue]);
+        validate_openmetrics(&output).unwrap();
+        assert!(output.contains("requests_total{instance=\"a\"} 1\n"));
+        assert!(output.contains(
+            "requests_total{instance=\"a\",method=\"GET\"} 5 # {trace_id=\"4bf92f35\"} 0.5 1700000000\n"
+        ));
+        assert!(output.contains("queue{instance=\"a\"} 0\n"));
+    }
+
+    #[test]
+    fn test_openmetrics_empty_exemplar_labels() {
+        let requests = Monotone::new().with_name("requests");
+        let observer = openmetrics().with_exemplars(|_| {
+            Some(Exemplar {
+                labels: Vec::new(),
+                value: 1.0,
+                timestamp: None,
+            })
+        });
+        let output = render(&observer, &[&requests]);
+        validate_openmetrics(&output).unwrap();
+        assert!(output.contains("requests_total 0 # {} 1\n"));
+    }
+
+    #[test]
+    fn test_openmetrics_validator_rejects() {
+        for invalid in [
+            "",
+            "# TYPE a counter\na_total 1\n",
+            "# TYPE a counter\na 1\n# EOF\n",
+            "# TYPE a gauge\na_total 1\n# EOF\n",
+            "# TYPE a gauge\na{x=\"1} 1\n# EOF\n",
+            "# TYPE a gauge\na 1\n# TYPE b gauge\nb 1\n# TYPE a gauge\n# EOF\n",
+            "# TYPE a_bytes gauge\n# UNIT a_bytes seconds\na_bytes 1\n# EOF\n",
+            "# TYPE a gauge\na 1 # {} 1\n# EOF\n",
+            "# TYPE a gauge\na 1\na 2\n# EOF\n",
+            "# TYPE a gauge\na one\n# EOF\n",
+            "# TYPE a histogram\na 1\n# EOF\n",
+            "# TYPE a histogram\na_bucket 1\n# EOF\n",
+            "# TYPE a summary\na 1\n# EOF\n",
+        ] {
+            assert!(validate_openmetrics(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_content_type() {
+        assert_eq!(Format::default().content_type(), CONTENT_TYPE);
+        assert_eq!(
+            openmetrics().format().content_type(),
+            "application/openmetrics-text; version=1.0.0; charset=utf-8"
+        );
+    }
+
+    /// A summary reporting precomputed quantiles.
+    #[derive(Debug)]
+    struct Quantiles;
+
+    impl Observable for Quantiles {
+        fn name(&self) -> &str {
+            "rpc_duration"
+        }
+
+        fn metric_kind(&self) -> MetricKind {
+            MetricKind::Summary
+        }
+
+        fn value(&self) -> CounterValue {
+            CounterValue::Unsigned(10)
+        }
+
+        fn distribution(&self) -> Option<Distribution> {
+            Some(Distribution {
+                count: 10,
+                sum: 4.5,
+                buckets: Vec::new(),
+                quantiles: vec![(0.5, 0.25), (0.99, 1.5)],
+            })
+        }
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let latency = Histogram::new(&[0.1, 1.0])
+            .with_name("latency")
+            .with_unit(Unit::Seconds);
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(3.0);
+
+        let observer = ExpositionObserver::new()
+            .with_const_label("instance", "a")
+            .with_metric_config(
+                "latency",
+                MetricConfig {
+                    labels: [("region".to_string(), "eu".to_string())].into(),
+                    ..Default::default()
+                },
+            );
+        assert_eq!(
+            render(&observer, &[&latency]),
+            "# HELP latency_seconds latency metric\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{instance=\"a\",region=\"eu\",le=\"0.1\"} 1\n\
+             latency_seconds_bucket{instance=\"a\",region=\"eu\",le=\"1\"} 2\n\
+             latency_seconds_bucket{instance=\"a\",region=\"eu\",le=\"+Inf\"} 3\n\
+             latency_seconds_sum{instance=\"a\",region=\"eu\"} 3.55\n\
+             latency_seconds_count{instance=\"a\",region=\"eu\"} 3\n"
+        );
+    }
+
+    #[test]
+    fn test_render_labeled_histogram() {
+        let latency: Family<Histogram<11>> = Family::new("latency", "method");
+        latency.get_or_create("GET").observe(0.3);
+        latency.get_or_create("POST").observe(20.0);
+
+        let observer = ExpositionObserver::new().with_const_label("instance", "a");
+        let output = render(&observer, &[&latency]);
+        assert!(output.contains("latency_bucket{instance=\"a\",method=\"GET\",le=\"0.25\"} 0\n"));
+        assert!(output.contains("latency_bucket{instance=\"a\",method=\"GET\",le=\"0.5\"} 1\n"));
+        assert!(output.contains("latency_bucket{instance=\"a\",method=\"POST\",le=\"10\"} 0\n"));
+        assert!(output.contains("latency_bucket{instance=\"a\",method=\"POST\",le=\"+Inf\"} 1\n"));
+        assert!(output.contains("latency_sum{instance=\"a\",method=\"POST\"} 20\n"));
+        assert_eq!(output.matches("# TYPE latency histogram").count(), 1);
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let output = render(&ExpositionObserver::new(), &[&Quantiles]);
+        assert_eq!(
+            output,
+            "# HELP rpc_duration rpc_duration metric\n\
+             # TYPE rpc_duration summary\n\
+             rpc_duration{quantile=\"0.5\"} 0.25\n\
+             rpc_duration{quantile=\"0.99\"} 1.5\n\
+             rpc_duration_sum 4.5\n\
+             rpc_duration_count 10\n"
+        );
+    }
+
+    #[test]
+    fn test_render_reserved_label() {
+        let latency = Histogram::new(&[1.0]).with_name("latency");
+        let observer = ExpositionObserver::new().with_const_label("le", "x");
+        let error = observer.render(std::iter::once(&latency as &dyn Observable));
+        assert!(matches!(error, Err(ObserverError::Metric(_))));
+
+        let observer = ExpositionObserver::new().with_const_label("quantile", "x");
+        let error = observer.render(std::iter::once(&Quantiles as &dyn Observable));
+        assert!(matches!(error, Err(ObserverError::Metric(_))));
+
+        // `le` is only reserved in histograms
+        let queue = Unsigned::new().with_name("queue");
+        let observer = ExpositionObserver::new().with_const_label("le", "x");
+        assert!(render(&observer, &[&queue]).contains("queue{le=\"x\"} 0\n"));
+    }
+
+    #[test]
+    fn test_render_histogram_type_override() {
+        let latency = Histogram::new(&[1.0]).with_name("latency");
+        latency.observe(0.5);
+        latency.observe(0.7);
+
+        // Forced to a gauge, the histogram exports its count
+        let observer = ExpositionObserver::new().with_type("latency", MetricType::Gauge);
+        let output = render(&observer, &[&latency]);
+        assert!(output.contains("# TYPE latency gauge\nlatency 2\n"));
+
+        // Without a distribution, a histogram type falls back to a gauge
+        let queue = Unsigned::new().with_name("queue");
+        let observer = ExpositionObserver::new().with_type("queue", MetricType::Histogram);
+        assert!(render(&observer, &[&queue]).contains("# TYPE queue gauge\n"));
+    }
+
+    #[test]
+    fn test_openmetrics_histogram_and_summary() {
+        let latency = Histogram::new(&[0.5, 1.0, 2.5])
+            .with_name("latency")
+            .with_unit(Unit::Seconds);
+        latency.observe(0.7);
+        latency.observe(2.0);
+
+        let observer = openmetrics()
+            .with_const_label("instance", "a")
+            .with_created(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        let output = render(&observer, &[&latency, &Quantiles]);
+        validate_openmetrics(&output).unwrap();
+        assert!(output.contains("# UNIT latency_seconds seconds\n"));
+        assert!(output.contains("latency_seconds_bucket{instance=\"a\",le=\"1.0\"} 1\n"));
+        assert!(output.contains("latency_seconds_bucket{instance=\"a\",le=\"2.5\"} 2\n"));
+        assert!(output.contains("latency_seconds_bucket{instance=\"a\",le=\"+Inf\"} 2\n"));
+        assert!(output.contains("latency_seconds_count{instance=\"a\"} 2\n"));
+        assert!(output.contains("latency_seconds_created{instance=\"a\"} 1700000000\n"));
+        assert!(output.contains("rpc_duration{instance=\"a\",quantile=\"0.99\"} 1.5\n"));
+        assert!(output.contains("rpc_duration_created{instance=\"a\"} 1700000000\n"));
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_distributions_match_prometheus_observer() {
+        use crate::observers::prometheus::PrometheusObserver;
+
+        let latency = Histogram::new(&[0.25, 1.0, 4.0]).with_name("latency");
+        latency.observe(0.1);
+        latency.observe(2.0);
+        let counters: Vec<&dyn Observable> = vec![&latency, &Quantiles];
+
+        let native = ExpositionObserver::new()
+            .with_const_label("instance", "a")
+            .render(counters.iter().copied())
+            .unwrap();
+        let reference = PrometheusObserver::new()
+            .with_const_label("instance", "a")
+            .render(counters.iter().copied())
+            .unwrap();
+
+        let mut native: Vec<_> = native.lines().collect();
+        let mut reference: Vec<_> = reference.lines().collect();
+        native.sort_unstable();
+        reference.sort_unstable();
+        assert_eq!(native, reference);
+    }
+}
@@ -0,0 +1,683 @@
+//! Graphite plaintext and pickle exporter.
+//!
+//! This module provides [`GraphiteObserver`], which flattens counters into
+//! dotted Graphite paths and renders them in the Carbon plaintext or pickle
+//! protocol, and [`GraphiteSender`], which sends them to Carbon over TCP,
+//! reconnecting when the connection is lost.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `graphite` feature (which enables
+//! `exposition`):
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["graphite"] }
+//! ```
+//!
+//! # Paths
+//!
+//! Names and labels are sanitized like in the Prometheus observers, so a
+//! metric keeps the same spelling across backends, and labels are appended
+//! as `key.value` pairs:
+//!
+//! ```text
+//! myapp.http_requests.method.GET 1024 1700000000
+//! ```
+//!
+//! Histograms and summaries are flattened into `.count`, `.sum`,
+//! `.bucket.<bound>` (cumulative, `inf` included) and `.quantile.<q>`
+//! paths, with the dots of bounds and quantiles replaced by underscores.
+//! Float values that are not finite are skipped.
+//!
+//! # Temporality
+//!
+//! Values are cumulative by default. With
+//! [`with_temporality(Temporality::Delta)`](GraphiteObserver::with_temporality),
+//! counters and histograms report the change since the previous render, as
+//! described on [`Temporality::Delta`].
+//!
+//! # Protocols
+//!
+//! | Protocol | Carbon port | Payload |
+//! |----------|-------------|---------|
+//! | [`Protocol::Plaintext`] | 2003 | `path value timestamp\n` lines |
+//! | [`Protocol::Pickle`] | 2004 | length-prefixed pickled list of `(path, (timestamp, value))` |
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::graphite::{GraphiteObserver, Protocol};
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("requests");
+//!
+//! let mut sender = GraphiteObserver::new()
+//!     .with_prefix("myapp")
+//!     .with_protocol(Protocol::Pickle)
+//!     .sender("carbon:2004");
+//! sender.send([&REQUESTS as &dyn Observable].into_iter())?;
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::delta::DeltaTracker;
+use super::exposition::{sanitize_chars, sanitize_name};
+use super::{Observer, ObserverError, Render, Result, Temporality};
+use crate::counters::{CounterValue, Distribution, Observable, ObservableEntry};
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Carbon protocol used to send metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    /// One `path value timestamp` line per metric.
+    #[default]
+    Plaintext,
+    /// Batches pickled with the Python pickle protocol 2.
+    Pickle,
+}
+
+/// A flattened Graphite data point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataPoint {
+    /// Dotted path.
+    pub path: String,
+    /// Value.
+    pub value: CounterValue,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Observer flattening counters into Graphite paths.
+#[derive(Debug, Clone, Default)]
+pub struct GraphiteObserver {
+    /// Dotted prefix of every path.
+    prefix: Option<String>,
+    /// Protocol of rendered payloads.
+    protocol: Protocol,
+    /// Temporality of counters and histograms.
+    temporality: Temporality,
+    /// Last values sent, for delta temporality.
+    last_seen: DeltaTracker,
+}
+
+impl GraphiteObserver {
+    /// Creates a new observer using the plaintext protocol.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a prefix of every path; dots separate its components.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix
+            .split('.')
+            .filter(|part| !part.is_empty())
+            .map(sanitize_chars)
+            .collect::<Vec<_>>()
+            .join(".");
+        self.prefix = (!prefix.is_empty()).then_some(prefix);
+        self
+    }
+
+    /// Sets the protocol of [`render_payload()`](Self::render_payload) and
+    /// senders (default: [`Protocol::Plaintext`]).
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the temporality of counters and histograms (default:
+    /// [`Temporality::Cumulative`]).
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Flattens counters into data points timestamped with `time`.
+    pub fn collect<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Vec<DataPoint> {
+        let timestamp = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut points = Vec::new();
+        for counter in counters {
+            for mut entry in counter.expand() {
+                if self.temporality == Temporality::Delta {
+                    self.last_seen.update_entry(&mut entry);
+                }
+                self.flatten(&entry, timestamp, &mut points);
+            }
+        }
+        points
+    }
+
+    /// Renders counters as plaintext lines, timestamped with the current
+    /// time.
+    pub fn render<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> String {
+        self.render_at(counters, SystemTime::now())
+    }
+
+    /// Renders counters as plaintext lines, timestamped with `time`.
+    pub fn render_at<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> String {
+        let mut output = String::new();
+        for point in self.collect(counters, time) {
+            output.push_str(&format!(
+                "{} {} {}\n",
+                point.path, point.value, point.timestamp
+            ));
+        }
+        output
+    }
+
+    /// Renders counters as a pickle payload, including its 4-byte length
+    /// header, timestamped with `time`.
+    pub fn render_pickle_at<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Vec<u8> {
+        pickle(&self.collect(counters, time))
+    }
+
+    /// Renders counters in the configured protocol, timestamped with
+    /// `time`.
+    pub fn render_payload<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Vec<u8> {
+        match self.protocol {
+            Protocol::Plaintext => self.render_at(counters, time).into_bytes(),
+            Protocol::Pickle => self.render_pickle_at(counters, time),
+        }
+    }
+
+    /// Returns a sender to the Carbon receiver at `addr`.
+    ///
+    /// The connection is opened on the first send.
+    pub fn sender(self, addr: impl Into<String>) -> GraphiteSender {
+        GraphiteSender {
+            observer: self,
+            addr: addr.into(),
+            timeout: Duration::from_secs(5),
+            stream: None,
+        }
+    }
+
+    /// Appends the data points of one entry.
+    fn flatten(&self, entry: &ObservableEntry, timestamp: u64, points: &mut Vec<DataPoint>) {
+        let mut path = String::new();
+        if let Some(prefix) = &self.prefix {
+            path.push_str(prefix);
+            path.push('.');
+        }
+        path.push_str(&sanitize_name(&entry.name));
+        for (key, value) in &entry.labels {
+            path.push('.');
+            path.push_str(&sanitize_name(key));
+            path.push('.');
+            path.push_str(&sanitize_chars(value));
+        }
+
+        let mut push = |path: String, value: CounterValue| {
+            if !matches!(value, CounterValue::Float(v) if !v.is_finite()) {
+                points.push(DataPoint {
+                    path,
+                    value,
+                    timestamp,
+                });
+            }
+        };
+        match &entry.distribution {
+            None => push(path, entry.value),
+            Some(Distribution {
+                count,
+                sum,
+                buckets,
+                quantiles,
+            }) => {
+                push(format!("{}.count", path), CounterValue::Unsigned(*count));
+                push(format!("{}.sum", path), CounterValue::Float(*sum));
+                for &(bound, cumulative) in buckets {
+                    push(
+                        format!("{}.bucket.{}", path, bound_segment(bound)),
+                        CounterValue::Unsigned(cumulative),
+                    );
+                }
+                if !buckets.is_empty() {
+                    push(
+                        format!("{}.bucket.inf", path),
+                        CounterValue::Unsigned(*count),
+                    );
+                }
+                for &(quantile, value) in quantiles {
+                    push(
+                        format!("{}.quantile.{}", path, bound_segment(quantile)),
+                        CounterValue::Float(value),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl Render for GraphiteObserver {
+    /// Writes a payload in the configured protocol.
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()> {
+        out.write_all(&self.render_payload(counters.iter().copied(), now))?;
+        Ok(())
+    }
+}
+
+/// Formats a bucket bound or quantile as a path segment.
+fn bound_segment(bound: f64) -> String {
+    sanitize_chars(&bound.to_string())
+}
+
+/// Pickles data points as a list of `(path, (timestamp, value))` tuples
+/// with protocol 2, prefixed by the big-endian payload length.
+fn pickle(points: &[DataPoint]) -> Vec<u8> {
+    // Opcodes of the pickle protocol
+    const PROTO: u8 = 0x80;
+    const EMPTY_LIST: u8 = b']';
+    const MARK: u8 = b'(';
+    const APPENDS: u8 = b'e';
+    const BINUNICODE: u8 = b'X';
+    const BININT: u8 = b'J';
+    const LONG1: u8 = 0x8a;
+    const BINFLOAT: u8 = b'G';
+    const TUPLE2: u8 = 0x86;
+    const STOP: u8 = b'.';
+
+    fn push_int(out: &mut Vec<u8>, value: i128) {
+        match i32::try_from(value) {
+            Ok(v) => {
+                out.push(BININT);
+                out.extend_from_slice(&v.to_le_bytes());
+            }
+            Err(_) => {
+                // Minimal two's complement little-endian encoding
+                let bytes = value.to_le_bytes();
+                let mut len = bytes.len();
+                while len > 1 {
+                    let (last, prev) = (bytes[len - 1], bytes[len - 2]);
+                    if (last == 0 && prev & 0x80 == 0) || (last == 0xff && prev & 0x80 != 0) {
+                        len -= 1;
+                    } else {
+                        break;
+                    }
+                }
+                out.push(LONG1);
+                out.push(len as u8);
+                out.extend_from_slice(&bytes[..len]);
+            }
+        }
+    }
+
+    let mut out = vec![0; 4];
+    out.extend_from_slice(&[PROTO, 2, EMPTY_LIST]);
+    if !points.is_empty() {
+        out.push(MARK);
+        for point in points {
+            out.push(BINUNICODE);
+            out.extend_from_slice(&(point.path.len() as u32).to_le_bytes());
+            out.extend_from_slice(point.path.as_bytes());
+            push_int(&mut out, point.timestamp as i128);
+            match point.value {
+                CounterValue::Unsigned(v) => push_int(&mut out, v as i128),
+                CounterValue::Signed(v) => push_int(&mut out, v as i128),
+                CounterValue::Float(v) => {
+                    out.push(BINFLOAT);
+                    out.extend_from_slice(&v.to_be_bytes());
+                }
+            }
+            out.push(TUPLE2);
+            out.push(TUPLE2);
+        }
+        out.push(APPENDS);
+    }
+    out.push(STOP);
+    let len = (out.len() - 4) as u32;
+    out[..4].copy_from_slice(&len.to_be_bytes());
+    out
+}
+
+/// Sends counters to a Carbon receiver over TCP.
+///
+/// Created with [`GraphiteObserver::sender()`]. The connection is kept open
+/// between sends and re-established when the receiver closed it or a write
+/// fails.
+#[derive(Debug)]
+pub struct GraphiteSender {
+    observer: GraphiteObserver,
+    addr: String,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl GraphiteSender {
+    /// Sets the connect and write timeout (default: 5s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns `true` if a connection is currently open.
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Sends counters timestamped with the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the receiver cannot be reached, or the write
+    /// fails again after reconnecting.
+    pub fn send<'a>(&mut self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<()> {
+        self.send_at(counters, SystemTime::now())
+    }
+
+    /// Sends counters timestamped with `time`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`send()`](Self::send).
+    pub fn send_at<'a>(
+        &mut self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Result<()> {
+        let payload = self.observer.render_payload(counters, time);
+        if payload.is_empty() {
+            return Ok(());
+        }
+        // A connection closed by the receiver may accept one more write
+        // before failing: check it first, then retry once on a fresh one
+        if self.stream.as_ref().is_some_and(|s| !is_open(s)) {
+            self.stream = None;
+        }
+        let reused = self.stream.is_some();
+        match self.write(&payload) {
+            Err(_) if reused => {
+                self.stream = None;
+                self.write(&payload)
+            }
+            result => result,
+        }
+    }
+
+    /// Closes the connection, if any.
+    pub fn disconnect(&mut self) {
+        self.stream = None;
+    }
+
+    /// Writes a payload, connecting first if needed.
+    fn write(&mut self, payload: &[u8]) -> Result<()> {
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => self.stream.insert(self.connect()?),
+        };
+        let result = stream.write_all(payload).and_then(|()| stream.flush());
+        if result.is_err() {
+            self.stream = None;
+        }
+        Ok(result?)
+    }
+
+    /// Connects to the first reachable address of the receiver.
+    fn connect(&self) -> Result<TcpStream> {
+        let addrs: Vec<SocketAddr> = self.addr.to_socket_addrs()?.collect();
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.map(ObserverError::Io).unwrap_or_else(|| {
+            ObserverError::Metric(format!("{} resolves to no address", self.addr))
+        }))
+    }
+}
+
+impl Observer for GraphiteSender {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        self.send_at(counters.iter().copied(), now)
+    }
+}
+
+/// Returns `false` if the peer closed the connection.
+fn is_open(stream: &TcpStream) -> bool {
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let mut buf = [0u8; 1];
+    let open = match stream.peek(&mut buf) {
+        Ok(0) => false,
+        Ok(_) => true,
+        Err(e) => e.kind() == io::ErrorKind::WouldBlock,
+    };
+    stream.set_nonblocking(false).is_ok() && open
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+    use crate::testing::{at, HttpRequests};
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_plaintext_paths() {
+        let requests = HttpRequests::new();
+        requests.get.add(3);
+        let balance = Signed::new().with_name("account.balance");
+        balance.sub(2);
+
+        let output = GraphiteObserver::new()
+            .with_prefix("myapp..web-1.")
+            .render_at(
+                [&requests as &dyn Observable, &balance].into_iter(),
+                at(100),
+            );
+        assert_eq!(
+            output,
+            "myapp.web_1.http_requests 0 100\n\
+             myapp.web_1.http_requests.method.GET 3 100\n\
+             myapp.web_1.http_requests.method.POST 0 100\n\
+             myapp.web_1.account_balance -2 100\n"
+        );
+    }
+
+    #[test]
+    fn test_sanitized_like_prometheus() {
+        let family = crate::adapters::Family::<Unsigned>::new("http-responses", "status");
+        family.get_or_create("200").add(1);
+        family.get_or_create("a b.c").add(1);
+        let output =
+            GraphiteObserver::new().render_at([&family as &dyn Observable].into_iter(), at(1));
+        assert_eq!(
+            output,
+            "http_responses.status.200 1 1\nhttp_responses.status.a_b_c 1 1\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_paths() {
+        static BOUNDS: [f64; 2] = [0.5, 1.0];
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        latency.observe(0.25);
+        latency.observe(2.0);
+        let output =
+            GraphiteObserver::new().render_at([&latency as &dyn Observable].into_iter(), at(7));
+        assert_eq!(
+            output,
+            "latency.count 2 7\nlatency.sum 2.25 7\nlatency.bucket.0_5 1 7\n\
+             latency.bucket.1 1 7\nlatency.bucket.inf 2 7\n"
+        );
+    }
+
+    #[test]
+    fn test_delta_temporality() {
+        static BOUNDS: [f64; 1] = [1.0];
+        let requests = Monotone::new().with_name("requests");
+        let queue = Unsigned::new().with_name("queue");
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        let counters = [&requests as &dyn Observable, &queue, &latency];
+        let observer = GraphiteObserver::new().with_temporality(Temporality::Delta);
+
+        requests.add(5);
+        queue.add(5);
+        latency.observe(0.5);
+        observer.render_at(counters.into_iter(), at(1));
+
+        requests.add(2);
+        latency.observe(2.0);
+        let output = observer.render_at(counters.into_iter(), at(2));
+        assert_eq!(
+            output,
+            "requests 2 2\nqueue 5 2\nlatency.count 1 2\nlatency.sum 2 2\n\
+             latency.bucket.1 0 2\nlatency.bucket.inf 1 2\n"
+        );
+    }
+
+    #[test]
+    fn test_pickle() {
+        let points = [
+            DataPoint {
+                path: "a.b".to_string(),
+                value: CounterValue::Unsigned(5),
+                timestamp: 1,
+            },
+            DataPoint {
+                path: "c".to_string(),
+                value: CounterValue::Float(0.5),
+                timestamp: 2,
+            },
+        ];
+        let payload = pickle(&points);
+        let mut expected = b"\x80\x02](".to_vec();
+        expected
+            .extend_from_slice(b"X\x03\x00\x00\x00a.bJ\x01\x00\x00\x00J\x05\x00\x00\x00\x86\x86");
+        expected.extend_from_slice(
+            b"X\x01\x00\x00\x00cJ\x02\x00\x00\x00G\x3f\xe0\x00\x00\x00\x00\x00\x00\x86\x86",
+        );
+        expected.extend_from_slice(b"e.");
+        assert_eq!(&payload[..4], &(expected.len() as u32).to_be_bytes());
+        assert_eq!(&payload[4..], &expected[..]);
+
+        // Empty list, and integers beyond i32
+        assert_eq!(pickle(&[]), b"\x00\x00\x00\x04\x80\x02].");
+        let big = pickle(&[DataPoint {
+            path: String::new(),
+            value: CounterValue::Unsigned(u64::MAX),
+            timestamp: 0x8000_0000,
+        }]);
+        let body = &big[4..];
+        assert!(body
+            .windows(7)
+            .any(|w| w == b"\x8a\x05\x00\x00\x00\x80\x00"));
+        assert!(body
+            .windows(11)
+            .any(|w| w == b"\x8a\x09\xff\xff\xff\xff\xff\xff\xff\xff\x00"));
+    }
+
+    /// Accepts `connections` connections, returning what each one received
+    /// until closed by the peer or `close_after` bytes.
+    fn receiver(
+        connections: usize,
+        close_after: usize,
+    ) -> (String, std::thread::JoinHandle<Vec<Vec<u8>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = std::thread::spawn(move || {
+            let mut received = Vec::new();
+            for _ in 0..connections {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut data = vec![0u8; close_after];
+                stream.read_exact(&mut data).unwrap();
+                received.push(data);
+            }
+            received
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_sender_plaintext() {
+        let counter = Unsigned::new().with_name("queue");
+        counter.add(4);
+        let line = "queue 4 10\n";
+        let (addr, receiver) = receiver(1, line.len() * 2);
+
+        let mut sender = GraphiteObserver::new().sender(addr);
+        assert!(!sender.is_connected());
+        sender
+            .send_at([&counter as &dyn Observable].into_iter(), at(10))
+            .unwrap();
+        sender
+            .send_at([&counter as &dyn Observable].into_iter(), at(10))
+            .unwrap();
+        assert!(sender.is_connected());
+        assert_eq!(
+            receiver.join().unwrap(),
+            [format!("{}{}", line, line).into_bytes()]
+        );
+    }
+
+    #[test]
+    fn test_sender_reconnects() {
+        let counter = Unsigned::new().with_name("queue");
+        let observer = GraphiteObserver::new().with_protocol(Protocol::Pickle);
+        let payload = observer.render_pickle_at([&counter as &dyn Observable].into_iter(), at(3));
+        // The receiver closes each connection after one payload
+        let (addr, receiver) = receiver(2, payload.len());
+
+        let mut sender = observer.sender(addr);
+        for _ in 0..2 {
+            sender
+                .send_at([&counter as &dyn Observable].into_iter(), at(3))
+                .unwrap();
+            // Wait for the receiver to close the connection
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(receiver.join().unwrap(), [payload.clone(), payload]);
+    }
+
+    #[test]
+    fn test_sender_unreachable() {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut sender = GraphiteObserver::new().sender(addr.to_string());
+        let counter = Unsigned::new().with_name("queue");
+        assert!(sender
+            .send([&counter as &dyn Observable].into_iter())
+            .is_err());
+        assert!(!sender.is_connected());
+    }
+}
@@ -0,0 +1,334 @@
+//! Minimal blocking HTTP/1.1 client for push exporters.
+//!
+//! Push exporters only need to send one request with a body to a plain
+//! `http://` endpoint and read the status back, so this client stays on
+//! [`std::net::TcpStream`] rather than pulling in an HTTP stack. Every
+//! request uses a fresh connection (`Connection: close`). TLS is not
+//! supported: put a local agent or proxy in front of remote endpoints.
+
+use super::{ObserverError, Result};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// A parsed `http://host[:port][/path]` URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Url {
+    /// Host name or address, IPv6 addresses in brackets.
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// Path without trailing slash (empty for the root).
+    pub(crate) path: String,
+}
+
+impl Url {
+    /// Parses a URL, defaulting to port 80.
+    pub(crate) fn parse(url: &str) -> Result<Self> {
+        let invalid = || ObserverError::Metric(format!("invalid http url: {}", url));
+        let rest = url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, path) = match rest.find('/') {
+            Some(pos) => rest.split_at(pos),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rfind(':') {
+            Some(pos) if !authority[pos..].contains(']') => {
+                let port = authority[pos + 1..].parse().map_err(|_| invalid())?;
+                (&authority[..pos], port)
+            }
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Url {
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// Returns the value of the `Host` header.
+    fn authority(&self) -> String {
+        if self.port == 80 {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Status and body of a response.
+#[derive(Debug)]
+pub(crate) struct Response {
+    pub(crate) status: u16,
+    pub(crate) body: Vec<u8>,
+}
+
+impl Response {
+    /// Returns `true` for 2xx statuses.
+    pub(crate) fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Converts a non-2xx response into [`ObserverError::Http`].
+    pub(crate) fn error_for_status(self) -> Result<Self> {
+        if self.is_success() {
+            Ok(self)
+        } else {
+            Err(ObserverError::Http {
+                status: self.status,
+                message: String::from_utf8_lossy(&self.body).trim().to_string(),
+            })
+        }
+    }
+}
+
+/// Sends a request to `path` (absolute, including any query) on the host of
+/// `url` and reads the response.
+pub(crate) fn send(
+    url: &Url,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    timeout: Duration,
+) -> Result<Response> {
+    let host = url.host.trim_start_matches('[').trim_end_matches(']');
+    let addr = (host, url.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| ObserverError::Metric(format!("cannot resolve {}", url.host)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        method,
+        path,
+        url.authority(),
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(name);
+        head.push_str(": ");
+        head.push_str(value);
+        head.push_str("\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw)?;
+    parse_response(&raw)
+}
+
+/// Parses a complete response read until the connection closed.
+fn parse_response(raw: &[u8]) -> Result<Response> {
+    let malformed = || ObserverError::Metric("malformed http response".to_string());
+    let end = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let head = std::str::from_utf8(&raw[..end]).map_err(|_| malformed())?;
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(malformed)?;
+
+    let mut body = &raw[end + 4..];
+    let mut chunked = false;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            let len: usize = value.parse().map_err(|_| malformed())?;
+            body = body.get(..len).ok_or_else(malformed)?;
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        }
+    }
+    let body = if chunked {
+        decode_chunked(body).ok_or_else(malformed)?
+    } else {
+        body.to_vec()
+    };
+    Ok(Response { status, body })
+}
+
+/// Decodes a chunked body, ignoring extensions and trailers.
+fn decode_chunked(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..line_end]).ok()?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        raw = &raw[line_end + 2..];
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(raw.get(..size)?);
+        raw = raw.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod stand_in {
+    //! A local HTTP server recording requests, for push exporter tests.
+
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+
+    /// A request received by the stand-in.
+    #[derive(Debug)]
+    pub(crate) struct Recorded {
+        pub(crate) method: String,
+        pub(crate) path: String,
+        pub(crate) headers: Vec<(String, String)>,
+        pub(crate) body: Vec<u8>,
+    }
+
+    impl Recorded {
+        pub(crate) fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// Starts a server answering successive requests with `statuses`, then
+    /// exiting. Returns its address and the recorded requests.
+    pub(crate) fn serve(statuses: &[u16]) -> (SocketAddr, Receiver<Recorded>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let statuses = statuses.to_vec();
+        thread::spawn(move || {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                let end = loop {
+                    let n = stream.read(&mut buf).unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    if let Some(end) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break end;
+                    }
+                };
+                let head = String::from_utf8(raw[..end].to_vec()).unwrap();
+                let mut lines = head.split("\r\n");
+                let mut request_line = lines.next().unwrap().split(' ');
+                let method = request_line.next().unwrap().to_string();
+                let path = request_line.next().unwrap().to_string();
+                let headers: Vec<(String, String)> = lines
+                    .map(|line| {
+                        let (k, v) = line.split_once(':').unwrap();
+                        (k.trim().to_string(), v.trim().to_string())
+                    })
+                    .collect();
+                let len: usize = headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case("content-length"))
+                    .map_or(0, |(_, v)| v.parse().unwrap());
+                let mut body = raw[end + 4..].to_vec();
+                while body.len() < len {
+                    let n = stream.read(&mut buf).unwrap();
+                    body.extend_from_slice(&buf[..n]);
+                }
+
+                let reply = format!("status {}", status);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    reply.len(),
+                    reply
+                );
+                let _ = tx.send(Recorded {
+                    method,
+                    path,
+                    headers,
+                    body,
+                });
+            }
+        });
+        (addr, rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url() {
+        let url = Url::parse("http://localhost:9091/prefix/").unwrap();
+        assert_eq!(url.host, "localhost");
+        assert_eq!(url.port, 9091);
+        assert_eq!(url.path, "/prefix");
+        assert_eq!(url.authority(), "localhost:9091");
+
+        let url = Url::parse("http://[::1]").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("[::1]", 80));
+        assert_eq!(Url::parse("http://[::1]:8080").unwrap().port, 8080);
+
+        assert!(Url::parse("https://localhost").is_err());
+        assert!(Url::parse("http://:80").is_err());
+        assert!(Url::parse("http://host:port").is_err());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response =
+            parse_response(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 3\r\n\r\nbad").unwrap();
+        assert_eq!(response.status, 400);
+        assert_eq!(response.body, b"bad");
+        match response.error_for_status() {
+            Err(ObserverError::Http { status, message }) => {
+                assert_eq!((status, message.as_str()), (400, "bad"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let response = parse_response(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=y\r\nde\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.body, b"abcde");
+        assert!(parse_response(b"HTTP/1.1 200").is_err());
+    }
+
+    #[test]
+    fn test_send() {
+        let (addr, requests) = stand_in::serve(&[202]);
+        let url = Url::parse(&format!("http://{}", addr)).unwrap();
+        let response = send(
+            &url,
+            "POST",
+            "/a?b=c",
+            &[("Content-Type", "text/plain")],
+            b"hello",
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(response.is_success());
+        assert_eq!(response.body, b"status 202");
+
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/a?b=c");
+        assert_eq!(request.header("Content-Type"), Some("text/plain"));
+        assert_eq!(request.header("Host"), Some(url.authority().as_str()));
+        assert_eq!(request.body, b"hello");
+    }
+}
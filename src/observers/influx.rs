@@ -0,0 +1,572 @@
+//! InfluxDB line protocol observer.
+//!
+//! This module provides [`InfluxObserver`], which renders counters in the
+//! InfluxDB [line protocol](https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/)
+//! accepted by InfluxDB and Telegraf.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `influx` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["influx"] }
+//! ```
+//!
+//! # Mapping
+//!
+//! Each entry of [`Observable::expand()`] becomes one line:
+//!
+//! - the counter name is the measurement
+//! - global tags and entry labels are the tags, sorted by key
+//! - the value is the `value` field, typed by its [`CounterValue`] variant:
+//!   `i` suffix for `Signed`, `u` for `Unsigned`, none for `Float`
+//! - the timestamp is in nanoseconds since the Unix epoch (configurable)
+//!
+//! Histograms and summaries carry `count` and `sum` fields plus one field
+//! per bucket bound (cumulative, `+Inf` included) or quantile, as Telegraf
+//! does for Prometheus metrics.
+//!
+//! ```text
+//! http_requests,host=web-1,method=GET value=1024u 1700000000000000000
+//! latency,host=web-1 count=3u,sum=0.42,0.1=1u,0.5=3u,+Inf=3u 1700000000000000000
+//! ```
+//!
+//! Float values that are not finite cannot be represented and their lines
+//! are skipped.
+//!
+//! # Temporality
+//!
+//! Values are cumulative by default. With
+//! [`with_temporality(Temporality::Delta)`](InfluxObserver::with_temporality),
+//! counters and histograms report the change since the previous write, as
+//! described on [`Temporality::Delta`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! use contatori::counters::signed::Signed;
+//! use contatori::counters::Observable;
+//! use contatori::observers::influx::InfluxObserver;
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! let balance = Signed::new().with_name("balance");
+//! balance.sub(5);
+//!
+//! let observer = InfluxObserver::new().with_tag("host", "web-1");
+//! let time = UNIX_EPOCH + Duration::from_secs(1);
+//! let output = observer.render_at([&balance as &dyn Observable].into_iter(), time)?;
+//! assert_eq!(output, "balance,host=web-1 value=-5i 1000000000\n");
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::delta::DeltaTracker;
+use super::{Render, Result, Temporality};
+use crate::counters::{CounterValue, Distribution, Observable, ObservableEntry};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Timestamp precision of the written lines.
+///
+/// Must match the `precision` parameter of the InfluxDB write API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Nanoseconds (`ns`), the InfluxDB default.
+    #[default]
+    Nanoseconds,
+    /// Microseconds (`us`).
+    Microseconds,
+    /// Milliseconds (`ms`).
+    Milliseconds,
+    /// Seconds (`s`).
+    Seconds,
+}
+
+impl Precision {
+    /// Returns the value of the `precision` write API parameter.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Precision::Nanoseconds => "ns",
+            Precision::Microseconds => "us",
+            Precision::Milliseconds => "ms",
+            Precision::Seconds => "s",
+        }
+    }
+
+    /// Converts a time to a timestamp in this precision.
+    fn timestamp(&self, time: SystemTime) -> u128 {
+        let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        match self {
+            Precision::Nanoseconds => elapsed.as_nanos(),
+            Precision::Microseconds => elapsed.as_micros(),
+            Precision::Milliseconds => elapsed.as_millis(),
+            Precision::Seconds => elapsed.as_secs() as u128,
+        }
+    }
+}
+
+/// Observer rendering counters in the InfluxDB line protocol.
+#[derive(Debug, Clone)]
+pub struct InfluxObserver {
+    /// Tags added to every line.
+    tags: Vec<(String, String)>,
+    /// Name of the value field.
+    field: String,
+    /// Timestamp precision, or `None` to let the server assign timestamps.
+    precision: Option<Precision>,
+    /// Whether unsigned values use the `u` suffix.
+    unsigned: bool,
+    /// Temporality of counters and histograms.
+    temporality: Temporality,
+    /// Last values written, for delta temporality.
+    last_seen: DeltaTracker,
+}
+
+impl Default for InfluxObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InfluxObserver {
+    /// Creates a new observer with nanosecond timestamps.
+    pub fn new() -> Self {
+        Self {
+            tags: Vec::new(),
+            field: "value".to_string(),
+            precision: Some(Precision::Nanoseconds),
+            unsigned: true,
+            temporality: Temporality::Cumulative,
+            last_seen: DeltaTracker::default(),
+        }
+    }
+
+    /// Adds a tag to every line.
+    ///
+    /// Entry labels with the same key take precedence.
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the name of the value field (default: `value`).
+    pub fn with_field(mut self, name: &str) -> Self {
+        self.field = name.to_string();
+        self
+    }
+
+    /// Sets the timestamp precision (default: [`Precision::Nanoseconds`]).
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Writes lines without timestamp, letting the server use its own
+    /// clock.
+    pub fn without_timestamp(mut self) -> Self {
+        self.precision = None;
+        self
+    }
+
+    /// Writes unsigned values as `i` integers instead of `u` (default:
+    /// `u`), for InfluxDB 1.x which does not support unsigned fields.
+    ///
+    /// Values above `i64::MAX` are clamped.
+    pub fn with_unsigned(mut self, enabled: bool) -> Self {
+        self.unsigned = enabled;
+        self
+    }
+
+    /// Sets the temporality of counters and histograms (default:
+    /// [`Temporality::Cumulative`]).
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Returns the timestamp precision, if timestamps are written.
+    pub fn precision(&self) -> Option<Precision> {
+        self.precision
+    }
+
+    /// Renders counters to a `String`, timestamped with the current time.
+    ///
+    /// # Errors
+    ///
+    /// Never fails in practice; the `Result` mirrors the other observers.
+    pub fn render<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<String> {
+        self.render_at(counters, SystemTime::now())
+    }
+
+    /// Renders counters to a `String`, timestamped with `time`.
+    ///
+    /// # Errors
+    ///
+    /// Never fails in practice; the `Result` mirrors the other observers.
+    pub fn render_at<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Result<String> {
+        let mut output = Vec::new();
+        self.write_at(counters, time, &mut output)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    /// Writes counters to an [`io::Write`] sink, timestamped with the
+    /// current time.
+    ///
+    /// Lines are written one at a time: wrap unbuffered sinks such as files
+    /// and sockets in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sink.
+    pub fn write<'a, W: io::Write + ?Sized>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        out: &mut W,
+    ) -> Result<()> {
+        self.write_at(counters, SystemTime::now(), out)
+    }
+
+    /// Writes counters to an [`io::Write`] sink, timestamped with `time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sink.
+    pub fn write_at<'a, W: io::Write + ?Sized>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+        out: &mut W,
+    ) -> Result<()> {
+        let timestamp = self.precision.map(|p| p.timestamp(time));
+        let mut line = String::new();
+        for counter in counters {
+            for mut entry in counter.expand() {
+                if self.temporality == Temporality::Delta {
+                    self.last_seen.update_entry(&mut entry);
+                }
+                line.clear();
+                if self.encode_entry(&entry, timestamp, &mut line) {
+                    out.write_all(line.as_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes one entry as a line, returning `false` if it has no
+    /// representable field.
+    fn encode_entry(
+        &self,
+        entry: &ObservableEntry,
+        timestamp: Option<u128>,
+        line: &mut String,
+    ) -> bool {
+        push_escaped(line, &entry.name, &[',', ' ']);
+
+        let mut tags: Vec<(&str, &str)> =
+            entry.labels.iter().map(|(k, v)| (*k, v.as_ref())).collect();
+        for (key, value) in &self.tags {
+            if !tags.iter().any(|(k, _)| k == key) {
+                tags.push((key, value));
+            }
+        }
+        tags.sort_by(|a, b| a.0.cmp(b.0));
+        // Empty tag values are rejected by InfluxDB
+        for (key, value) in tags.into_iter().filter(|(_, v)| !v.is_empty()) {
+            line.push(',');
+            push_escaped(line, key, &[',', '=', ' ']);
+            line.push('=');
+            push_escaped(line, value, &[',', '=', ' ']);
+        }
+
+        line.push(' ');
+        let fields = match &entry.distribution {
+            Some(distribution) => self.push_distribution(line, distribution),
+            None => self.push_field(line, &self.field, entry.value),
+        };
+        if !fields {
+            return false;
+        }
+
+        if let Some(timestamp) = timestamp {
+            line.push(' ');
+            line.push_str(&timestamp.to_string());
+        }
+        line.push('\n');
+        true
+    }
+
+    /// Appends the fields of a histogram or summary.
+    fn push_distribution(&self, line: &mut String, distribution: &Distribution) -> bool {
+        self.push_field(line, "count", CounterValue::Unsigned(distribution.count));
+        line.push(',');
+        let mut complete = self.push_field(line, "sum", CounterValue::Float(distribution.sum));
+        for &(bound, count) in &distribution.buckets {
+            line.push(',');
+            self.push_field(line, &bound.to_string(), CounterValue::Unsigned(count));
+        }
+        if !distribution.buckets.is_empty() {
+            line.push(',');
+            self.push_field(line, "+Inf", CounterValue::Unsigned(distribution.count));
+        }
+        for &(quantile, value) in &distribution.quantiles {
+            line.push(',');
+            complete &= self.push_field(line, &quantile.to_string(), CounterValue::Float(value));
+        }
+        complete
+    }
+
+    /// Appends `key=value` with the type suffix of the value, returning
+    /// `false` for non-finite floats.
+    fn push_field(&self, line: &mut String, key: &str, value: CounterValue) -> bool {
+        push_escaped(line, key, &[',', '=', ' ']);
+        line.push('=');
+        match value {
+            CounterValue::Unsigned(v) if self.unsigned => line.push_str(&format!("{}u", v)),
+            CounterValue::Unsigned(v) => {
+                line.push_str(&format!("{}i", v.min(i64::MAX as u64)));
+            }
+            CounterValue::Signed(v) => line.push_str(&format!("{}i", v)),
+            CounterValue::Float(v) if v.is_finite() => line.push_str(&v.to_string()),
+            CounterValue::Float(_) => return false,
+        }
+        true
+    }
+}
+
+impl Render for InfluxObserver {
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()> {
+        self.write_at(counters.iter().copied(), now, out)
+    }
+}
+
+/// Appends `text` with the `special` characters backslash-escaped.
+///
+/// Newlines cannot appear in line protocol and are written as `\n`.
+fn push_escaped(out: &mut String, text: &str, special: &[char]) {
+    for c in text.chars() {
+        if special.contains(&c) {
+            out.push('\\');
+            out.push(c);
+        } else if c == '\n' {
+            out.push_str("\\n");
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+    use crate::observers::Observer;
+    use crate::testing::at;
+    use std::time::Duration;
+
+    fn render(observer: &InfluxObserver, counters: &[&dyn Observable]) -> String {
+        observer.render_at(counters.iter().copied(), at(2)).unwrap()
+    }
+
+    #[test]
+    fn test_typed_fields() {
+        let requests = Monotone::new().with_name("requests");
+        let balance = Signed::new().with_name("balance");
+        requests.add(7);
+        balance.sub(3);
+        let ratio = crate::counters::rate::Rate::new().with_name("ratio");
+
+        let output = render(&InfluxObserver::new(), &[&requests, &balance, &ratio]);
+        assert_eq!(
+            output,
+            "requests value=7u 2000000000\nbalance value=-3i 2000000000\nratio value=0 2000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_precision_and_timestamps() {
+        let counter = Unsigned::new().with_name("c");
+        let time = UNIX_EPOCH + Duration::new(1, 500_000_000);
+        let line = |observer: InfluxObserver| {
+            observer
+                .render_at([&counter as &dyn Observable].into_iter(), time)
+                .unwrap()
+        };
+        assert_eq!(line(InfluxObserver::new()), "c value=0u 1500000000\n");
+        assert_eq!(
+            line(InfluxObserver::new().with_precision(Precision::Microseconds)),
+            "c value=0u 1500000\n"
+        );
+        assert_eq!(
+            line(InfluxObserver::new().with_precision(Precision::Milliseconds)),
+            "c value=0u 1500\n"
+        );
+        assert_eq!(
+            line(InfluxObserver::new().with_precision(Precision::Seconds)),
+            "c value=0u 1\n"
+        );
+        assert_eq!(
+            line(InfluxObserver::new().without_timestamp()),
+            "c value=0u\n"
+        );
+    }
+
+    #[test]
+    fn test_tags_sorted_and_labels_override() {
+        crate::labeled_group!(
+            Requests,
+            "http_requests",
+            "method",
+            value: Monotone,
+            get: "GET": Monotone,
+        );
+        let requests = Requests::new();
+        requests.get.add(2);
+
+        let observer = InfluxObserver::new()
+            .with_tag("host", "web-1")
+            .with_tag("method", "ignored")
+            .with_tag("dc", "eu");
+        let output = render(&observer, &[&requests]);
+        assert!(output.contains("http_requests,dc=eu,host=web-1,method=GET value=2u 2000000000\n"));
+        assert!(output.contains("http_requests,dc=eu,host=web-1,method=ignored value=0u"));
+    }
+
+    #[test]
+    fn test_escaping() {
+        let counter = Unsigned::new().with_name("disk usage,total");
+        let observer = InfluxObserver::new()
+            .with_tag("mount point", "/a=b,c")
+            .with_tag("empty", "")
+            .with_field("used bytes")
+            .without_timestamp();
+        assert_eq!(
+            render(&observer, &[&counter]),
+            "disk\\ usage\\,total,mount\\ point=/a\\=b\\,c used\\ bytes=0u\n"
+        );
+    }
+
+    #[test]
+    fn test_unsigned_as_integer() {
+        let counter = Unsigned::new().with_name("c");
+        counter.add(usize::MAX);
+        let observer = InfluxObserver::new()
+            .with_unsigned(false)
+            .without_timestamp();
+        assert_eq!(
+            render(&observer, &[&counter]),
+            format!("c value={}i\n", i64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_histogram_fields() {
+        static BOUNDS: [f64; 2] = [0.1, 0.5];
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        latency.observe(0.05);
+        latency.observe(0.25);
+        latency.observe(1.0);
+
+        let output = render(&InfluxObserver::new().without_timestamp(), &[&latency]);
+        assert_eq!(output, "latency count=3u,sum=1.3,0.1=1u,0.5=2u,+Inf=3u\n");
+    }
+
+    #[test]
+    fn test_delta_temporality() {
+        static BOUNDS: [f64; 1] = [0.5];
+        let requests = Monotone::new().with_name("requests");
+        let queue = Unsigned::new().with_name("queue");
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        let observer = InfluxObserver::new()
+            .without_timestamp()
+            .with_temporality(Temporality::Delta);
+
+        requests.add(5);
+        queue.add(5);
+        latency.observe(0.25);
+        render(&observer, &[&requests, &queue, &latency]);
+
+        requests.add(2);
+        latency.observe(1.0);
+        let output = render(&observer, &[&requests, &queue, &latency]);
+        assert_eq!(
+            output,
+            "requests value=2u\nqueue value=5u\nlatency count=1u,sum=1,0.5=0u,+Inf=1u\n"
+        );
+    }
+
+    #[test]
+    fn test_non_finite_skipped() {
+        #[derive(Debug)]
+        struct Nan;
+        impl Observable for Nan {
+            fn name(&self) -> &str {
+                "nan"
+            }
+            fn value(&self) -> CounterValue {
+                CounterValue::Float(f64::NAN)
+            }
+        }
+        let counter = Unsigned::new().with_name("ok");
+        let output = render(
+            &InfluxObserver::new().without_timestamp(),
+            &[&Nan, &counter],
+        );
+        assert_eq!(output, "ok value=0u\n");
+    }
+
+    #[test]
+    fn test_observer() {
+        let counter = Unsigned::new().with_name("c");
+        let mut output = InfluxObserver::new().writing_to(Vec::new());
+        output.observe(&[&counter], at(3)).unwrap();
+        counter.add(2);
+        output.observe(&[&counter], at(4)).unwrap();
+        assert_eq!(
+            output.into_inner(),
+            b"c value=0u 3000000000\nc value=2u 4000000000\n"
+        );
+    }
+
+    #[test]
+    fn test_write_streaming() {
+        let a = Unsigned::new().with_name("a");
+        let b = Unsigned::new().with_name("b");
+        let observer = InfluxObserver::new();
+        let mut out = Vec::new();
+        observer
+            .write([&a as &dyn Observable, &b].into_iter(), &mut out)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let timestamp: u128 = lines[0].rsplit(' ').next().unwrap().parse().unwrap();
+        assert!(timestamp > 1_600_000_000_000_000_000);
+
+        // I/O errors are reported
+        struct Broken;
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::other("broken"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(observer
+            .write([&a as &dyn Observable].into_iter(), &mut Broken)
+            .is_err());
+    }
+}
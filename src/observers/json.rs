@@ -34,7 +34,10 @@
 //! // [{"name":"http_requests","value":1000},{"name":"http_errors","value":5}]
 //! ```
 
+use super::Render;
 use crate::counters::Observable;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 // Re-export snapshot types for backwards compatibility
 pub use crate::snapshot::{CounterSnapshot, MetricsSnapshot};
@@ -152,12 +155,22 @@ impl JsonObserver {
     pub fn to_json<'a>(
         &self,
         counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<String, serde_json::Error> {
+        self.to_json_at(counters, SystemTime::now())
+    }
+
+    /// Serializes counters to a JSON string, timestamped with `time` if
+    /// timestamps are included.
+    pub fn to_json_at<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
     ) -> Result<String, serde_json::Error> {
         let snapshots = self.collect(counters);
 
         if self.config.wrap_in_snapshot {
             let snapshot = if self.config.include_timestamp {
-                MetricsSnapshot::with_timestamp(snapshots, current_timestamp_ms())
+                MetricsSnapshot::with_timestamp(snapshots, timestamp_ms(time))
             } else {
                 MetricsSnapshot::new(snapshots)
             };
@@ -183,7 +196,7 @@ impl JsonObserver {
 
         if self.config.wrap_in_snapshot {
             let snapshot = if self.config.include_timestamp {
-                MetricsSnapshot::with_timestamp(snapshots, current_timestamp_ms())
+                MetricsSnapshot::with_timestamp(snapshots, timestamp_ms(SystemTime::now()))
             } else {
                 MetricsSnapshot::new(snapshots)
             };
@@ -194,10 +207,23 @@ impl JsonObserver {
     }
 }
 
-/// Returns the current timestamp in milliseconds since Unix epoch.
-fn current_timestamp_ms() -> u64 {
-    std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
+impl Render for JsonObserver {
+    /// Writes the JSON document followed by a newline.
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> super::Result<()> {
+        let json = self.to_json_at(counters.iter().copied(), now)?;
+        writeln!(out, "{}", json)?;
+        Ok(())
+    }
+}
+
+/// Returns `time` in milliseconds since Unix epoch.
+fn timestamp_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
         .unwrap_or(0)
 }
@@ -210,7 +236,8 @@ mod tests {
     use crate::counters::minimum::Minimum;
     use crate::counters::signed::Signed;
     use crate::counters::unsigned::Unsigned;
-    use crate::counters::CounterValue;
+    use crate::counters::{CounterValue, MetricKind, Stability};
+    use crate::testing::HttpMetrics;
 
     #[test]
     fn test_to_json_empty() {
@@ -360,6 +387,18 @@ mod tests {
         assert!(json.contains("average"));
     }
 
+    #[test]
+    fn test_to_json_multiple_labels() {
+        let observer = JsonObserver::new();
+        let counters: Vec<&dyn Observable> = vec![&HttpMetrics];
+        let json = observer.to_json(counters.into_iter()).unwrap();
+
+        assert!(json.contains(r#""labels":[["method","GET"],["route","/users"],["status","200"]]"#));
+        assert!(
+            json.contains(r#""labels":[["method","POST"],["route","/orders"],["status","500"]]"#)
+        );
+    }
+
     #[test]
     fn test_deserialize_snapshot() {
         let json = r#"{"name":"test","value":42}"#;
@@ -384,13 +423,21 @@ mod tests {
         let snapshot = MetricsSnapshot::new(vec![
             CounterSnapshot {
                 name: "foo".to_string(),
-                label: None,
+                labels: vec![],
                 value: CounterValue::Unsigned(1),
+                unit: None,
+                kind: MetricKind::Gauge,
+                distribution: None,
+                stability: Stability::Stable,
             },
             CounterSnapshot {
                 name: "bar".to_string(),
-                label: None,
+                labels: vec![],
                 value: CounterValue::Unsigned(2),
+                unit: None,
+                kind: MetricKind::Gauge,
+                distribution: None,
+                stability: Stability::Stable,
             },
         ]);
 
@@ -0,0 +1,525 @@
+//! NDJSON snapshot log for cheap on-disk metric history.
+//!
+//! This module provides [`NdjsonObserver`], which appends one
+//! [`MetricsSnapshot`] per line to any [`io::Write`] sink, [`RotatingFile`],
+//! a file sink that rotates by size, and [`SnapshotReader`], which iterates
+//! the snapshots back for postmortem analysis.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `ndjson` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["ndjson"] }
+//! ```
+//!
+//! # Format
+//!
+//! Every line is a self-contained [`MetricsSnapshot`] document, the same
+//! shape produced by [`JsonObserver`](super::json::JsonObserver) with
+//! `wrap_in_snapshot(true)`:
+//!
+//! ```text
+//! {"timestamp_ms":1700000000000,"counters":[{"name":"requests","value":10}]}
+//! {"timestamp_ms":1700000001000,"counters":[{"name":"requests","value":25}]}
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::counters::Observable;
+//! use contatori::observers::ndjson::{NdjsonObserver, SnapshotReader};
+//!
+//! let requests = Unsigned::new().with_name("requests");
+//! let mut log = NdjsonObserver::new(Vec::new());
+//!
+//! for _ in 0..3 {
+//!     requests.add(10);
+//!     log.record([&requests as &dyn Observable].into_iter())?;
+//! }
+//!
+//! let lines = log.into_inner();
+//! let values: Vec<_> = SnapshotReader::new(lines.as_slice())
+//!     .map(|snapshot| snapshot.map(|s| s.get("requests").unwrap().value))
+//!     .collect::<Result<_, _>>()?;
+//! assert_eq!(values.len(), 3);
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::{Observer, Result};
+use crate::counters::Observable;
+use crate::snapshot::MetricsSnapshot;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// When an [`NdjsonObserver`] flushes its sink.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FlushPolicy {
+    /// Flush after every snapshot.
+    #[default]
+    EveryLine,
+    /// Flush after every `n` snapshots.
+    EveryN(usize),
+    /// Flush after a snapshot when at least this much time passed since
+    /// the previous flush.
+    Interval(Duration),
+    /// Never flush explicitly; leave it to the sink (e.g. on drop).
+    Never,
+}
+
+/// Observer appending one [`MetricsSnapshot`] per line to a sink.
+///
+/// Each snapshot is serialized in full before being handed to the sink in a
+/// single write, so a [`RotatingFile`] never splits a line across files.
+#[derive(Debug)]
+pub struct NdjsonObserver<W: io::Write> {
+    /// Destination of the lines.
+    out: W,
+    /// When to flush `out`.
+    flush_policy: FlushPolicy,
+    /// Whether snapshots carry a timestamp.
+    include_timestamp: bool,
+    /// Snapshots written since the last flush.
+    pending: usize,
+    /// Time of the last flush.
+    last_flush: Instant,
+}
+
+impl<W: io::Write> NdjsonObserver<W> {
+    /// Creates an observer writing to `out`.
+    ///
+    /// Snapshots are timestamped and the sink is flushed after every line.
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            flush_policy: FlushPolicy::EveryLine,
+            include_timestamp: true,
+            pending: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Sets the flush policy (default: [`FlushPolicy::EveryLine`]).
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Enables or disables the `timestamp_ms` field (default: enabled).
+    pub fn with_timestamp(mut self, enabled: bool) -> Self {
+        self.include_timestamp = enabled;
+        self
+    }
+
+    /// Returns a reference to the sink.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Consumes the observer and returns the sink, without flushing it.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    /// Appends a snapshot of `counters` taken at the current time.
+    ///
+    /// # Errors
+    ///
+    /// Returns the serialization or I/O error reported while writing.
+    pub fn record<'a>(&mut self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<()> {
+        self.record_at(counters, SystemTime::now())
+    }
+
+    /// Appends a snapshot of `counters` taken at `time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the serialization or I/O error reported while writing.
+    pub fn record_at<'a>(
+        &mut self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        time: SystemTime,
+    ) -> Result<()> {
+        let snapshot = if self.include_timestamp {
+            let elapsed = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            MetricsSnapshot::collect_with_timestamp(counters, elapsed.as_millis() as u64)
+        } else {
+            MetricsSnapshot::collect(counters)
+        };
+        self.write_snapshot(&snapshot)
+    }
+
+    /// Appends an already collected snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns the serialization or I/O error reported while writing.
+    pub fn write_snapshot(&mut self, snapshot: &MetricsSnapshot) -> Result<()> {
+        let mut line = serde_json::to_vec(snapshot)?;
+        line.push(b'\n');
+        self.out.write_all(&line)?;
+        self.pending += 1;
+
+        let due = match self.flush_policy {
+            FlushPolicy::EveryLine => true,
+            FlushPolicy::EveryN(n) => self.pending >= n.max(1),
+            FlushPolicy::Interval(interval) => self.last_flush.elapsed() >= interval,
+            FlushPolicy::Never => false,
+        };
+        if due {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the sink regardless of the flush policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error reported by the sink.
+    pub fn flush(&mut self) -> Result<()> {
+        self.out.flush()?;
+        self.pending = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+impl<W: io::Write> Observer for NdjsonObserver<W> {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        self.record_at(counters.iter().copied(), now)
+    }
+}
+
+/// Returns the path of the `index`-th rotated file: `path.index`.
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Append-only file sink rotating by size.
+///
+/// When a write would grow the current file past the size limit, the file is
+/// renamed to `path.1`, older files shift to `path.2`, `path.3`, ... and the
+/// oldest beyond the retention limit is deleted. Every write lands whole in a
+/// single file, so line-oriented writers never see a line split.
+#[derive(Debug)]
+pub struct RotatingFile {
+    /// Path of the live file.
+    path: PathBuf,
+    /// Buffered handle to the live file.
+    file: BufWriter<File>,
+    /// Size of the live file, including buffered bytes.
+    size: u64,
+    /// Size that triggers a rotation.
+    max_bytes: u64,
+    /// Number of rotated files kept besides the live one.
+    max_files: usize,
+}
+
+impl RotatingFile {
+    /// Number of rotated files kept by default.
+    pub const DEFAULT_MAX_FILES: usize = 5;
+
+    /// Opens `path` for appending, rotating it once it exceeds `max_bytes`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error raised while opening the file.
+    pub fn new(path: impl AsRef<Path>, max_bytes: u64) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file: BufWriter::new(file),
+            size,
+            max_bytes,
+            max_files: Self::DEFAULT_MAX_FILES,
+        })
+    }
+
+    /// Sets how many rotated files are kept (default:
+    /// [`DEFAULT_MAX_FILES`](Self::DEFAULT_MAX_FILES)).
+    ///
+    /// With `0`, the live file is truncated instead of rotated.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Returns the path of the live file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Closes the live file, shifts the rotated ones and reopens it empty.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 0 {
+            let _ = fs::remove_file(rotated_path(&self.path, self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.file = BufWriter::new(file);
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl io::Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Iterator over the snapshots of an NDJSON log.
+///
+/// Blank lines are skipped. A malformed line, such as one truncated by a
+/// crash, yields an error and iteration continues with the next line.
+#[derive(Debug)]
+pub struct SnapshotReader<R: BufRead> {
+    /// Source of the lines.
+    reader: R,
+    /// Reused line buffer.
+    line: String,
+}
+
+impl<R: BufRead> SnapshotReader<R> {
+    /// Creates a reader over `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+        }
+    }
+}
+
+impl SnapshotReader<BufReader<File>> {
+    /// Opens the log at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error raised while opening the file.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl SnapshotReader<BufReader<Box<dyn Read + Send>>> {
+    /// Opens the log at `path` together with its rotated files, oldest first,
+    /// as written by a [`RotatingFile`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error raised while opening a file.
+    pub fn open_rotated(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let mut rotated = Vec::new();
+        while rotated_path(path, rotated.len() + 1).exists() {
+            rotated.push(rotated_path(path, rotated.len() + 1));
+        }
+
+        let mut chain: Box<dyn Read + Send> = Box::new(io::empty());
+        for file in rotated.iter().rev().map(PathBuf::as_path).chain([path]) {
+            chain = Box::new(chain.chain(File::open(file)?));
+        }
+        Ok(Self::new(BufReader::new(chain)))
+    }
+}
+
+impl<R: BufRead> Iterator for SnapshotReader<R> {
+    type Item = Result<MetricsSnapshot>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) if self.line.trim().is_empty() => continue,
+                Ok(_) => return Some(serde_json::from_str(&self.line).map_err(Into::into)),
+                Err(err) => return Some(Err(err.into())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+    use crate::counters::CounterValue;
+
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("contatori-ndjson-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_record_writes_one_snapshot_per_line() {
+        let requests = Monotone::new().with_name("requests");
+        let mut log = NdjsonObserver::new(Vec::new());
+
+        requests.add(10);
+        let time = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        log.record_at([&requests as &dyn Observable].into_iter(), time)
+            .unwrap();
+        requests.add(5);
+        log.record_at([&requests as &dyn Observable].into_iter(), time)
+            .unwrap();
+
+        let output = String::from_utf8(log.into_inner()).unwrap();
+        let lines: Vec<_> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            r#"{"timestamp_ms":1700000000123,"counters":[{"name":"requests","value":10,"kind":"counter"}]}"#
+        );
+        assert!(lines[1].contains(r#""value":15"#));
+    }
+
+    #[test]
+    fn test_round_trip_through_reader() {
+        let hits = Unsigned::new().with_name("hits");
+        let mut log = NdjsonObserver::new(Vec::new()).with_timestamp(false);
+
+        for _ in 0..3 {
+            hits.add(2);
+            log.record([&hits as &dyn Observable].into_iter()).unwrap();
+        }
+
+        let bytes = log.into_inner();
+        let snapshots: Vec<_> = SnapshotReader::new(bytes.as_slice())
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert!(snapshots.iter().all(|s| s.timestamp_ms.is_none()));
+        assert_eq!(
+            snapshots[2].get("hits").unwrap().value,
+            CounterValue::Unsigned(6)
+        );
+    }
+
+    #[test]
+    fn test_reader_skips_blank_lines_and_reports_truncated_ones() {
+        let input = b"{\"counters\":[]}\n\n{\"counters\":[{\"na";
+        let results: Vec<_> = SnapshotReader::new(&input[..]).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+    }
+
+    #[derive(Default)]
+    struct CountingSink {
+        flushes: usize,
+    }
+
+    impl io::Write for CountingSink {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        let snapshot = MetricsSnapshot::new(Vec::new());
+        let flushes = |policy| {
+            let mut log = NdjsonObserver::new(CountingSink::default()).with_flush_policy(policy);
+            for _ in 0..5 {
+                log.write_snapshot(&snapshot).unwrap();
+            }
+            log.into_inner().flushes
+        };
+
+        assert_eq!(flushes(FlushPolicy::EveryLine), 5);
+        assert_eq!(flushes(FlushPolicy::EveryN(2)), 2);
+        assert_eq!(flushes(FlushPolicy::Never), 0);
+        assert_eq!(flushes(FlushPolicy::Interval(Duration::ZERO)), 5);
+        assert_eq!(flushes(FlushPolicy::Interval(Duration::from_secs(3600))), 0);
+    }
+
+    #[test]
+    fn test_rotating_file_keeps_lines_whole() {
+        let dir = scratch_dir("rotate");
+        let path = dir.join("metrics.ndjson");
+
+        let mut file = RotatingFile::new(&path, 10).unwrap().with_max_files(2);
+        for line in [
+            "aaaa\n", "bbbb\n", "cccc\n", "dddd\n", "eeee\n", "ffff\n", "gg\n",
+        ] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |p: PathBuf| fs::read_to_string(p).unwrap();
+        assert_eq!(read(path.clone()), "gg\n");
+        assert_eq!(read(rotated_path(&path, 1)), "eeee\nffff\n");
+        assert_eq!(read(rotated_path(&path, 2)), "cccc\ndddd\n");
+        // The oldest file fell beyond the retention limit
+        assert!(!rotated_path(&path, 3).exists());
+
+        drop(file);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_rotated_reads_oldest_first() {
+        let dir = scratch_dir("history");
+        let path = dir.join("metrics.ndjson");
+        let counter = Unsigned::new().with_name("ticks");
+
+        let sink = RotatingFile::new(&path, 120).unwrap().with_max_files(10);
+        let mut log = NdjsonObserver::new(sink);
+        for _ in 0..10 {
+            counter.add(1);
+            log.record([&counter as &dyn Observable].into_iter())
+                .unwrap();
+        }
+        drop(log);
+        assert!(rotated_path(&path, 1).exists());
+
+        let values: Vec<_> = SnapshotReader::open_rotated(&path)
+            .unwrap()
+            .map(|s| s.unwrap().get("ticks").unwrap().value)
+            .collect();
+        let expected: Vec<_> = (1..=10).map(CounterValue::Unsigned).collect();
+        assert_eq!(values, expected);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
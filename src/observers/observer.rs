@@ -0,0 +1,422 @@
+//! Common interface over all exporters.
+//!
+//! Every exporter has methods shaped after its output: renderers return a
+//! `String`, senders push to a socket, file observers append to a writer.
+//! The [`Observer`] trait puts them behind a single call, so that exporters
+//! can be chosen at runtime and [`Fanout`] can feed one collection pass to
+//! several of them.
+//!
+//! - Exporters with a destination (files, sockets, HTTP endpoints)
+//!   implement [`Observer`] directly.
+//! - Renderers, which only produce text or bytes, implement [`Render`] and
+//!   become observers once given an [`io::Write`] sink with
+//!   [`writing_to()`](Render::writing_to).
+//!
+//! The OpenTelemetry observer is the exception: the SDK reads the counters
+//! itself whenever its reader collects, so it is registered once rather than
+//! fed collections.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::csv::CsvObserver;
+//! use contatori::observers::table::TableObserver;
+//! use contatori::observers::{Fanout, Observer, Render};
+//! use std::time::SystemTime;
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("requests");
+//!
+//! let mut observers = Fanout::new()
+//!     .with(TableObserver::new().writing_to(std::io::stdout()))
+//!     .with(CsvObserver::new(std::fs::File::create("metrics.csv")?));
+//!
+//! // Counters are read once, and every observer sees the same values
+//! observers.observe(&[&REQUESTS], SystemTime::now())?;
+//! ```
+
+use super::Result;
+use crate::counters::{
+    CounterValue, Distribution, MetricKind, Observable, ObservableEntry, Stability, Unit,
+};
+use std::fmt;
+use std::io;
+use std::time::SystemTime;
+
+/// An exporter fed with collections of counters.
+pub trait Observer {
+    /// Exports `counters`, as collected at `now`.
+    ///
+    /// Observers that write timestamps use `now` rather than reading the
+    /// clock, so that all the observers of a [`Fanout`] agree.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the underlying exporter.
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()>;
+}
+
+impl<O: Observer + ?Sized> Observer for Box<O> {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        (**self).observe(counters, now)
+    }
+}
+
+impl<O: Observer + ?Sized> Observer for &mut O {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        (**self).observe(counters, now)
+    }
+}
+
+/// A renderer of counters into text or bytes.
+pub trait Render {
+    /// Writes one rendering of `counters`, as collected at `now`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails or the sink reports one.
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()>;
+
+    /// Returns an [`Observer`] writing every collection to `out`.
+    fn writing_to<W: io::Write>(self, out: W) -> Output<Self, W>
+    where
+        Self: Sized,
+    {
+        Output::new(self, out)
+    }
+}
+
+/// Observer writing the renderings of a [`Render`] to an [`io::Write`]
+/// sink, flushed after every collection.
+#[derive(Debug)]
+pub struct Output<R, W> {
+    renderer: R,
+    out: W,
+}
+
+impl<R: Render, W: io::Write> Output<R, W> {
+    /// Creates an observer writing the renderings of `renderer` to `out`.
+    pub fn new(renderer: R, out: W) -> Self {
+        Self { renderer, out }
+    }
+
+    /// Returns a reference to the renderer.
+    pub fn renderer(&self) -> &R {
+        &self.renderer
+    }
+
+    /// Returns a reference to the sink.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Consumes the observer, returning the sink.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<R: Render, W: io::Write> Observer for Output<R, W> {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        self.renderer.render_to(counters, now, &mut self.out)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Observer feeding one collection pass to several observers.
+///
+/// Every counter is read once per [`observe()`](Observer::observe), and all
+/// the observers see the same values: counters wrapped in
+/// [`Resettable`](crate::adapters::Resettable) are reset once, not once per
+/// observer. An observer failing does not stop the others.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use contatori::observers::{Fanout, Observer};
+///
+/// // Exporters picked from the configuration
+/// let observers: Vec<Box<dyn Observer + Send>> = config.exporters();
+/// let mut fanout: Fanout = observers.into_iter().collect();
+/// fanout.observe(&counters, SystemTime::now())?;
+/// ```
+#[derive(Default)]
+pub struct Fanout {
+    observers: Vec<Box<dyn Observer + Send>>,
+}
+
+impl fmt::Debug for Fanout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fanout")
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+impl Fanout {
+    /// Creates a fan-out without observers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an observer.
+    pub fn with(mut self, observer: impl Observer + Send + 'static) -> Self {
+        self.push(observer);
+        self
+    }
+
+    /// Adds an observer.
+    pub fn push(&mut self, observer: impl Observer + Send + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    /// Returns the number of observers.
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    /// Returns `true` if there are no observers.
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+}
+
+impl FromIterator<Box<dyn Observer + Send>> for Fanout {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Observer + Send>>>(iter: I) -> Self {
+        Self {
+            observers: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Observer for Fanout {
+    /// Reads `counters` once and passes the values read to every observer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error, after every observer has been called.
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        let frozen: Vec<Frozen> = counters
+            .iter()
+            .map(|&counter| Frozen::new(counter))
+            .collect();
+        let counters: Vec<&dyn Observable> = frozen.iter().map(|f| f as &dyn Observable).collect();
+
+        let mut result = Ok(());
+        for observer in &mut self.observers {
+            let observed = observer.observe(&counters, now);
+            if result.is_ok() {
+                result = observed;
+            }
+        }
+        result
+    }
+}
+
+/// The entries of a counter read once, with its value and metadata.
+#[derive(Debug)]
+struct Frozen {
+    name: String,
+    metric_kind: MetricKind,
+    description: Option<&'static str>,
+    unit: Option<Unit>,
+    stability: Stability,
+    additive: bool,
+    value: CounterValue,
+    distribution: Option<Distribution>,
+    entries: Vec<ObservableEntry>,
+}
+
+impl Frozen {
+    /// Reads the entries of `counter`, and its own value and distribution.
+    ///
+    /// A single counter has them in its only entry, which is not read twice
+    /// so that [`Resettable`](crate::adapters::Resettable) counters are
+    /// reset once. Groups and families are read again after their entries,
+    /// for their aggregate.
+    fn new(counter: &dyn Observable) -> Self {
+        let entries = counter.expand();
+        let (value, distribution) = match entries.as_slice() {
+            [entry] if entry.labels.is_empty() => (entry.value, entry.distribution.clone()),
+            _ => (counter.value(), counter.distribution()),
+        };
+        Self {
+            name: counter.name().to_string(),
+            metric_kind: counter.metric_kind(),
+            description: counter.description(),
+            unit: counter.unit(),
+            stability: counter.stability(),
+            additive: counter.is_additive(),
+            value,
+            distribution,
+            entries,
+        }
+    }
+}
+
+impl Observable for Frozen {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metric_kind(&self) -> MetricKind {
+        self.metric_kind
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        self.description
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.unit
+    }
+
+    fn stability(&self) -> Stability {
+        self.stability
+    }
+
+    fn is_additive(&self) -> bool {
+        self.additive
+    }
+
+    fn value(&self) -> CounterValue {
+        self.value
+    }
+
+    fn distribution(&self) -> Option<Distribution> {
+        self.distribution.clone()
+    }
+
+    fn expand(&self) -> Vec<ObservableEntry> {
+        self.entries.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::{Family, Resettable};
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+    use crate::observers::ObserverError;
+    use std::sync::{Arc, Mutex};
+
+    /// Records the values of every collection as `name=value` lines.
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Observer for Recorder {
+        fn observe(&mut self, counters: &[&dyn Observable], _now: SystemTime) -> Result<()> {
+            let mut lines = self.0.lock().unwrap();
+            for entry in counters.iter().flat_map(|c| c.expand()) {
+                lines.push(format!("{}={}", entry.name, entry.value));
+            }
+            Ok(())
+        }
+    }
+
+    /// Records the values of every collection, as counters and not entries.
+    struct Totals(Arc<Mutex<Vec<String>>>);
+
+    impl Observer for Totals {
+        fn observe(&mut self, counters: &[&dyn Observable], _now: SystemTime) -> Result<()> {
+            let mut lines = self.0.lock().unwrap();
+            for counter in counters {
+                lines.push(format!("{}={}", counter.name(), counter.value()));
+            }
+            Ok(())
+        }
+    }
+
+    /// Fails every collection.
+    struct Failing;
+
+    impl Observer for Failing {
+        fn observe(&mut self, _: &[&dyn Observable], _: SystemTime) -> Result<()> {
+            Err(ObserverError::Metric("unavailable".to_string()))
+        }
+    }
+
+    /// Renders `name value` lines.
+    struct Lines;
+
+    impl Render for Lines {
+        fn render_to(
+            &self,
+            counters: &[&dyn Observable],
+            _now: SystemTime,
+            out: &mut dyn io::Write,
+        ) -> Result<()> {
+            for counter in counters {
+                writeln!(out, "{} {}", counter.name(), counter.value())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fanout_reads_counters_once() {
+        let period = Resettable::new(Unsigned::new().with_name("period"));
+        let total = Monotone::new().with_name("total");
+        period.add(3);
+        total.add(5);
+
+        let first = Arc::new(Mutex::new(Vec::new()));
+        let second = Arc::new(Mutex::new(Vec::new()));
+        let mut fanout = Fanout::new()
+            .with(Recorder(first.clone()))
+            .with(Recorder(second.clone()));
+        assert_eq!(fanout.len(), 2);
+
+        fanout
+            .observe(&[&period, &total], SystemTime::now())
+            .unwrap();
+        assert_eq!(*first.lock().unwrap(), ["period=3", "total=5"]);
+        assert_eq!(*second.lock().unwrap(), ["period=3", "total=5"]);
+        assert_eq!(period.value(), CounterValue::Unsigned(0));
+    }
+
+    #[test]
+    fn test_fanout_keeps_aggregate_values() {
+        let requests: Family<Unsigned> = Family::new("requests", "tenant");
+        requests.get_or_create("a").add(2);
+        requests.get_or_create("b").add(3);
+
+        let totals = Arc::new(Mutex::new(Vec::new()));
+        let mut fanout = Fanout::new().with(Totals(totals.clone()));
+        fanout.observe(&[&requests], SystemTime::now()).unwrap();
+
+        // The family's own value is the sum of its children, not the first
+        assert_eq!(*totals.lock().unwrap(), ["requests=5"]);
+    }
+
+    #[test]
+    fn test_fanout_continues_after_error() {
+        let total = Monotone::new().with_name("total");
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let observers: Vec<Box<dyn Observer + Send>> =
+            vec![Box::new(Failing), Box::new(Recorder(lines.clone()))];
+        let mut fanout: Fanout = observers.into_iter().collect();
+
+        let result = fanout.observe(&[&total], SystemTime::now());
+        assert!(matches!(result, Err(ObserverError::Metric(_))));
+        assert_eq!(*lines.lock().unwrap(), ["total=0"]);
+    }
+
+    #[test]
+    fn test_output() {
+        let total = Monotone::new().with_name("total");
+        let mut output = Lines.writing_to(Vec::new());
+        total.add(1);
+        output.observe(&[&total], SystemTime::now()).unwrap();
+        total.add(1);
+        output.observe(&[&total], SystemTime::now()).unwrap();
+        assert_eq!(output.into_inner(), b"total 1\ntotal 2\n");
+    }
+}
@@ -41,9 +41,14 @@
 //! }
 //! ```
 
-use crate::counters::{MetricKind, Observable, ObservableEntry};
-use opentelemetry::{global, metrics::Meter, KeyValue};
+use crate::counters::{CounterValue, MetricKind, Observable, ObservableEntry};
+use opentelemetry::metrics::{AsyncInstrumentBuilder, Meter};
+use opentelemetry::{global, KeyValue};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
+use super::delta::{entry_key, DeltaTracker};
 use super::{OtelError, Result};
 
 /// Observer that exports counters to OpenTelemetry using observable instruments.
@@ -51,10 +56,15 @@ use super::{OtelError, Result};
 /// This observer registers contatori counters with OpenTelemetry's MeterProvider,
 /// using callbacks that read counter values during metric collection.
 ///
-/// # Static Counters
+/// # Static and Shared Counters
 ///
-/// Counters must be `'static` (typically declared as `static` globals) because
-/// OpenTelemetry callbacks need to hold references for the lifetime of the program.
+/// [`register`](Self::register) takes `'static` counters (typically declared
+/// as `static` globals), read for the lifetime of the program.
+/// [`register_shared`](Self::register_shared) takes counters owned elsewhere,
+/// such as by a connection or a tenant, and returns a [`Registration`] that
+/// stops observing them when dropped. Shared counters are observed by one
+/// callback per instrument name, so registering and dropping them at a high
+/// rate does not accumulate callbacks.
 ///
 /// # Example
 ///
@@ -75,6 +85,11 @@ use super::{OtelError, Result};
 pub struct OtelObserver {
     meter: Meter,
     description_prefix: Option<String>,
+    reset_smoothing: bool,
+    /// Counters registered with `register_shared`, by instrument name.
+    shared: Mutex<HashMap<String, Arc<Members>>>,
+    /// Identifier of the next [`Registration`].
+    next_registration: AtomicU64,
 }
 
 impl OtelObserver {
@@ -92,6 +107,9 @@ impl OtelObserver {
         Self {
             meter: global::meter(meter_name),
             description_prefix: None,
+            reset_smoothing: false,
+            shared: Mutex::default(),
+            next_registration: AtomicU64::new(0),
         }
     }
 
@@ -110,12 +128,16 @@ impl OtelObserver {
         Self {
             meter,
             description_prefix: None,
+            reset_smoothing: false,
+            shared: Mutex::default(),
+            next_registration: AtomicU64::new(0),
         }
     }
 
     /// Sets a description prefix for all registered metrics.
     ///
-    /// The prefix will be prepended to each metric's description.
+    /// The prefix will be prepended to each metric's description, or to its
+    /// name when the counter has no [`description()`](Observable::description).
     ///
     /// # Example
     ///
@@ -129,25 +151,67 @@ impl OtelObserver {
         self
     }
 
+    /// Observes counters as the running total of their increments (default:
+    /// disabled).
+    ///
+    /// The increments are computed from the last value seen of every entry
+    /// of every registered counter, so that a counter going down counts as
+    /// reset rather than as a negative increment, and the observed total
+    /// never goes down. Use it for counters that may be reset, e.g. by a
+    /// [`Resettable`](crate::adapters::Resettable) read by another observer.
+    /// Gauges and up-down counters are always observed as they are.
+    ///
+    /// This does not pick the temporality of the export: the reader of the
+    /// meter provider does, reporting the totals as they are or as their
+    /// change since its previous collection.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let observer = OtelObserver::new("myapp").with_reset_smoothing(true);
+    /// ```
+    pub fn with_reset_smoothing(mut self, enabled: bool) -> Self {
+        self.reset_smoothing = enabled;
+        self
+    }
+
     /// Builds the description string for a metric.
-    fn build_description(&self, name: &str) -> String {
-        match &self.description_prefix {
-            Some(prefix) => format!("{}: {}", prefix, name),
-            None => format!("{} metric", name),
+    ///
+    /// Uses the counter's own description when it has one.
+    fn build_description(&self, name: &str, description: Option<&str>) -> String {
+        match (&self.description_prefix, description) {
+            (Some(prefix), Some(description)) => format!("{}: {}", prefix, description),
+            (Some(prefix), None) => format!("{}: {}", prefix, name),
+            (None, Some(description)) => description.to_string(),
+            (None, None) => format!("{} metric", name),
         }
     }
 
     /// Registers all counters with OpenTelemetry.
     ///
-    /// Each counter is registered as an observable instrument based on its
-    /// [`metric_kind()`](Observable::metric_kind):
+    /// Each counter is registered as an instrument based on its
+    /// [`metric_kind()`](Observable::metric_kind) and the type of its
+    /// [`value()`](Observable::value), keeping integers as integers:
     ///
-    /// - [`MetricKind::Counter`] → `ObservableCounter` (monotonically increasing)
-    /// - [`MetricKind::Gauge`] → `ObservableGauge` (can go up or down)
-    /// - [`MetricKind::Histogram`] → `ObservableGauge` (treated as gauge)
+    /// - [`CounterValue::Signed`] and
+    ///   [`is_additive()`](Observable::is_additive) →
+    ///   `ObservableUpDownCounter<i64>`
+    /// - [`MetricKind::Counter`] → `ObservableCounter<u64>` (or `<f64>` for
+    ///   float values), observing the running total of the increments with
+    ///   [reset smoothing](Self::with_reset_smoothing)
+    /// - anything else → `ObservableGauge<u64>` (or `<i64>` or `<f64>`,
+    ///   following the value)
+    ///
+    /// OpenTelemetry has no asynchronous histogram, nor a way to record
+    /// aggregated buckets, so [`MetricKind::Histogram`] counters with a
+    /// [`distribution()`](Observable::distribution) are rejected: export them
+    /// with the [`otlp`](super::otlp) exporter, which sends their buckets as
+    /// they are.
     ///
     /// For labeled groups, the labels from [`expand()`](Observable::expand)
-    /// are automatically converted to OpenTelemetry attributes.
+    /// are automatically converted to OpenTelemetry attributes. The counter's
+    /// [`description()`](Observable::description) and [`unit()`](Observable::unit)
+    /// (as a UCUM code) are set on the instrument.
     ///
     /// # Example
     ///
@@ -167,81 +231,637 @@ impl OtelObserver {
     /// ```
     pub fn register(&self, counters: &[&'static (dyn Observable + Send + Sync)]) -> Result<()> {
         for &counter in counters {
-            self.register_one(counter)?;
+            self.register_one(counter, Source::Static(counter, RunningTotals::default()))?;
         }
         Ok(())
     }
 
-    /// Registers a single counter based on its metric kind.
-    fn register_one(&self, counter: &'static (dyn Observable + Send + Sync)) -> Result<()> {
-        match counter.metric_kind() {
-            MetricKind::Counter => self.register_counter(counter),
-            MetricKind::Gauge | MetricKind::Histogram => self.register_gauge(counter),
+    /// Registers counters owned elsewhere, holding only weak references.
+    ///
+    /// Instruments are mapped as in [`register`](Self::register), when the
+    /// first counter of a name is registered. Its callback observes every
+    /// counter of that name still alive and registered: dropping the
+    /// returned [`Registration`], or
+    /// [unregistering](Registration::unregister) it, removes its counters
+    /// from the callbacks. OpenTelemetry keeps the instruments and their
+    /// callbacks until the meter provider shuts down.
+    ///
+    /// Counters sharing a name feed the same instrument, so give them
+    /// distinct labels (e.g. with [`Family`](crate::adapters::Family)).
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use contatori::counters::monotone::Monotone;
+    /// use contatori::counters::Observable;
+    /// use contatori::observers::opentelemetry::OtelObserver;
+    /// use std::sync::Arc;
+    ///
+    /// let bytes: Arc<dyn Observable + Send + Sync> =
+    ///     Arc::new(Monotone::new().with_name("connection_bytes"));
+    ///
+    /// let observer = OtelObserver::new("myapp");
+    /// let registration = observer.register_shared(&[bytes.clone()])?;
+    ///
+    /// // When the connection closes, its counters are no longer exported
+    /// drop(registration);
+    /// ```
+    pub fn register_shared(
+        &self,
+        counters: &[Arc<dyn Observable + Send + Sync>],
+    ) -> Result<Registration> {
+        let mut registration = Registration {
+            id: self.next_registration.fetch_add(1, Ordering::Relaxed),
+            members: Vec::new(),
+        };
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        for counter in counters {
+            let members = match shared.get(counter.name()) {
+                Some(members) => Arc::clone(members),
+                None => {
+                    let members = Arc::<Members>::default();
+                    self.register_one(counter.as_ref(), Source::Shared(Arc::clone(&members)))?;
+                    shared.insert(counter.name().to_string(), Arc::clone(&members));
+                    members
+                }
+            };
+            members
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(Member {
+                    registration: registration.id,
+                    counter: Arc::downgrade(counter),
+                    totals: RunningTotals::default(),
+                });
+            if !registration
+                .members
+                .iter()
+                .any(|m| Arc::ptr_eq(m, &members))
+            {
+                registration.members.push(members);
+            }
         }
+        Ok(registration)
     }
 
-    /// Registers an observable counter (monotonically increasing).
-    fn register_counter(&self, counter: &'static (dyn Observable + Send + Sync)) -> Result<()> {
+    /// Registers a single counter based on its metric kind and value type.
+    fn register_one(&self, counter: &dyn Observable, source: Source) -> Result<()> {
         let name = counter.name();
         if name.is_empty() {
             return Err(OtelError::MetricError("counter must have a name".into()).into());
         }
+        let name = name.to_string();
 
-        let description = self.build_description(name);
-
-        let _ = self
-            .meter
-            .u64_observable_counter(name)
-            .with_description(description)
-            .with_callback(move |observer| {
-                for entry in counter.expand() {
-                    let attributes = entry_to_attributes(&entry);
-                    observer.observe(entry.value.as_u64(), &attributes);
-                }
-            })
-            .build();
+        if counter.metric_kind() == MetricKind::Histogram
+            && counter.expand().iter().any(|e| e.distribution.is_some())
+        {
+            let message = format!("histogram {name} is not supported, use the otlp exporter");
+            return Err(OtelError::MetricError(message).into());
+        }
 
+        let meter = &self.meter;
+        let smooth = self.reset_smoothing;
+        match (counter.metric_kind(), counter.value()) {
+            (_, CounterValue::Signed(_)) if counter.is_additive() => {
+                let builder = meter.i64_observable_up_down_counter(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_i64())
+                    .build();
+            }
+            (MetricKind::Counter, CounterValue::Float(_)) if smooth => {
+                let builder = meter.f64_observable_counter(name);
+                self.configure(builder, counter, source, |totals, entry| {
+                    totals.add(entry).as_f64()
+                })
+                .build();
+            }
+            (MetricKind::Counter, _) if smooth => {
+                let builder = meter.u64_observable_counter(name);
+                self.configure(builder, counter, source, |totals, entry| {
+                    totals.add(entry).as_u64()
+                })
+                .build();
+            }
+            (MetricKind::Counter, CounterValue::Float(_)) => {
+                let builder = meter.f64_observable_counter(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_f64())
+                    .build();
+            }
+            (MetricKind::Counter, _) => {
+                let builder = meter.u64_observable_counter(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_u64())
+                    .build();
+            }
+            (_, CounterValue::Unsigned(_)) => {
+                let builder = meter.u64_observable_gauge(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_u64())
+                    .build();
+            }
+            (_, CounterValue::Signed(_)) => {
+                let builder = meter.i64_observable_gauge(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_i64())
+                    .build();
+            }
+            (_, CounterValue::Float(_)) => {
+                let builder = meter.f64_observable_gauge(name);
+                self.configure(builder, counter, source, |_, entry| entry.value.as_f64())
+                    .build();
+            }
+        }
         Ok(())
     }
 
-    /// Registers an observable gauge (can go up or down).
-    fn register_gauge(&self, counter: &'static (dyn Observable + Send + Sync)) -> Result<()> {
-        let name = counter.name();
-        if name.is_empty() {
-            return Err(OtelError::MetricError("counter must have a name".into()).into());
+    /// Sets the counter's description and unit on an observable instrument,
+    /// with a callback observing every entry converted by `value`, given the
+    /// running totals of its counter.
+    fn configure<'a, I, M: 'static>(
+        &self,
+        builder: AsyncInstrumentBuilder<'a, I, M>,
+        counter: &dyn Observable,
+        source: Source,
+        value: impl Fn(&RunningTotals, &ObservableEntry) -> M + Send + Sync + 'static,
+    ) -> AsyncInstrumentBuilder<'a, I, M> {
+        let description = self.build_description(counter.name(), counter.description());
+        let mut builder = builder.with_description(description);
+        if let Some(unit) = counter.unit() {
+            builder = builder.with_unit(unit.ucum());
         }
 
-        let description = self.build_description(name);
-
-        // Use f64 gauge to support all value types (unsigned, signed, float)
-        let _ = self
-            .meter
-            .f64_observable_gauge(name)
-            .with_description(description)
-            .with_callback(move |observer| {
+        builder.with_callback(move |observer| {
+            source.for_each(|counter, totals| {
                 for entry in counter.expand() {
                     let attributes = entry_to_attributes(&entry);
-                    observer.observe(entry.value.as_f64(), &attributes);
+                    observer.observe(value(totals, &entry), &attributes);
                 }
             })
-            .build();
+        })
+    }
+}
 
-        Ok(())
+/// Counters registered with [`OtelObserver::register_shared`] under one
+/// instrument name.
+type Members = Mutex<Vec<Member>>;
+
+/// A counter registered with [`OtelObserver::register_shared`].
+#[derive(Debug)]
+struct Member {
+    /// Identifier of its [`Registration`].
+    registration: u64,
+    counter: Weak<dyn Observable + Send + Sync>,
+    /// Forgotten with the member, when it is unregistered.
+    totals: RunningTotals,
+}
+
+/// Handle to counters registered with
+/// [`OtelObserver::register_shared`].
+///
+/// Dropping the handle unregisters the counters: the callbacks of their
+/// instruments stop observing them, even if the counters are still alive.
+#[derive(Debug)]
+#[must_use = "dropping a Registration unregisters its counters"]
+pub struct Registration {
+    id: u64,
+    members: Vec<Arc<Members>>,
+}
+
+impl Registration {
+    /// Unregisters the counters; the same as dropping the handle.
+    pub fn unregister(self) {}
+}
+
+impl Drop for Registration {
+    /// Removes the counters of this registration, and those that have been
+    /// dropped, from their instruments.
+    fn drop(&mut self) {
+        for members in &self.members {
+            let mut members = members.lock().unwrap_or_else(|e| e.into_inner());
+            members.retain(|m| m.registration != self.id && m.counter.strong_count() > 0);
+        }
     }
 }
 
-/// Converts an [`ObservableEntry`]'s label to OpenTelemetry [`KeyValue`] attributes.
+/// Counters read by an instrument callback.
+enum Source {
+    /// A counter living for the whole program.
+    Static(&'static (dyn Observable + Send + Sync), RunningTotals),
+    /// Counters owned elsewhere, read while alive and registered.
+    Shared(Arc<Members>),
+}
+
+impl Source {
+    /// Calls `observe` with every counter that is alive and registered, and
+    /// its running totals.
+    fn for_each(&self, mut observe: impl FnMut(&dyn Observable, &RunningTotals)) {
+        match self {
+            Source::Static(counter, totals) => observe(*counter, totals),
+            Source::Shared(members) => {
+                let members = members.lock().unwrap_or_else(|e| e.into_inner());
+                for member in members.iter() {
+                    if let Some(counter) = member.counter.upgrade() {
+                        observe(counter.as_ref(), &member.totals);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Converts an [`ObservableEntry`]'s labels to OpenTelemetry [`KeyValue`] attributes.
 fn entry_to_attributes(entry: &ObservableEntry) -> Vec<KeyValue> {
-    match &entry.label {
-        Some((key, value)) => vec![KeyValue::new(*key, *value)],
-        None => vec![],
+    entry
+        .labels
+        .iter()
+        .map(|(key, value)| KeyValue::new(*key, value.clone()))
+        .collect()
+}
+
+/// Running totals of the increments of the entries of one counter, for
+/// [reset smoothing](OtelObserver::with_reset_smoothing).
+///
+/// Kept per counter rather than per entry name, so that counters sharing a
+/// name and labels do not read each other's values as resets.
+#[derive(Debug, Default)]
+struct RunningTotals {
+    last_seen: DeltaTracker,
+    totals: Mutex<HashMap<String, CounterValue>>,
+}
+
+impl RunningTotals {
+    /// Adds the increment of `entry` since the previous call to its running
+    /// total, and returns the total.
+    ///
+    /// A counter that goes down was reset, and its value is all increment.
+    fn add(&self, entry: &ObservableEntry) -> CounterValue {
+        let key = entry_key(
+            &entry.name,
+            entry.labels.iter().map(|(k, v)| (*k, v.as_ref())),
+        );
+        let mut increment = entry.value;
+        self.last_seen
+            .update(key.clone(), &mut increment, &mut None);
+        let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        let total = totals.entry(key).or_insert(match increment {
+            CounterValue::Float(_) => CounterValue::Float(0.0),
+            _ => CounterValue::Unsigned(0),
+        });
+        *total = match *total {
+            CounterValue::Float(total) => CounterValue::Float(total + increment.as_f64()),
+            total => CounterValue::Unsigned(total.as_u64().wrapping_add(increment.as_u64())),
+        };
+        *total
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
     use crate::counters::unsigned::Unsigned;
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::data::{Gauge, ResourceMetrics, Sum};
+    use opentelemetry_sdk::metrics::reader::MetricReader;
+    use opentelemetry_sdk::metrics::{
+        InstrumentKind, ManualReader, MetricResult, Pipeline, SdkMeterProvider,
+        Temporality as SdkTemporality,
+    };
+    use opentelemetry_sdk::Resource;
+
+    /// Shares a [`ManualReader`] between the provider and the test.
+    #[derive(Debug, Clone)]
+    struct SharedReader(Arc<ManualReader>);
+
+    impl MetricReader for SharedReader {
+        fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+            self.0.register_pipeline(pipeline)
+        }
+
+        fn collect(&self, rm: &mut ResourceMetrics) -> MetricResult<()> {
+            self.0.collect(rm)
+        }
+
+        fn force_flush(&self) -> MetricResult<()> {
+            self.0.force_flush()
+        }
+
+        fn shutdown(&self) -> MetricResult<()> {
+            self.0.shutdown()
+        }
+
+        fn temporality(&self, kind: InstrumentKind) -> SdkTemporality {
+            self.0.temporality(kind)
+        }
+    }
+
+    /// Returns an observer on a private meter provider, and its reader.
+    fn observer() -> (OtelObserver, SharedReader, SdkMeterProvider) {
+        observer_with(SdkTemporality::Cumulative)
+    }
+
+    /// Returns an observer on a private meter provider whose reader exports
+    /// the given temporality.
+    fn observer_with(
+        temporality: SdkTemporality,
+    ) -> (OtelObserver, SharedReader, SdkMeterProvider) {
+        let reader = ManualReader::builder()
+            .with_temporality(temporality)
+            .build();
+        let reader = SharedReader(Arc::new(reader));
+        let provider = SdkMeterProvider::builder()
+            .with_reader(reader.clone())
+            .build();
+        let observer = OtelObserver::with_meter(provider.meter("test"));
+        (observer, reader, provider)
+    }
+
+    /// A data point, by instrument type.
+    #[derive(Debug, PartialEq)]
+    enum Point {
+        Counter(u64),
+        UpDownCounter(i64),
+        Gauge(u64),
+        SignedGauge(i64),
+    }
+
+    /// Collects every data point as `(name, point)`.
+    fn collect(reader: &SharedReader) -> Vec<(String, Point)> {
+        let mut rm = ResourceMetrics {
+            resource: Resource::empty(),
+            scope_metrics: Vec::new(),
+        };
+        reader.collect(&mut rm).unwrap();
+
+        let mut points = Vec::new();
+        for metric in rm.scope_metrics.iter().flat_map(|s| &s.metrics) {
+            let data = metric.data.as_any();
+            let values: Vec<Point> = if let Some(sum) = data.downcast_ref::<Sum<u64>>() {
+                assert!(sum.is_monotonic);
+                sum.data_points
+                    .iter()
+                    .map(|p| Point::Counter(p.value))
+                    .collect()
+            } else if let Some(sum) = data.downcast_ref::<Sum<i64>>() {
+                assert!(!sum.is_monotonic);
+                sum.data_points
+                    .iter()
+                    .map(|p| Point::UpDownCounter(p.value))
+                    .collect()
+            } else if let Some(gauge) = data.downcast_ref::<Gauge<u64>>() {
+                gauge
+                    .data_points
+                    .iter()
+                    .map(|p| Point::Gauge(p.value))
+                    .collect()
+            } else if let Some(gauge) = data.downcast_ref::<Gauge<i64>>() {
+                gauge
+                    .data_points
+                    .iter()
+                    .map(|p| Point::SignedGauge(p.value))
+                    .collect()
+            } else {
+                panic!("unexpected aggregation for {}", metric.name)
+            };
+            points.extend(values.into_iter().map(|v| (metric.name.to_string(), v)));
+        }
+        points.sort_by(|a, b| a.0.cmp(&b.0));
+        points
+    }
+
+    #[test]
+    fn test_register_shared_observes_while_registered() {
+        let (observer, reader, _provider) = observer();
+        let requests = Arc::new(Monotone::new().with_name("shared_requests"));
+        let queue = Arc::new(Unsigned::new().with_name("shared_queue"));
+        requests.add(3);
+        queue.add(2);
+
+        let registration = observer
+            .register_shared(&[requests.clone(), queue.clone()])
+            .unwrap();
+        requests.add(1);
+        assert_eq!(
+            collect(&reader),
+            [
+                ("shared_queue".to_string(), Point::Gauge(2)),
+                ("shared_requests".to_string(), Point::Counter(4))
+            ]
+        );
+
+        // The SDK keeps reporting the last cumulative values, which no longer
+        // follow the counters once unregistered.
+        registration.unregister();
+        requests.add(1);
+        queue.add(1);
+        assert_eq!(
+            collect(&reader),
+            [
+                ("shared_queue".to_string(), Point::Gauge(2)),
+                ("shared_requests".to_string(), Point::Counter(4))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_shared_reuses_instruments() {
+        let (observer, reader, _provider) = observer();
+        let members = |name: &str| {
+            let shared = observer.shared.lock().unwrap();
+            let members = shared[name].lock().unwrap();
+            members.len()
+        };
+        let queue = Unsigned::new().with_name("tenant_queue");
+        queue.add(1);
+        let first: Arc<dyn Observable + Send + Sync> = Arc::new(queue);
+        let second = Arc::new(Unsigned::new().with_name("tenant_queue"));
+
+        let registration = observer
+            .register_shared(std::slice::from_ref(&first))
+            .unwrap();
+        let other = observer.register_shared(&[second]).unwrap();
+        assert_eq!(observer.shared.lock().unwrap().len(), 1);
+        assert_eq!(members("tenant_queue"), 2);
+
+        drop(other);
+        assert_eq!(members("tenant_queue"), 1);
+        assert_eq!(
+            collect(&reader),
+            [("tenant_queue".to_string(), Point::Gauge(1))]
+        );
+
+        // Dropped counters are pruned along with the registration
+        drop(first);
+        drop(registration);
+        assert_eq!(members("tenant_queue"), 0);
+    }
+
+    #[test]
+    fn test_register_shared_skips_dropped_counters() {
+        let (observer, reader, _provider) = observer();
+        let kept = Arc::new(Unsigned::new().with_name("kept"));
+        let dropped = Arc::new(Unsigned::new().with_name("dropped"));
+        kept.add(1);
+        dropped.add(1);
+
+        let _registration = observer
+            .register_shared(&[kept.clone(), dropped.clone()])
+            .unwrap();
+        drop(dropped);
+
+        assert_eq!(collect(&reader), [("kept".to_string(), Point::Gauge(1))]);
+    }
+
+    #[test]
+    fn test_register_keeps_integer_types() {
+        static REQUESTS: Monotone = Monotone::new().with_name("typed_requests");
+        static QUEUE: Unsigned = Unsigned::new().with_name("typed_queue");
+        static BALANCE: Signed = Signed::new().with_name("typed_balance");
+
+        let (observer, reader, _provider) = observer();
+        observer.register(&[&REQUESTS, &QUEUE, &BALANCE]).unwrap();
+        REQUESTS.add(1 << 40);
+        QUEUE.add(7);
+        BALANCE.sub(3);
+
+        assert_eq!(
+            collect(&reader),
+            [
+                ("typed_balance".to_string(), Point::UpDownCounter(-3)),
+                ("typed_queue".to_string(), Point::Gauge(7)),
+                ("typed_requests".to_string(), Point::Counter(1 << 40)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_register_histogram_is_rejected() {
+        static LATENCY: Histogram<3> = Histogram::new(&[1.0, 2.0, 5.0]).with_name("typed_latency");
+
+        let (observer, reader, _provider) = observer();
+        let err = observer.register(&[&LATENCY]).unwrap_err();
+        assert!(err.to_string().contains("otlp"));
+        LATENCY.observe(0.5);
+        assert_eq!(collect(&reader), []);
+    }
+
+    #[test]
+    fn test_register_signed_gauge() {
+        /// A signed measurement, which does not add up.
+        #[derive(Debug)]
+        struct Temperature;
+
+        impl Observable for Temperature {
+            fn name(&self) -> &str {
+                "temperature"
+            }
+
+            fn value(&self) -> CounterValue {
+                CounterValue::Signed(-5)
+            }
+        }
+
+        let (observer, reader, _provider) = observer();
+        let temperature: Arc<dyn Observable + Send + Sync> = Arc::new(Temperature);
+        let _registration = observer
+            .register_shared(std::slice::from_ref(&temperature))
+            .unwrap();
+        assert_eq!(
+            collect(&reader),
+            [("temperature".to_string(), Point::SignedGauge(-5))]
+        );
+    }
+
+    #[test]
+    fn test_reset_smoothing_with_delta_reader() {
+        static REQUESTS: Monotone = Monotone::new().with_name("delta_requests");
+        static QUEUE: Unsigned = Unsigned::new().with_name("delta_queue");
+
+        let (observer, reader, _provider) = observer_with(SdkTemporality::Delta);
+        let observer = observer.with_reset_smoothing(true);
+        observer.register(&[&REQUESTS, &QUEUE]).unwrap();
+
+        REQUESTS.add(5);
+        QUEUE.add(5);
+        assert_eq!(
+            collect(&reader),
+            [
+                ("delta_queue".to_string(), Point::Gauge(5)),
+                ("delta_requests".to_string(), Point::Counter(5)),
+            ]
+        );
+
+        // Counters report increments, gauges their value
+        REQUESTS.add(2);
+        assert_eq!(
+            collect(&reader),
+            [
+                ("delta_queue".to_string(), Point::Gauge(5)),
+                ("delta_requests".to_string(), Point::Counter(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reset_smoothing_with_cumulative_reader() {
+        static REQUESTS: Monotone = Monotone::new().with_name("fed_requests");
+
+        let (observer, reader, _provider) = observer();
+        let observer = observer.with_reset_smoothing(true);
+        observer.register(&[&REQUESTS]).unwrap();
+
+        REQUESTS.add(5);
+        assert_eq!(
+            collect(&reader),
+            [("fed_requests".to_string(), Point::Counter(5))]
+        );
+        REQUESTS.add(2);
+        assert_eq!(
+            collect(&reader),
+            [("fed_requests".to_string(), Point::Counter(7))]
+        );
+    }
+
+    #[test]
+    fn test_reset_smoothing_keeps_same_named_counters_apart() {
+        let (observer, reader, _provider) = observer();
+        let observer = observer.with_reset_smoothing(true);
+        let a = Arc::new(Monotone::new().with_name("conn_bytes"));
+        let b = Arc::new(Monotone::new().with_name("conn_bytes"));
+        let registration = observer
+            .register_shared(&[a.clone(), b.clone() as Arc<dyn Observable + Send + Sync>])
+            .unwrap();
+
+        // The last observation of an attribute set wins, and each counter
+        // keeps its own total instead of reading the other as a reset
+        a.add(10);
+        b.add(3);
+        assert_eq!(
+            collect(&reader),
+            [("conn_bytes".to_string(), Point::Counter(3))]
+        );
+        a.add(1);
+        b.add(1);
+        assert_eq!(
+            collect(&reader),
+            [("conn_bytes".to_string(), Point::Counter(4))]
+        );
+
+        drop(registration);
+        let _registration = observer
+            .register_shared(&[a.clone() as Arc<dyn Observable + Send + Sync>])
+            .unwrap();
+        a.add(1);
+        assert_eq!(
+            collect(&reader),
+            [("conn_bytes".to_string(), Point::Counter(12))]
+        );
+    }
+
+    #[test]
+    fn test_register_shared_unnamed_counter_fails() {
+        let (observer, _reader, _provider) = observer();
+        let unnamed: Arc<dyn Observable + Send + Sync> = Arc::new(Unsigned::new());
+        assert!(observer.register_shared(&[unnamed]).is_err());
+    }
 
     #[test]
     fn test_otel_observer_new() {
@@ -258,35 +878,93 @@ mod tests {
     #[test]
     fn test_build_description_with_prefix() {
         let observer = OtelObserver::new("test").with_description_prefix("My App");
-        assert_eq!(observer.build_description("requests"), "My App: requests");
+        assert_eq!(
+            observer.build_description("requests", None),
+            "My App: requests"
+        );
     }
 
     #[test]
     fn test_build_description_without_prefix() {
         let observer = OtelObserver::new("test");
-        assert_eq!(observer.build_description("requests"), "requests metric");
+        assert_eq!(
+            observer.build_description("requests", None),
+            "requests metric"
+        );
+    }
+
+    #[test]
+    fn test_build_description_from_counter() {
+        let observer = OtelObserver::new("test");
+        assert_eq!(
+            observer.build_description("requests", Some("Requests served")),
+            "Requests served"
+        );
+
+        let observer = observer.with_description_prefix("My App");
+        assert_eq!(
+            observer.build_description("requests", Some("Requests served")),
+            "My App: Requests served"
+        );
+    }
+
+    #[test]
+    fn test_register_with_metadata() {
+        use crate::counters::monotone::Monotone;
+        use crate::counters::Unit;
+
+        static SENT: Monotone = Monotone::new()
+            .with_name("bytes_sent")
+            .with_help("Bytes written to the socket")
+            .with_unit(Unit::Bytes);
+
+        let observer = OtelObserver::new("test");
+        assert!(observer.register(&[&SENT]).is_ok());
     }
 
     #[test]
     fn test_entry_to_attributes_with_label() {
         let entry = ObservableEntry {
-            name: "test",
-            label: Some(("method", "GET")),
+            name: "test".into(),
+            labels: vec![("method", "GET".into())],
             value: crate::counters::CounterValue::Unsigned(1),
             metric_kind: MetricKind::Counter,
+            distribution: None,
         };
         let attrs = entry_to_attributes(&entry);
         assert_eq!(attrs.len(), 1);
         assert_eq!(attrs[0].key.as_str(), "method");
     }
 
+    #[test]
+    fn test_entry_to_attributes_with_multiple_labels() {
+        let entry = ObservableEntry {
+            name: "test".into(),
+            labels: vec![
+                ("method", "GET".into()),
+                ("route", "/users".into()),
+                ("status", "200".into()),
+            ],
+            value: crate::counters::CounterValue::Unsigned(1),
+            metric_kind: MetricKind::Counter,
+            distribution: None,
+        };
+        let attrs = entry_to_attributes(&entry);
+        assert_eq!(attrs.len(), 3);
+        assert_eq!(attrs[0].key.as_str(), "method");
+        assert_eq!(attrs[1].key.as_str(), "route");
+        assert_eq!(attrs[2].key.as_str(), "status");
+        assert_eq!(attrs[2].value.as_str(), "200");
+    }
+
     #[test]
     fn test_entry_to_attributes_without_label() {
         let entry = ObservableEntry {
-            name: "test",
-            label: None,
+            name: "test".into(),
+            labels: vec![],
             value: crate::counters::CounterValue::Unsigned(1),
             metric_kind: MetricKind::Counter,
+            distribution: None,
         };
         let attrs = entry_to_attributes(&entry);
         assert!(attrs.is_empty());
@@ -300,4 +978,4 @@ mod tests {
         let result = observer.register(counters);
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+}
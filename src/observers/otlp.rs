@@ -0,0 +1,806 @@
+//! OTLP/HTTP protobuf exporter, without the OpenTelemetry SDK.
+//!
+//! This module provides [`OtlpExporter`], which encodes a [`MetricsSnapshot`]
+//! directly into an OTLP `ExportMetricsServiceRequest` protobuf message and
+//! posts it to a collector's `/v1/metrics` endpoint. Unlike
+//! [`OtelObserver`](super::opentelemetry::OtelObserver), it needs no
+//! `MeterProvider` and no `'static` counters, which suits small agents.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `otlp` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["otlp"] }
+//! ```
+//!
+//! # Mapping
+//!
+//! Snapshot entries sharing a name become the data points of one OTLP
+//! metric, their labels becoming attributes. The [`MetricKind`] of an entry
+//! picks the OTLP data type:
+//!
+//! | Kind | OTLP data |
+//! |------|-----------|
+//! | [`MetricKind::Counter`] | monotonic `Sum` |
+//! | [`MetricKind::Gauge`] | `Gauge` |
+//! | [`MetricKind::Histogram`] | `Histogram` with explicit bounds |
+//! | [`MetricKind::Summary`] | `Summary` |
+//!
+//! Histograms and summaries without a distribution are exported as gauges.
+//! Integer values are sent as `as_int` (unsigned values above `i64::MAX` are
+//! clamped), floating point values as `as_double`.
+//!
+//! Sums and histograms follow the exporter's [`Temporality`]: cumulative
+//! since the exporter was created, or the delta since the previous export.
+//! Gauges and summaries are always sent as they are.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::otlp::{OtlpExporter, Temporality};
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("http_requests");
+//!
+//! let exporter = OtlpExporter::new("http://localhost:4318")?
+//!     .with_service_name("my_agent")
+//!     .with_temporality(Temporality::Delta);
+//!
+//! REQUESTS.add(10);
+//! exporter.export_counters([&REQUESTS as &dyn Observable].into_iter())?;
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::delta::{entry_key, DeltaTracker, Pending};
+use super::http_client::{self, Url};
+use super::{Observer, Result};
+use crate::counters::{CounterValue, Distribution, MetricKind, Observable};
+use crate::snapshot::{CounterSnapshot, MetricsSnapshot};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub use super::Temporality;
+
+/// Content type of OTLP/HTTP protobuf requests.
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Path of the metrics endpoint, used when the URL has none.
+pub const DEFAULT_PATH: &str = "/v1/metrics";
+
+/// Returns the OTLP `AggregationTemporality` value of `temporality`.
+fn aggregation_temporality(temporality: Temporality) -> u64 {
+    match temporality {
+        Temporality::Delta => 1,
+        Temporality::Cumulative => 2,
+    }
+}
+
+/// Exporter encoding snapshots as OTLP protobuf and posting them over HTTP.
+///
+/// With [`Temporality::Delta`], [`encode`](Self::encode) and successful
+/// [`export`](Self::export) calls are the exports closing intervals.
+#[derive(Debug)]
+pub struct OtlpExporter {
+    /// Collector URL.
+    url: Url,
+    /// Request path.
+    path: String,
+    /// Resource attributes.
+    resource: Vec<(String, String)>,
+    /// Extra request headers.
+    headers: Vec<(String, String)>,
+    /// Temporality of sums and histograms.
+    temporality: Temporality,
+    /// Connection and I/O timeout.
+    timeout: Duration,
+    /// Start of cumulative intervals.
+    start_time: SystemTime,
+    /// End of the last delta interval.
+    last_export: Mutex<SystemTime>,
+    /// Last values exported, for delta temporality.
+    last_seen: DeltaTracker,
+}
+
+impl OtlpExporter {
+    /// Creates an exporter posting to the collector at `url`.
+    ///
+    /// A URL without a path, such as `http://localhost:4318`, posts to
+    /// [`DEFAULT_PATH`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ObserverError::Metric`](super::ObserverError::Metric) if the
+    /// URL is not a valid `http://` URL.
+    pub fn new(url: &str) -> Result<Self> {
+        let url = Url::parse(url)?;
+        let path = if url.path.is_empty() {
+            DEFAULT_PATH.to_string()
+        } else {
+            url.path.clone()
+        };
+        let now = SystemTime::now();
+        Ok(Self {
+            url,
+            path,
+            resource: Vec::new(),
+            headers: Vec::new(),
+            temporality: Temporality::Cumulative,
+            timeout: Duration::from_secs(10),
+            start_time: now,
+            last_export: Mutex::new(now),
+            last_seen: DeltaTracker::default(),
+        })
+    }
+
+    /// Sets the `service.name` resource attribute.
+    pub fn with_service_name(self, name: &str) -> Self {
+        self.with_resource_attribute("service.name", name)
+    }
+
+    /// Sets a resource attribute, replacing any previous value for `key`.
+    pub fn with_resource_attribute(mut self, key: &str, value: &str) -> Self {
+        self.resource.retain(|(k, _)| k != key);
+        self.resource.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a header to every request, e.g. for authentication.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the temporality of sums and histograms (default:
+    /// [`Temporality::Cumulative`]).
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Sets the connection and I/O timeout (default: 10 seconds).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the temporality of sums and histograms.
+    pub fn temporality(&self) -> Temporality {
+        self.temporality
+    }
+
+    /// Encodes `snapshot` as an `ExportMetricsServiceRequest`.
+    ///
+    /// Data points are stamped with the snapshot timestamp, or the current
+    /// time if it has none.
+    pub fn encode(&self, snapshot: &MetricsSnapshot) -> Vec<u8> {
+        let mut pending = Pending::default();
+        let (request, time) = self.stage(snapshot, &mut pending);
+        self.commit(time, pending);
+        request
+    }
+
+    /// Encodes `snapshot` without closing the delta interval; the values
+    /// exported are added to `pending` and the end of the interval returned,
+    /// to be [committed](Self::commit) once the request is accepted.
+    fn stage(&self, snapshot: &MetricsSnapshot, pending: &mut Pending) -> (Vec<u8>, SystemTime) {
+        let time = snapshot
+            .timestamp_ms
+            .map(|ms| UNIX_EPOCH + Duration::from_millis(ms))
+            .unwrap_or_else(SystemTime::now);
+
+        let (start, counters) = match self.temporality {
+            Temporality::Cumulative => (self.start_time, snapshot.counters.clone()),
+            Temporality::Delta => {
+                let start = *self.last_export.lock().unwrap_or_else(|e| e.into_inner());
+                let counters = snapshot
+                    .counters
+                    .iter()
+                    .cloned()
+                    .map(|mut counter| {
+                        if matches!(counter.kind, MetricKind::Counter | MetricKind::Histogram) {
+                            let key = entry_key(
+                                &counter.name,
+                                counter.labels.iter().map(|(k, v)| (k.as_str(), v.as_str())),
+                            );
+                            self.last_seen.stage(
+                                key,
+                                &mut counter.value,
+                                &mut counter.distribution,
+                                pending,
+                            );
+                        }
+                        counter
+                    })
+                    .collect();
+                (start, counters)
+            }
+        };
+
+        let times = (unix_nanos(start), unix_nanos(time));
+        let mut request = Vec::new();
+        put_message(&mut request, 1, |resource_metrics| {
+            put_message(resource_metrics, 1, |resource| {
+                for (key, value) in &self.resource {
+                    put_attribute(resource, 1, key, value);
+                }
+            });
+            put_message(resource_metrics, 2, |scope_metrics| {
+                put_message(scope_metrics, 1, |scope| {
+                    put_string(scope, 1, env!("CARGO_PKG_NAME"));
+                    put_string(scope, 2, env!("CARGO_PKG_VERSION"));
+                });
+                for metric in group(&counters) {
+                    put_message(scope_metrics, 2, |out| self.put_metric(out, &metric, times));
+                }
+            });
+        });
+        (request, time)
+    }
+
+    /// Closes the delta interval at `time`, remembering the values of
+    /// `pending` as exported.
+    fn commit(&self, time: SystemTime, pending: Pending) {
+        if self.temporality == Temporality::Delta {
+            *self.last_export.lock().unwrap_or_else(|e| e.into_inner()) = time;
+            self.last_seen.commit(pending);
+        }
+    }
+
+    /// Encodes and posts `snapshot` to the collector.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ObserverError::Io`](super::ObserverError::Io) if the
+    /// collector cannot be reached, or
+    /// [`ObserverError::Http`](super::ObserverError::Http) if it answers
+    /// with a non-success status. The delta interval is then left open, so
+    /// the next export covers it.
+    pub fn export(&self, snapshot: &MetricsSnapshot) -> Result<()> {
+        let mut pending = Pending::default();
+        let (body, time) = self.stage(snapshot, &mut pending);
+        let mut headers = vec![("Content-Type", CONTENT_TYPE)];
+        headers.extend(self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        http_client::send(&self.url, "POST", &self.path, &headers, &body, self.timeout)?
+            .error_for_status()?;
+        self.commit(time, pending);
+        Ok(())
+    }
+
+    /// Collects a snapshot of `counters` at the current time and exports it.
+    ///
+    /// # Errors
+    ///
+    /// See [`export`](Self::export).
+    pub fn export_counters<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<()> {
+        let now = unix_nanos(SystemTime::now()) / 1_000_000;
+        self.export(&MetricsSnapshot::collect_with_timestamp(counters, now))
+    }
+
+    /// Writes an OTLP `Metric`.
+    fn put_metric(&self, out: &mut Vec<u8>, metric: &Metric<'_>, (start, time): (u64, u64)) {
+        let first = metric.points[0];
+        put_string(out, 1, &first.name);
+        if let Some(unit) = &first.unit {
+            put_string(out, 3, ucum(unit));
+        }
+        let temporality = aggregation_temporality(self.temporality);
+
+        match metric.shape {
+            Shape::Sum => put_message(out, 7, |sum| {
+                for point in &metric.points {
+                    put_message(sum, 1, |p| put_number_point(p, point, Some(start), time));
+                }
+                put_varint_field(sum, 2, temporality);
+                put_varint_field(sum, 3, 1);
+            }),
+            Shape::Gauge => put_message(out, 5, |gauge| {
+                for point in &metric.points {
+                    put_message(gauge, 1, |p| put_number_point(p, point, None, time));
+                }
+            }),
+            Shape::Histogram => put_message(out, 9, |histogram| {
+                for point in &metric.points {
+                    put_message(histogram, 1, |p| put_histogram_point(p, point, start, time));
+                }
+                put_varint_field(histogram, 2, temporality);
+            }),
+            Shape::Summary => put_message(out, 11, |summary| {
+                for point in &metric.points {
+                    put_message(summary, 1, |p| {
+                        put_summary_point(p, point, self.start_time, time)
+                    });
+                }
+            }),
+        }
+    }
+}
+
+impl Observer for OtlpExporter {
+    fn observe(&mut self, counters: &[&dyn Observable], now: SystemTime) -> Result<()> {
+        let timestamp_ms = unix_nanos(now) / 1_000_000;
+        self.export(&MetricsSnapshot::collect_with_timestamp(
+            counters.iter().copied(),
+            timestamp_ms,
+        ))
+    }
+}
+
+/// OTLP data type of a metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Sum,
+    Gauge,
+    Histogram,
+    Summary,
+}
+
+impl Shape {
+    fn of(counter: &CounterSnapshot) -> Self {
+        match (counter.kind, &counter.distribution) {
+            (MetricKind::Counter, _) => Shape::Sum,
+            (MetricKind::Histogram, Some(_)) => Shape::Histogram,
+            (MetricKind::Summary, Some(_)) => Shape::Summary,
+            _ => Shape::Gauge,
+        }
+    }
+}
+
+/// Entries exported as the data points of one OTLP metric.
+struct Metric<'a> {
+    shape: Shape,
+    points: Vec<&'a CounterSnapshot>,
+}
+
+/// Groups entries by name and shape, in order of first appearance.
+fn group(counters: &[CounterSnapshot]) -> Vec<Metric<'_>> {
+    let mut metrics: Vec<Metric<'_>> = Vec::new();
+    for counter in counters {
+        let shape = Shape::of(counter);
+        match metrics
+            .iter_mut()
+            .find(|m| m.shape == shape && m.points[0].name == counter.name)
+        {
+            Some(metric) => metric.points.push(counter),
+            None => metrics.push(Metric {
+                shape,
+                points: vec![counter],
+            }),
+        }
+    }
+    metrics
+}
+
+/// Returns the UCUM code of a unit stored by its
+/// [`Unit::as_str`](crate::counters::Unit::as_str) name.
+fn ucum(unit: &str) -> &str {
+    match unit {
+        "seconds" => "s",
+        "milliseconds" => "ms",
+        "microseconds" => "us",
+        "nanoseconds" => "ns",
+        "bytes" => "By",
+        "ratio" => "1",
+        "percent" => "%",
+        other => other,
+    }
+}
+
+/// Returns nanoseconds since the Unix epoch.
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// Writes a `NumberDataPoint`.
+fn put_number_point(out: &mut Vec<u8>, point: &CounterSnapshot, start: Option<u64>, time: u64) {
+    for (key, value) in &point.labels {
+        put_attribute(out, 7, key, value);
+    }
+    if let Some(start) = start {
+        put_fixed64(out, 2, start);
+    }
+    put_fixed64(out, 3, time);
+    match point.value {
+        CounterValue::Unsigned(v) => put_fixed64(out, 6, v.min(i64::MAX as u64)),
+        CounterValue::Signed(v) => put_fixed64(out, 6, v as u64),
+        CounterValue::Float(v) => put_fixed64(out, 4, v.to_bits()),
+    }
+}
+
+/// Writes a `HistogramDataPoint`, converting cumulative buckets into
+/// per-bucket counts.
+fn put_histogram_point(out: &mut Vec<u8>, point: &CounterSnapshot, start: u64, time: u64) {
+    let distribution = point
+        .distribution
+        .as_ref()
+        .map_or_else(Distribution::default, Clone::clone);
+    for (key, value) in &point.labels {
+        put_attribute(out, 9, key, value);
+    }
+    put_fixed64(out, 2, start);
+    put_fixed64(out, 3, time);
+    put_fixed64(out, 4, distribution.count);
+    put_fixed64(out, 5, distribution.sum.to_bits());
+
+    let buckets: Vec<(f64, u64)> = distribution
+        .buckets
+        .iter()
+        .copied()
+        .filter(|(bound, _)| bound.is_finite())
+        .collect();
+    let mut counts = Vec::with_capacity(buckets.len() + 1);
+    let mut previous = 0;
+    for &(_, cumulative) in &buckets {
+        counts.push(cumulative.saturating_sub(previous));
+        previous = cumulative;
+    }
+    counts.push(distribution.count.saturating_sub(previous));
+    put_packed_fixed64(out, 6, counts.into_iter());
+    put_packed_fixed64(out, 7, buckets.iter().map(|(bound, _)| bound.to_bits()));
+}
+
+/// Writes a `SummaryDataPoint`.
+fn put_summary_point(out: &mut Vec<u8>, point: &CounterSnapshot, start: SystemTime, time: u64) {
+    let distribution = point
+        .distribution
+        .as_ref()
+        .map_or_else(Distribution::default, Clone::clone);
+    for (key, value) in &point.labels {
+        put_attribute(out, 7, key, value);
+    }
+    put_fixed64(out, 2, unix_nanos(start));
+    put_fixed64(out, 3, time);
+    put_fixed64(out, 4, distribution.count);
+    put_fixed64(out, 5, distribution.sum.to_bits());
+    for (quantile, value) in &distribution.quantiles {
+        put_message(out, 6, |q| {
+            put_fixed64(q, 1, quantile.to_bits());
+            put_fixed64(q, 2, value.to_bits());
+        });
+    }
+}
+
+/// Writes a `KeyValue` with a string value.
+fn put_attribute(out: &mut Vec<u8>, field: u32, key: &str, value: &str) {
+    put_message(out, field, |kv| {
+        put_string(kv, 1, key);
+        put_message(kv, 2, |any| put_string(any, 1, value));
+    });
+}
+
+// Protobuf wire types.
+const WIRE_VARINT: u32 = 0;
+const WIRE_I64: u32 = 1;
+const WIRE_LEN: u32 = 2;
+
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn put_tag(out: &mut Vec<u8>, field: u32, wire_type: u32) {
+    put_varint(out, u64::from(field << 3 | wire_type));
+}
+
+fn put_varint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    put_tag(out, field, WIRE_VARINT);
+    put_varint(out, value);
+}
+
+fn put_fixed64(out: &mut Vec<u8>, field: u32, value: u64) {
+    put_tag(out, field, WIRE_I64);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_bytes(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_tag(out, field, WIRE_LEN);
+    put_varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn put_string(out: &mut Vec<u8>, field: u32, value: &str) {
+    if !value.is_empty() {
+        put_bytes(out, field, value.as_bytes());
+    }
+}
+
+fn put_packed_fixed64(out: &mut Vec<u8>, field: u32, values: impl Iterator<Item = u64>) {
+    let packed: Vec<u8> = values.flat_map(u64::to_le_bytes).collect();
+    if !packed.is_empty() {
+        put_bytes(out, field, &packed);
+    }
+}
+
+/// Writes an embedded message built by `build`.
+fn put_message(out: &mut Vec<u8>, field: u32, build: impl FnOnce(&mut Vec<u8>)) {
+    let mut message = Vec::new();
+    build(&mut message);
+    put_bytes(out, field, &message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+    use crate::observers::http_client::stand_in;
+    use crate::observers::ObserverError;
+    use crate::testing::HttpRequests;
+
+    /// A decoded protobuf field value.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Value {
+        Varint(u64),
+        Fixed(u64),
+        Bytes(Vec<u8>),
+    }
+
+    /// A decoded protobuf message.
+    struct Message(Vec<(u32, Value)>);
+
+    impl Message {
+        fn parse(mut bytes: &[u8]) -> Self {
+            fn varint(bytes: &mut &[u8]) -> u64 {
+                let mut value = 0;
+                for shift in (0..64).step_by(7) {
+                    let byte = bytes[0];
+                    *bytes = &bytes[1..];
+                    value |= u64::from(byte & 0x7f) << shift;
+                    if byte < 0x80 {
+                        break;
+                    }
+                }
+                value
+            }
+            let mut fields = Vec::new();
+            while !bytes.is_empty() {
+                let tag = varint(&mut bytes);
+                let value = match tag & 7 {
+                    0 => Value::Varint(varint(&mut bytes)),
+                    1 => {
+                        let (fixed, rest) = bytes.split_at(8);
+                        bytes = rest;
+                        Value::Fixed(u64::from_le_bytes(fixed.try_into().unwrap()))
+                    }
+                    2 => {
+                        let len = varint(&mut bytes) as usize;
+                        let (data, rest) = bytes.split_at(len);
+                        bytes = rest;
+                        Value::Bytes(data.to_vec())
+                    }
+                    other => panic!("unexpected wire type {}", other),
+                };
+                fields.push(((tag >> 3) as u32, value));
+            }
+            Message(fields)
+        }
+
+        fn all(&self, field: u32) -> Vec<&Value> {
+            self.0
+                .iter()
+                .filter(|(f, _)| *f == field)
+                .map(|(_, v)| v)
+                .collect()
+        }
+
+        fn get(&self, field: u32) -> Option<&Value> {
+            self.all(field).into_iter().next()
+        }
+
+        fn messages(&self, field: u32) -> Vec<Message> {
+            self.all(field)
+                .into_iter()
+                .map(|value| match value {
+                    Value::Bytes(bytes) => Message::parse(bytes),
+                    other => panic!("field {} is not a message: {:?}", field, other),
+                })
+                .collect()
+        }
+
+        fn message(&self, field: u32) -> Message {
+            self.messages(field).remove(0)
+        }
+
+        fn string(&self, field: u32) -> String {
+            match self.get(field) {
+                Some(Value::Bytes(bytes)) => String::from_utf8(bytes.clone()).unwrap(),
+                other => panic!("field {} is not a string: {:?}", field, other),
+            }
+        }
+
+        fn fixed(&self, field: u32) -> u64 {
+            match self.get(field) {
+                Some(Value::Fixed(value)) => *value,
+                other => panic!("field {} is not fixed64: {:?}", field, other),
+            }
+        }
+
+        fn packed(&self, field: u32) -> Vec<u64> {
+            match self.get(field) {
+                Some(Value::Bytes(bytes)) => bytes
+                    .chunks(8)
+                    .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+                    .collect(),
+                other => panic!("field {} is not packed: {:?}", field, other),
+            }
+        }
+    }
+
+    fn exporter() -> OtlpExporter {
+        OtlpExporter::new("http://127.0.0.1:4318").unwrap()
+    }
+
+    /// Encodes `counters` at `ms` and returns the metrics of the request.
+    fn metrics(exporter: &OtlpExporter, counters: &[&dyn Observable], ms: u64) -> Vec<Message> {
+        let snapshot = MetricsSnapshot::collect_with_timestamp(counters.iter().copied(), ms);
+        Message::parse(&exporter.encode(&snapshot))
+            .message(1)
+            .message(2)
+            .messages(2)
+    }
+
+    #[test]
+    fn test_resource_and_scope() {
+        let exporter = exporter()
+            .with_service_name("agent")
+            .with_resource_attribute("host.name", "h1");
+        let request = Message::parse(&exporter.encode(&MetricsSnapshot::new(Vec::new())));
+        let resource_metrics = request.message(1);
+
+        let attributes = resource_metrics.message(1).messages(1);
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[0].string(1), "service.name");
+        assert_eq!(attributes[0].message(2).string(1), "agent");
+        let scope = resource_metrics.message(2).message(1);
+        assert_eq!(scope.string(1), "contatori");
+    }
+
+    #[test]
+    fn test_sum_and_gauge_mapping() {
+        let requests = Monotone::new().with_name("requests");
+        let queue = Unsigned::new().with_name("queue");
+        let balance = Signed::new().with_name("balance");
+        requests.add(5);
+        queue.add(3);
+        balance.sub(4);
+
+        let metrics = metrics(&exporter(), &[&requests, &queue, &balance], 1_000);
+        assert_eq!(metrics.len(), 3);
+
+        assert_eq!(metrics[0].string(1), "requests");
+        let sum = metrics[0].message(7);
+        assert_eq!(sum.get(2), Some(&Value::Varint(2)));
+        assert_eq!(sum.get(3), Some(&Value::Varint(1)));
+        let point = sum.message(1);
+        assert_eq!(point.fixed(6), 5);
+        assert_eq!(point.fixed(3), 1_000_000_000);
+
+        let gauge = metrics[1].message(5).message(1);
+        assert_eq!(gauge.fixed(6), 3);
+        assert!(gauge.get(2).is_none());
+        let gauge = metrics[2].message(5).message(1);
+        assert_eq!(gauge.fixed(6) as i64, -4);
+    }
+
+    #[test]
+    fn test_delta_temporality() {
+        let requests = Monotone::new().with_name("requests");
+        let queue = Unsigned::new().with_name("queue");
+        let exporter = exporter().with_temporality(Temporality::Delta);
+
+        requests.add(5);
+        queue.add(3);
+        metrics(&exporter, &[&requests, &queue], 1_000);
+        requests.add(2);
+        let metrics = metrics(&exporter, &[&requests, &queue], 2_000);
+
+        let sum = metrics[0].message(7);
+        assert_eq!(sum.get(2), Some(&Value::Varint(1)));
+        let point = sum.message(1);
+        assert_eq!(point.fixed(6), 2);
+        // The interval starts where the previous one ended
+        assert_eq!(point.fixed(2), 1_000_000_000);
+        assert_eq!(point.fixed(3), 2_000_000_000);
+        // Gauges are not affected
+        assert_eq!(metrics[1].message(5).message(1).fixed(6), 3);
+    }
+
+    #[test]
+    fn test_histogram() {
+        static BOUNDS: [f64; 2] = [0.1, 0.5];
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        for value in [0.05, 0.3, 2.0] {
+            latency.observe(value);
+        }
+
+        let metrics = metrics(&exporter(), &[&latency], 1_000);
+        let histogram = metrics[0].message(9);
+        assert_eq!(histogram.get(2), Some(&Value::Varint(2)));
+        let point = histogram.message(1);
+        assert_eq!(point.fixed(4), 3);
+        assert!((f64::from_bits(point.fixed(5)) - 2.35).abs() < 1e-9);
+        assert_eq!(point.packed(6), [1, 1, 1]);
+        let bounds: Vec<f64> = point.packed(7).into_iter().map(f64::from_bits).collect();
+        assert_eq!(bounds, BOUNDS);
+    }
+
+    #[test]
+    fn test_labels_become_data_points() {
+        let requests = HttpRequests::new();
+        requests.get.add(3);
+        requests.post.add(1);
+
+        let metrics = metrics(&exporter(), &[&requests], 1_000);
+        assert_eq!(metrics.len(), 1);
+        let points = metrics[0].message(7).messages(1);
+        assert_eq!(points.len(), 3);
+        assert!(points[0].get(7).is_none());
+        let attribute = points[1].message(7);
+        assert_eq!(attribute.string(1), "method");
+        assert_eq!(attribute.message(2).string(1), "GET");
+        assert_eq!(points[1].fixed(6), 3);
+    }
+
+    #[test]
+    fn test_export_posts_protobuf() {
+        let (addr, requests) = stand_in::serve(&[200, 503]);
+        let exporter = OtlpExporter::new(&format!("http://{}", addr))
+            .unwrap()
+            .with_header("Authorization", "Bearer token");
+        let snapshot = MetricsSnapshot::with_timestamp(
+            vec![CounterSnapshot::new("queue", CounterValue::Unsigned(1))],
+            1_000,
+        );
+
+        exporter.export(&snapshot).unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, DEFAULT_PATH);
+        assert_eq!(request.header("Content-Type"), Some(CONTENT_TYPE));
+        assert_eq!(request.header("Authorization"), Some("Bearer token"));
+        assert_eq!(request.body, exporter.encode(&snapshot));
+
+        match exporter.export(&snapshot) {
+            Err(ObserverError::Http { status, .. }) => assert_eq!(status, 503),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_failed_export_keeps_delta() {
+        let (addr, requests) = stand_in::serve(&[503, 200]);
+        let exporter = OtlpExporter::new(&format!("http://{}", addr))
+            .unwrap()
+            .with_temporality(Temporality::Delta);
+        let counter = Monotone::new().with_name("requests");
+        let export = |ms| {
+            exporter.export(&MetricsSnapshot::collect_with_timestamp(
+                [&counter as &dyn Observable].into_iter(),
+                ms,
+            ))
+        };
+
+        counter.add(5);
+        assert!(export(1_000).is_err());
+        requests.recv().unwrap();
+        counter.add(2);
+        export(2_000).unwrap();
+
+        let body = requests.recv().unwrap().body;
+        let metric = Message::parse(&body).message(1).message(2).message(2);
+        let point = metric.message(7).message(1);
+        assert_eq!(point.fixed(6), 7);
+        assert_eq!(point.fixed(3), 2_000_000_000);
+    }
+}
@@ -77,37 +77,20 @@
 //! let observer = PrometheusObserver::with_registry(registry);
 //! ```
 
-use crate::counters::{CounterValue, MetricKind, Observable, ObservableEntry};
-use prometheus::{Encoder, Gauge, IntCounter, IntGauge, Registry, TextEncoder};
+use crate::counters::{CounterValue, Distribution, Observable, ObservableEntry};
+use prometheus::core::{Collector, Desc};
+use prometheus::{proto, Encoder, Gauge, IntCounter, IntGauge, Registry, TextEncoder};
 use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use super::{PrometheusError, Result};
+use super::exposition::{
+    build_full_name, with_unit_suffix, Exemplar, ExemplarFn, ExpositionObserver, Format,
+};
+use super::{PrometheusError, Render, Result};
 
-/// Prometheus metric type.
-///
-/// Determines how the metric is registered and displayed in Prometheus.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
-pub enum MetricType {
-    /// A counter is a cumulative metric that only ever goes up.
-    /// Use for metrics like total requests, errors, bytes sent.
-    #[default]
-    Counter,
-    /// A gauge can go up and down.
-    /// Use for metrics like current connections, temperature, queue size.
-    Gauge,
-}
-
-/// Configuration for a specific metric.
-#[derive(Debug, Clone, Default)]
-pub struct MetricConfig {
-    /// The type of metric (Counter or Gauge).
-    /// If `None`, the type is auto-detected based on the counter's `metric_kind` method.
-    pub metric_type: Option<MetricType>,
-    /// Help text describing the metric.
-    pub help: Option<String>,
-    /// Additional labels specific to this metric.
-    pub labels: HashMap<String, String>,
-}
+pub use super::exposition::{MetricConfig, MetricType};
 
 /// Observer that exports counters to Prometheus format using the official crate.
 ///
@@ -142,6 +125,10 @@ pub struct PrometheusObserver {
     const_labels: HashMap<String, String>,
     /// Per-metric configuration.
     metric_configs: HashMap<String, MetricConfig>,
+    /// Creation time of counters, for OpenMetrics `_created` samples.
+    created: Option<SystemTime>,
+    /// Callback providing the exemplar of an entry, for OpenMetrics output.
+    exemplars: Option<Arc<ExemplarFn>>,
 }
 
 impl Default for PrometheusObserver {
@@ -165,6 +152,8 @@ impl PrometheusObserver {
             subsystem: None,
             const_labels: HashMap::new(),
             metric_configs: HashMap::new(),
+            created: None,
+            exemplars: None,
         }
     }
 
@@ -179,6 +168,8 @@ impl PrometheusObserver {
             subsystem: None,
             const_labels: HashMap::new(),
             metric_configs: HashMap::new(),
+            created: None,
+            exemplars: None,
         }
     }
 
@@ -241,49 +232,39 @@ impl PrometheusObserver {
         self
     }
 
-    /// Sanitizes a metric name to be Prometheus-compatible.
+    /// Sets the creation time of all counters, usually the process start.
     ///
-    /// Prometheus metric names must match `[a-zA-Z_:][a-zA-Z0-9_:]*`.
-    fn sanitize_name(name: &str) -> String {
-        let mut result = String::with_capacity(name.len());
-        for c in name.chars() {
-            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
-                result.push(c);
-            } else if c == '-' || c == '.' || c == ' ' {
-                result.push('_');
-            } else if c.is_alphabetic() {
-                result.push('_');
-                result.push(c);
-            }
-        }
-        if result.is_empty() {
-            result.push_str("unnamed");
-        }
-        // Ensure name doesn't start with a digit
-        if result
-            .chars()
-            .next()
-            .map(|c| c.is_ascii_digit())
-            .unwrap_or(false)
-        {
-            result.insert(0, '_');
-        }
-        result
+    /// Only used by [`render_openmetrics()`](Self::render_openmetrics), which
+    /// follows every counter sample with a `_created` sample, unless
+    /// [`MetricConfig::created`] sets one for the metric.
+    pub fn with_created(mut self, created: SystemTime) -> Self {
+        self.created = Some(created);
+        self
+    }
+
+    /// Sets a callback providing the [`Exemplar`] of each counter entry.
+    ///
+    /// Only used by [`render_openmetrics()`](Self::render_openmetrics);
+    /// returning `None` writes the sample without exemplar.
+    pub fn with_exemplars<F>(mut self, exemplars: F) -> Self
+    where
+        F: Fn(&ObservableEntry) -> Option<Exemplar> + Send + Sync + 'static,
+    {
+        self.exemplars = Some(Arc::new(exemplars));
+        self
     }
 
     /// Builds the full metric name with namespace and subsystem.
     fn build_full_name(&self, name: &str) -> String {
-        let sanitized = Self::sanitize_name(name);
-        match (&self.namespace, &self.subsystem) {
-            (Some(ns), Some(ss)) => format!("{}_{}_{}", ns, ss, sanitized),
-            (Some(ns), None) => format!("{}_{}", ns, sanitized),
-            (None, Some(ss)) => format!("{}_{}", ss, sanitized),
-            (None, None) => sanitized,
-        }
+        build_full_name(self.namespace.as_deref(), self.subsystem.as_deref(), name)
     }
 
     /// Renders counters to Prometheus exposition format.
     ///
+    /// The [`description()`](Observable::description) of a counter is used
+    /// as `# HELP` text unless [`with_help()`](Self::with_help) overrides it,
+    /// and its [`unit()`](Observable::unit) is appended to the metric name.
+    ///
     /// This method:
     /// 1. Creates Prometheus metrics for each counter
     /// 2. Registers them with the registry
@@ -303,6 +284,8 @@ impl PrometheusObserver {
         // This is needed because Prometheus requires all label combinations
         // for a metric to be registered together
         let mut entries_by_name: HashMap<String, Vec<ObservableEntry>> = HashMap::new();
+        // The first observable of each metric name provides its metadata
+        let mut sources: HashMap<String, &dyn Observable> = HashMap::new();
 
         for counter in counters {
             for entry in counter.expand() {
@@ -311,26 +294,23 @@ impl PrometheusObserver {
                 } else {
                     entry.name.to_string()
                 };
+                sources.entry(raw_name.clone()).or_insert(counter);
                 entries_by_name.entry(raw_name).or_default().push(entry);
             }
         }
 
         // Now process each metric name group
         for (raw_name, entries) in entries_by_name {
-            let full_name = self.build_full_name(&raw_name);
+            let source = sources[&raw_name];
+            let full_name = with_unit_suffix(self.build_full_name(&raw_name), source.unit());
             let config = self.metric_configs.get::<str>(&raw_name);
 
             // Use the first entry to determine metric type (all should be same)
             let first_entry = &entries[0];
-            let metric_type =
-                config.and_then(|c| c.metric_type).unwrap_or_else(|| {
-                    match first_entry.metric_kind {
-                        MetricKind::Counter => MetricType::Counter,
-                        MetricKind::Gauge | MetricKind::Histogram => MetricType::Gauge,
-                    }
-                });
+            let metric_type = MetricType::resolve(config.and_then(|c| c.metric_type), first_entry);
             let help = config
                 .and_then(|c| c.help.clone())
+                .or_else(|| source.description().map(str::to_string))
                 .unwrap_or_else(|| format!("{} metric", raw_name));
 
             // Collect all unique label keys across all entries
@@ -352,9 +332,9 @@ impl PrometheusObserver {
                 }
             }
 
-            // Then entry label
+            // Then entry labels
             for entry in &entries {
-                if let Some((k, _)) = &entry.label {
+                for (k, _) in &entry.labels {
                     let key = k.to_string();
                     if !all_label_keys.contains(&key) {
                         all_label_keys.push(key);
@@ -365,7 +345,18 @@ impl PrometheusObserver {
             // Sort label keys for consistent ordering
             all_label_keys.sort();
 
-            if all_label_keys.is_empty() {
+            if let MetricType::Histogram | MetricType::Summary = metric_type {
+                let family = self.distribution_family(
+                    &full_name,
+                    &help,
+                    metric_type,
+                    &all_label_keys,
+                    config,
+                    &entries,
+                )?;
+                let desc = Desc::new(full_name, help, all_label_keys, HashMap::new())?;
+                registry.register(Box::new(FamilyCollector { desc, family }))?;
+            } else if all_label_keys.is_empty() {
                 // No labels - use simple metrics
                 // All entries should have the same value for unlabeled metrics
                 // (or we just use the first one)
@@ -380,7 +371,7 @@ impl PrometheusObserver {
                             value,
                         )?;
                     }
-                    MetricType::Gauge => {
+                    _ => {
                         self.register_gauge(&registry, &full_name, &help, &HashMap::new(), value)?;
                     }
                 }
@@ -401,7 +392,7 @@ impl PrometheusObserver {
                             if let Some(cfg) = config {
                                 labels_map.extend(cfg.labels.clone());
                             }
-                            if let Some((k, v)) = &entry.label {
+                            for (k, v) in &entry.labels {
                                 labels_map.insert(k.to_string(), v.to_string());
                             }
 
@@ -418,7 +409,7 @@ impl PrometheusObserver {
                             counter_vec.with_label_values(&label_values).inc_by(val);
                         }
                     }
-                    MetricType::Gauge => {
+                    _ => {
                         // Check if any entry has a Float value
                         let has_float = entries
                             .iter()
@@ -437,7 +428,7 @@ impl PrometheusObserver {
                                 if let Some(cfg) = config {
                                     labels_map.extend(cfg.labels.clone());
                                 }
-                                if let Some((k, v)) = &entry.label {
+                                for (k, v) in &entry.labels {
                                     labels_map.insert(k.to_string(), v.to_string());
                                 }
 
@@ -463,7 +454,7 @@ impl PrometheusObserver {
                                 if let Some(cfg) = config {
                                     labels_map.extend(cfg.labels.clone());
                                 }
-                                if let Some((k, v)) = &entry.label {
+                                for (k, v) in &entry.labels {
                                     labels_map.insert(k.to_string(), v.to_string());
                                 }
 
@@ -501,6 +492,45 @@ impl PrometheusObserver {
         Ok(self.render(counters)?.into_bytes())
     }
 
+    /// Renders counters in the OpenMetrics 1.0 text format.
+    ///
+    /// The `prometheus` crate only encodes the 0.0.4 text format, so this
+    /// goes through [`ExpositionObserver`] with the same namespace,
+    /// subsystem, constant labels, metric configurations, creation time and
+    /// exemplars. The registry is not used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing the output fails.
+    pub fn render_openmetrics<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<String> {
+        let mut observer = ExpositionObserver::new().with_format(Format::OpenMetrics);
+        if let Some(namespace) = &self.namespace {
+            observer = observer.with_namespace(namespace);
+        }
+        if let Some(subsystem) = &self.subsystem {
+            observer = observer.with_subsystem(subsystem);
+        }
+        let mut const_labels: Vec<_> = self.const_labels.iter().collect();
+        const_labels.sort_unstable();
+        for (name, value) in const_labels {
+            observer = observer.with_const_label(name, value);
+        }
+        for (name, config) in &self.metric_configs {
+            observer = observer.with_metric_config(name, config.clone());
+        }
+        if let Some(created) = self.created {
+            observer = observer.with_created(created);
+        }
+        if let Some(exemplars) = &self.exemplars {
+            let exemplars = Arc::clone(exemplars);
+            observer = observer.with_exemplars(move |entry| exemplars(entry));
+        }
+        observer.render(counters)
+    }
+
     /// Encodes the registry to a string.
     fn encode_registry(&self, registry: &Registry) -> Result<String> {
         let encoder = TextEncoder::new();
@@ -512,6 +542,101 @@ impl PrometheusObserver {
         Ok(String::from_utf8(buffer)?)
     }
 
+    /// Builds the histogram or summary family of distribution entries.
+    ///
+    /// The `prometheus` crate only builds histograms from raw observations,
+    /// so the buckets and quantiles are copied into the family directly.
+    fn distribution_family(
+        &self,
+        name: &str,
+        help: &str,
+        metric_type: MetricType,
+        label_keys: &[String],
+        config: Option<&MetricConfig>,
+        entries: &[ObservableEntry],
+    ) -> Result<proto::MetricFamily> {
+        if let Some(reserved) = metric_type.reserved_label() {
+            if label_keys.iter().any(|key| key == reserved) {
+                return Err(PrometheusError::MetricError(format!(
+                    "label `{}` is reserved in {} {}",
+                    reserved,
+                    metric_type.as_str(),
+                    name
+                ))
+                .into());
+            }
+        }
+
+        let mut family = proto::MetricFamily::default();
+        family.set_name(name.to_string());
+        family.set_help(help.to_string());
+        family.set_field_type(match metric_type {
+            MetricType::Summary => proto::MetricType::SUMMARY,
+            _ => proto::MetricType::HISTOGRAM,
+        });
+
+        let no_distribution = Distribution::default();
+        for entry in entries {
+            let mut labels_map = self.const_labels.clone();
+            if let Some(cfg) = config {
+                labels_map.extend(cfg.labels.clone());
+            }
+            for (k, v) in &entry.labels {
+                labels_map.insert(k.to_string(), v.to_string());
+            }
+            let labels = label_keys
+                .iter()
+                .map(|key| {
+                    let mut pair = proto::LabelPair::default();
+                    pair.set_name(key.clone());
+                    pair.set_value(labels_map.get(key).cloned().unwrap_or_default());
+                    pair
+                })
+                .collect();
+
+            let distribution = entry.distribution.as_ref().unwrap_or(&no_distribution);
+            let mut metric = proto::Metric::default();
+            metric.set_label(labels);
+            if metric_type == MetricType::Summary {
+                let mut summary = proto::Summary::default();
+                summary.set_sample_count(distribution.count);
+                summary.set_sample_sum(distribution.sum);
+                summary.set_quantile(
+                    distribution
+                        .quantiles
+                        .iter()
+                        .map(|&(q, value)| {
+                            let mut quantile = proto::Quantile::default();
+                            quantile.set_quantile(q);
+                            quantile.set_value(value);
+                            quantile
+                        })
+                        .collect(),
+                );
+                metric.set_summary(summary);
+            } else {
+                let mut histogram = proto::Histogram::default();
+                histogram.set_sample_count(distribution.count);
+                histogram.set_sample_sum(distribution.sum);
+                histogram.set_bucket(
+                    distribution
+                        .buckets
+                        .iter()
+                        .map(|&(upper_bound, count)| {
+                            let mut bucket = proto::Bucket::default();
+                            bucket.set_upper_bound(upper_bound);
+                            bucket.set_cumulative_count(count);
+                            bucket
+                        })
+                        .collect(),
+                );
+                metric.set_histogram(histogram);
+            }
+            family.mut_metric().push(metric);
+        }
+        Ok(family)
+    }
+
     /// Registers a counter metric with the given value.
     fn register_counter(
         &self,
@@ -596,14 +721,47 @@ impl PrometheusObserver {
     }
 }
 
+impl Render for PrometheusObserver {
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        _now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()> {
+        out.write_all(self.render(counters.iter().copied())?.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Exposes a prebuilt metric family through a [`Registry`].
+struct FamilyCollector {
+    desc: Desc,
+    family: proto::MetricFamily,
+}
+
+impl Collector for FamilyCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<proto::MetricFamily> {
+        vec![self.family.clone()]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::counters::average::Average;
+    use crate::counters::histogram::Histogram;
     use crate::counters::maximum::Maximum;
     use crate::counters::minimum::Minimum;
+    use crate::counters::monotone::Monotone;
     use crate::counters::signed::Signed;
     use crate::counters::unsigned::Unsigned;
+    use crate::counters::{MetricKind, Unit};
+    use crate::observers::exposition::sanitize_name;
+    use crate::testing::HttpMetrics;
 
     #[test]
     fn test_render_empty() {
@@ -682,6 +840,169 @@ mod tests {
         assert!(output.contains("http_requests 50"));
     }
 
+    #[test]
+    fn test_render_help_from_description() {
+        use crate::metrics::Metric;
+
+        let counter = Unsigned::new();
+        counter.add(5);
+        let described = Metric::new(&counter, "queue_depth").with_help("Jobs waiting");
+        let overridden = Metric::new(&counter, "queue_peak").with_help("Ignored");
+
+        let observer = PrometheusObserver::new().with_help("queue_peak", "Configured help");
+        let counters: Vec<&dyn Observable> = vec![&described, &overridden];
+        let output = observer.render(counters.into_iter()).unwrap();
+
+        assert!(output.contains("# HELP queue_depth Jobs waiting"));
+        // Help configured on the observer wins over the description
+        assert!(output.contains("# HELP queue_peak Configured help"));
+    }
+
+    #[test]
+    fn test_render_metadata_from_counter() {
+        use crate::counters::monotone::Monotone;
+
+        let sent = Monotone::new()
+            .with_name("sent_bytes")
+            .with_help("Bytes written to the socket")
+            .with_unit(Unit::Bytes);
+        sent.add(512);
+        let latency = Unsigned::new()
+            .with_name("request_latency")
+            .with_unit(Unit::Seconds);
+        latency.add(2);
+
+        let observer = PrometheusObserver::new();
+        let counters: Vec<&dyn Observable> = vec![&sent, &latency];
+        let output = observer.render(counters.into_iter()).unwrap();
+
+        // Unit already in the name is not repeated
+        assert!(output.contains("# HELP sent_bytes Bytes written to the socket"));
+        assert!(output.contains("sent_bytes 512"));
+        assert!(output.contains("# TYPE request_latency_seconds gauge"));
+        assert!(output.contains("request_latency_seconds 2"));
+    }
+
+    #[test]
+    fn test_render_openmetrics() {
+        let requests = Monotone::new().with_name("requests_total");
+        let temperature = Signed::new().with_name("temperature");
+        requests.add(7);
+        temperature.add(21);
+
+        let observer = PrometheusObserver::new()
+            .with_namespace("app")
+            .with_const_label("instance", "a")
+            .with_type("temperature", MetricType::Gauge);
+        let counters: Vec<&dyn Observable> = vec![&requests, &temperature];
+        let output = observer.render_openmetrics(counters.into_iter()).unwrap();
+
+        assert!(output.contains("# TYPE app_requests counter\n"));
+        assert!(output.contains("app_requests_total{instance=\"a\"} 7\n"));
+        assert!(output.contains("app_temperature{instance=\"a\"} 21\n"));
+        assert!(output.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_render_openmetrics_created_and_exemplars() {
+        let requests = Monotone::new().with_name("requests");
+        requests.add(3);
+
+        let observer = PrometheusObserver::new()
+            .with_created(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000))
+            .with_exemplars(|entry| {
+                (entry.name == "requests").then(|| Exemplar::trace("4bf92f35", 1.0))
+            });
+        let counters: Vec<&dyn Observable> = vec![&requests];
+        let output = observer.render_openmetrics(counters.into_iter()).unwrap();
+
+        assert_eq!(
+            output,
+            "# HELP requests requests metric\n\
+             # TYPE requests counter\n\
+             requests_total 3 # {trace_id=\"4bf92f35\"} 1\n\
+             requests_created 1700000000\n\
+             # EOF\n"
+        );
+    }
+
+    #[test]
+    fn test_render_histogram() {
+        let latency = Histogram::new(&[0.1, 1.0])
+            .with_name("latency")
+            .with_help("Request latency")
+            .with_unit(Unit::Seconds);
+        latency.observe(0.05);
+        latency.observe(3.0);
+
+        let observer = PrometheusObserver::new()
+            .with_namespace("app")
+            .with_const_label("instance", "a");
+        let counters: Vec<&dyn Observable> = vec![&latency];
+        let output = observer.render(counters.into_iter()).unwrap();
+
+        assert!(output.contains("# HELP app_latency_seconds Request latency\n"));
+        assert!(output.contains("# TYPE app_latency_seconds histogram\n"));
+        assert!(output.contains("app_latency_seconds_bucket{instance=\"a\",le=\"0.1\"} 1\n"));
+        assert!(output.contains("app_latency_seconds_bucket{instance=\"a\",le=\"1\"} 1\n"));
+        assert!(output.contains("app_latency_seconds_bucket{instance=\"a\",le=\"+Inf\"} 2\n"));
+        assert!(output.contains("app_latency_seconds_sum{instance=\"a\"} 3.05\n"));
+        assert!(output.contains("app_latency_seconds_count{instance=\"a\"} 2\n"));
+    }
+
+    #[test]
+    fn test_render_summary() {
+        #[derive(Debug)]
+        struct Quantiles;
+
+        impl Observable for Quantiles {
+            fn name(&self) -> &str {
+                "rpc_duration"
+            }
+
+            fn metric_kind(&self) -> MetricKind {
+                MetricKind::Summary
+            }
+
+            fn value(&self) -> CounterValue {
+                CounterValue::Unsigned(4)
+            }
+
+            fn distribution(&self) -> Option<Distribution> {
+                Some(Distribution {
+                    count: 4,
+                    sum: 2.0,
+                    buckets: Vec::new(),
+                    quantiles: vec![(0.5, 0.4), (0.9, 0.8)],
+                })
+            }
+        }
+
+        let counters: Vec<&dyn Observable> = vec![&Quantiles];
+        let output = PrometheusObserver::new()
+            .render(counters.into_iter())
+            .unwrap();
+        assert!(output.contains("# TYPE rpc_duration summary\n"));
+        assert!(output.contains("rpc_duration{quantile=\"0.5\"} 0.4\n"));
+        assert!(output.contains("rpc_duration{quantile=\"0.9\"} 0.8\n"));
+        assert!(output.contains("rpc_duration_sum 2\n"));
+        assert!(output.contains("rpc_duration_count 4\n"));
+    }
+
+    #[test]
+    fn test_render_histogram_reserved_label() {
+        let latency = Histogram::new(&[1.0]).with_name("latency");
+        let observer = PrometheusObserver::new().with_metric_config(
+            "latency",
+            MetricConfig {
+                labels: [("le".to_string(), "x".to_string())].into(),
+                ..Default::default()
+            },
+        );
+        let counters: Vec<&dyn Observable> = vec![&latency];
+        assert!(observer.render(counters.into_iter()).is_err());
+    }
+
     #[test]
     fn test_render_with_type_gauge() {
         let counter = Signed::new().with_name("temperature");
@@ -721,18 +1042,12 @@ mod tests {
 
     #[test]
     fn test_sanitize_name() {
-        assert_eq!(
-            PrometheusObserver::sanitize_name("valid_name"),
-            "valid_name"
-        );
-        assert_eq!(PrometheusObserver::sanitize_name("with-dash"), "with_dash");
-        assert_eq!(PrometheusObserver::sanitize_name("with.dot"), "with_dot");
-        assert_eq!(
-            PrometheusObserver::sanitize_name("with space"),
-            "with_space"
-        );
-        assert_eq!(PrometheusObserver::sanitize_name(""), "unnamed");
-        assert_eq!(PrometheusObserver::sanitize_name("123starts"), "_123starts");
+        assert_eq!(sanitize_name("valid_name"), "valid_name");
+        assert_eq!(sanitize_name("with-dash"), "with_dash");
+        assert_eq!(sanitize_name("with.dot"), "with_dot");
+        assert_eq!(sanitize_name("with space"), "with_space");
+        assert_eq!(sanitize_name(""), "unnamed");
+        assert_eq!(sanitize_name("123starts"), "_123starts");
     }
 
     #[test]
@@ -919,6 +1234,21 @@ mod tests {
         assert!(output.contains("50"));
     }
 
+    #[test]
+    fn test_render_multiple_labels() {
+        let observer = PrometheusObserver::new().with_const_label("instance", "server-1");
+        let counters: Vec<&dyn Observable> = vec![&HttpMetrics];
+        let output = observer.render(counters.into_iter()).unwrap();
+
+        assert!(output.contains("# TYPE http_requests counter"));
+        assert!(output.contains(
+            "http_requests{instance=\"server-1\",method=\"GET\",route=\"/users\",status=\"200\"} 7"
+        ));
+        assert!(output.contains(
+            "http_requests{instance=\"server-1\",method=\"POST\",route=\"/orders\",status=\"500\"} 2"
+        ));
+    }
+
     #[test]
     fn test_auto_detect_metric_type_from_metric_kind() {
         use crate::counters::monotone::Monotone;
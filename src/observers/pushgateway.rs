@@ -0,0 +1,534 @@
+//! Prometheus Pushgateway client for batch jobs.
+//!
+//! Short-lived jobs may exit before Prometheus scrapes them. This module
+//! provides [`Pushgateway`], which renders counters with a
+//! [`PrometheusObserver`] and pushes them to a
+//! [Pushgateway](https://github.com/prometheus/pushgateway), where they are
+//! kept until replaced or deleted.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `pushgateway` feature (which enables
+//! `prometheus`):
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["pushgateway"] }
+//! ```
+//!
+//! # Grouping Keys
+//!
+//! Pushed metrics are stored per group, identified by the job name and
+//! optional grouping labels (typically `instance`). The group becomes the
+//! URL path (`/metrics/job/<job>/<label>/<value>...`); values that cannot
+//! appear in a path segment are base64url-encoded as the Pushgateway
+//! specifies.
+//!
+//! | Method | HTTP | Effect on the group |
+//! |--------|------|---------------------|
+//! | [`push()`](Pushgateway::push) | `PUT` | Replaces all metrics |
+//! | [`push_add()`](Pushgateway::push_add) | `POST` | Replaces metrics with the same names |
+//! | [`delete()`](Pushgateway::delete) | `DELETE` | Removes all metrics |
+//!
+//! Failed requests are retried with exponential backoff on connection
+//! errors and `5xx` responses; `4xx` responses fail immediately.
+//!
+//! # Transport
+//!
+//! Requests go over a plain `std::net` connection to an `http://` URL. TLS
+//! is not supported.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::pushgateway::Pushgateway;
+//!
+//! let processed = Monotone::new().with_name("records_processed");
+//!
+//! let gateway = Pushgateway::new("http://pushgateway:9091", "nightly_import")?
+//!     .with_grouping_key("instance", "db-1");
+//!
+//! // Pushes the final values when the job ends, even on early return
+//! let guard = gateway.guard([&processed as &dyn Observable]);
+//! processed.add(1000);
+//! guard.finish()?;
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::http_client::{self, Url};
+use super::prometheus::PrometheusObserver;
+use super::{Observer, ObserverError, Result};
+use crate::counters::Observable;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// HTTP method used to push metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PushMethod {
+    /// `PUT`: replaces all metrics of the group.
+    #[default]
+    Replace,
+    /// `POST`: replaces only metrics with the same names.
+    Add,
+}
+
+impl PushMethod {
+    /// Returns the HTTP method.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            PushMethod::Replace => "PUT",
+            PushMethod::Add => "POST",
+        }
+    }
+}
+
+/// A client pushing counters to a Prometheus Pushgateway.
+///
+/// Created with [`new()`](Self::new) and configured with the `with_*`
+/// builders.
+pub struct Pushgateway {
+    /// Base URL of the Pushgateway.
+    url: Url,
+    /// Job name, the first grouping label.
+    job: String,
+    /// Additional grouping labels, in order.
+    grouping: Vec<(String, String)>,
+    /// Observer rendering the pushed text.
+    observer: PrometheusObserver,
+    /// Number of retries after the first attempt.
+    retries: u32,
+    /// Delay before the first retry, doubled on each retry.
+    backoff: Duration,
+    /// Upper bound of the retry delay.
+    max_backoff: Duration,
+    /// Connect, read and write timeout of each request.
+    timeout: Duration,
+}
+
+impl fmt::Debug for Pushgateway {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pushgateway")
+            .field("url", &self.url)
+            .field("job", &self.job)
+            .field("grouping", &self.grouping)
+            .field("retries", &self.retries)
+            .field("backoff", &self.backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Pushgateway {
+    /// Creates a client for the Pushgateway at `url` (for example
+    /// `http://localhost:9091`), pushing under `job`.
+    ///
+    /// Defaults to 3 retries with a backoff from 100ms up to 5s, and a 10s
+    /// timeout per request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `url` is not an `http://` URL or `job` is empty.
+    pub fn new(url: &str, job: &str) -> Result<Self> {
+        if job.is_empty() {
+            return Err(ObserverError::Metric(
+                "empty pushgateway job name".to_string(),
+            ));
+        }
+        Ok(Self {
+            url: Url::parse(url)?,
+            job: job.to_string(),
+            grouping: Vec::new(),
+            observer: PrometheusObserver::new(),
+            retries: 3,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            timeout: Duration::from_secs(10),
+        })
+    }
+
+    /// Adds a grouping label (such as `instance`) identifying the group
+    /// together with the job.
+    ///
+    /// Setting a label twice replaces its value.
+    pub fn with_grouping_key(mut self, name: &str, value: &str) -> Self {
+        match self.grouping.iter_mut().find(|(k, _)| k == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.grouping.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    /// Sets the observer rendering the pushed metrics (namespace, constant
+    /// labels, metric configurations...).
+    pub fn with_observer(mut self, observer: PrometheusObserver) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Sets the number of retries after a failed attempt (default: 3).
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Sets the delay before the first retry and its upper bound; the delay
+    /// doubles on each retry (default: 100ms up to 5s).
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets the timeout of each request (default: 10s).
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the URL path of the group.
+    pub fn group_path(&self) -> String {
+        let mut path = format!("{}/metrics", self.url.path);
+        push_segment(&mut path, "job", &self.job);
+        for (name, value) in &self.grouping {
+            push_segment(&mut path, name, value);
+        }
+        path
+    }
+
+    /// Replaces all metrics of the group with `counters` (`PUT`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if rendering fails, or if the last attempt fails
+    /// with a connection error or a non-success status
+    /// ([`ObserverError::Http`]).
+    pub fn push<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<()> {
+        self.push_with(PushMethod::Replace, counters)
+    }
+
+    /// Replaces the metrics of the group with the same names as `counters`
+    /// (`POST`).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`push()`](Self::push).
+    pub fn push_add<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<()> {
+        self.push_with(PushMethod::Add, counters)
+    }
+
+    /// Pushes `counters` with the given method.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`push()`](Self::push).
+    pub fn push_with<'a>(
+        &self,
+        method: PushMethod,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<()> {
+        let body = self.observer.render_bytes(counters)?;
+        self.send(method.as_str(), &body)
+    }
+
+    /// Deletes all metrics of the group (`DELETE`), typically once the job
+    /// has completed and its results have been scraped.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`push()`](Self::push).
+    pub fn delete(&self) -> Result<()> {
+        self.send("DELETE", &[])
+    }
+
+    /// Returns a guard pushing `counters` when finished or dropped.
+    ///
+    /// The guard makes sure a batch job reports its final values on every
+    /// exit path. Errors are returned by [`PushGuard::finish()`] and
+    /// ignored on drop.
+    pub fn guard<'a>(
+        self,
+        counters: impl IntoIterator<Item = &'a dyn Observable>,
+    ) -> PushGuard<'a> {
+        PushGuard {
+            pushgateway: self,
+            counters: counters.into_iter().collect(),
+            method: PushMethod::Replace,
+            pending: true,
+        }
+    }
+
+    /// Sends a request to the group, retrying on connection errors and
+    /// `5xx` responses.
+    fn send(&self, method: &str, body: &[u8]) -> Result<()> {
+        let path = self.group_path();
+        let headers = [("Content-Type", super::exposition::CONTENT_TYPE)];
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+        loop {
+            let result = http_client::send(&self.url, method, &path, &headers, body, self.timeout)
+                .and_then(|response| response.error_for_status());
+            let retryable = match &result {
+                Ok(_) => return Ok(()),
+                Err(ObserverError::Http { status, .. }) => *status >= 500,
+                Err(ObserverError::Io(_)) => true,
+                Err(_) => false,
+            };
+            if !retryable || attempt >= self.retries {
+                return result.map(|_| ());
+            }
+            attempt += 1;
+            thread::sleep(delay);
+            delay = (delay * 2).min(self.max_backoff);
+        }
+    }
+}
+
+impl Observer for Pushgateway {
+    /// Replaces the metrics of the group, like [`push()`](Self::push).
+    ///
+    /// The Pushgateway stamps pushes with its own clock, so `now` is unused.
+    fn observe(&mut self, counters: &[&dyn Observable], _now: SystemTime) -> Result<()> {
+        self.push(counters.iter().copied())
+    }
+}
+
+/// Appends `/<name>/<value>` to a group path, base64url-encoding values
+/// that are empty or contain characters outside the URL unreserved set.
+fn push_segment(path: &mut String, name: &str, value: &str) {
+    let plain = !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~'));
+    path.push('/');
+    path.push_str(name);
+    if plain {
+        path.push('/');
+        path.push_str(value);
+    } else {
+        path.push_str("@base64/");
+        if value.is_empty() {
+            path.push('=');
+        } else {
+            base64url(path, value.as_bytes());
+        }
+    }
+}
+
+/// Appends the padded base64url encoding (RFC 4648 §5) of `bytes`.
+fn base64url(out: &mut String, bytes: &[u8]) {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+}
+
+/// Pushes counters when finished or dropped.
+///
+/// Created with [`Pushgateway::guard()`].
+#[derive(Debug)]
+#[must_use = "dropping the guard pushes immediately"]
+pub struct PushGuard<'a> {
+    pushgateway: Pushgateway,
+    counters: Vec<&'a dyn Observable>,
+    method: PushMethod,
+    pending: bool,
+}
+
+impl<'a> PushGuard<'a> {
+    /// Sets the push method (default: [`PushMethod::Replace`]).
+    pub fn with_method(mut self, method: PushMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Returns the client.
+    pub fn pushgateway(&self) -> &Pushgateway {
+        &self.pushgateway
+    }
+
+    /// Pushes the counters now and disarms the guard.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Pushgateway::push()`].
+    pub fn finish(mut self) -> Result<()> {
+        self.pending = false;
+        self.push()
+    }
+
+    fn push(&self) -> Result<()> {
+        self.pushgateway
+            .push_with(self.method, self.counters.iter().copied())
+    }
+}
+
+impl Drop for PushGuard<'_> {
+    fn drop(&mut self) {
+        if self.pending {
+            let _ = self.push();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+    use crate::observers::http_client::stand_in;
+
+    fn gateway(addr: std::net::SocketAddr) -> Pushgateway {
+        Pushgateway::new(&format!("http://{}", addr), "batch")
+            .unwrap()
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    #[test]
+    fn test_group_path() {
+        let gateway = Pushgateway::new("http://localhost:9091/gw/", "import")
+            .unwrap()
+            .with_grouping_key("instance", "db-1")
+            .with_grouping_key("path", "/var/tmp")
+            .with_grouping_key("empty", "")
+            .with_grouping_key("instance", "db-2");
+        assert_eq!(
+            gateway.group_path(),
+            "/gw/metrics/job/import/instance/db-2/path@base64/L3Zhci90bXA=/empty@base64/="
+        );
+    }
+
+    #[test]
+    fn test_base64url() {
+        for (input, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("\u{fb}\u{ff}", "w7vDvw=="),
+        ] {
+            let mut out = String::new();
+            base64url(&mut out, input.as_bytes());
+            assert_eq!(out, expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(Pushgateway::new("localhost:9091", "job").is_err());
+        assert!(Pushgateway::new("http://localhost:9091", "").is_err());
+    }
+
+    #[test]
+    fn test_push_and_delete() {
+        let requests_total = Monotone::new().with_name("requests");
+        requests_total.add(7);
+        let (addr, requests) = stand_in::serve(&[200, 202, 202]);
+        let gateway = gateway(addr).with_grouping_key("instance", "a");
+        let counters: [&dyn Observable; 1] = [&requests_total];
+
+        gateway.push(counters.iter().copied()).unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/metrics/job/batch/instance/a");
+        assert_eq!(
+            request.header("Content-Type"),
+            Some(super::super::exposition::CONTENT_TYPE)
+        );
+        let body = String::from_utf8(request.body).unwrap();
+        assert!(body.contains("# TYPE requests counter\nrequests 7\n"));
+
+        gateway.push_add(counters.iter().copied()).unwrap();
+        assert_eq!(requests.recv().unwrap().method, "POST");
+
+        gateway.delete().unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "DELETE");
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn test_retries_server_errors() {
+        let (addr, requests) = stand_in::serve(&[503, 500, 200]);
+        let counter = Unsigned::new().with_name("queue");
+        gateway(addr)
+            .push([&counter as &dyn Observable].into_iter())
+            .unwrap();
+        assert_eq!(requests.iter().count(), 3);
+    }
+
+    #[test]
+    fn test_gives_up_after_retries() {
+        let (addr, requests) = stand_in::serve(&[502, 502]);
+        let result = gateway(addr).with_retries(1).delete();
+        match result {
+            Err(ObserverError::Http { status, message }) => {
+                assert_eq!(status, 502);
+                assert_eq!(message, "status 502");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(requests.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_client_errors_not_retried() {
+        let (addr, requests) = stand_in::serve(&[400]);
+        let result = gateway(addr).delete();
+        assert!(matches!(
+            result,
+            Err(ObserverError::Http { status: 400, .. })
+        ));
+        assert_eq!(requests.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_connection_errors_retried() {
+        // Nothing listens on a freshly closed port
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let result = gateway(addr).with_retries(2).delete();
+        assert!(matches!(result, Err(ObserverError::Io(_))));
+    }
+
+    #[test]
+    fn test_guard_pushes_on_drop() {
+        let (addr, requests) = stand_in::serve(&[200, 200]);
+        let counter = Monotone::new().with_name("processed");
+        {
+            let _guard = gateway(addr).guard([&counter as &dyn Observable]);
+            counter.add(3);
+        }
+        let body = String::from_utf8(requests.recv().unwrap().body).unwrap();
+        assert!(body.contains("processed 3\n"));
+
+        let guard = gateway(addr)
+            .guard([&counter as &dyn Observable])
+            .with_method(PushMethod::Add);
+        counter.add(1);
+        guard.finish().unwrap();
+        let request = requests.recv().unwrap();
+        assert_eq!(request.method, "POST");
+        assert!(String::from_utf8(request.body)
+            .unwrap()
+            .contains("processed 4\n"));
+        // finish() disarmed the guard
+        assert!(requests.recv().is_err());
+    }
+}
@@ -0,0 +1,850 @@
+//! StatsD / DogStatsD UDP exporter.
+//!
+//! This module provides [`StatsdObserver`], which renders counters as StatsD
+//! lines, batches them into UDP packets that fit the network MTU and sends
+//! them to a StatsD agent, either on demand or from a background
+//! [`StatsdReporter`] thread.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `statsd` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["statsd"] }
+//! ```
+//!
+//! # Metric Type Mapping
+//!
+//! | Entry | StatsD line |
+//! |-------|-------------|
+//! | [`MetricKind::Counter`] | `name:value\|c` |
+//! | [`MetricKind::Gauge`] | `name:value\|g` |
+//! | [`MetricKind::Histogram`] / [`MetricKind::Summary`] | `name:bound\|ms\|@rate` per bucket |
+//!
+//! Other mappings can be configured per metric with
+//! [`with_type()`](StatsdObserver::with_type), for example to send an
+//! [`Average`](crate::counters::average::Average) as a timer sample.
+//!
+//! # Deltas
+//!
+//! StatsD counters are increments: the agent adds up every value it
+//! receives. The observer remembers the last value sent for each counter and
+//! histogram entry (by name and labels) and sends the difference, so
+//! cumulative counters such as
+//! [`Monotone`](crate::counters::monotone::Monotone) and histograms are
+//! counted once. Gauges are absolute and sent as they are.
+//!
+//! Counters that already report per-period values, such as a
+//! [`Resettable<Unsigned>`](crate::adapters::Resettable) declared as a
+//! counter with [`with_type()`](StatsdObserver::with_type), must be sent as
+//! they are: disable the tracking with
+//! [`with_delta_tracking(false)`](StatsdObserver::with_delta_tracking).
+//!
+//! Histogram buckets are sent as samples at the bucket upper bound, with a
+//! sample rate of `1/count`, so the agent accounts for every observation
+//! without one line per observation.
+//!
+//! # Tags
+//!
+//! With [`Flavor::DogStatsd`], entry labels (from `labeled_group!` or
+//! [`Family`](crate::adapters::Family)) and global tags become DogStatsD
+//! tags (`|#key:value,...`). Plain StatsD has no tags, so labels are folded
+//! into the metric name (`name.key.value`) and global tags are ignored.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::Observable;
+//! use contatori::observers::statsd::{Flavor, StatsdObserver};
+//! use std::time::Duration;
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("requests");
+//!
+//! let reporter = StatsdObserver::new()
+//!     .with_flavor(Flavor::DogStatsd)
+//!     .with_prefix("myapp")
+//!     .with_tag("env", "prod")
+//!     .reporter("127.0.0.1:8125", Duration::from_secs(10), || {
+//!         vec![&REQUESTS as &'static dyn Observable]
+//!     })?;
+//! // ... sends every 10 seconds, and a last time when dropped
+//! # Ok::<(), contatori::observers::ObserverError>(())
+//! ```
+
+use super::delta::{entry_key, DeltaTracker, Pending};
+use super::{Observer, Result};
+use crate::counters::{CounterValue, Distribution, MetricKind, Observable, ObservableEntry};
+use std::collections::HashMap;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// Default maximum packet size: the Ethernet MTU minus IP and UDP headers,
+/// with room for IP options.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 1432;
+
+/// StatsD protocol flavor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flavor {
+    /// Plain StatsD (Etsy): labels are folded into the metric name.
+    #[default]
+    Statsd,
+    /// DogStatsD: labels and global tags are sent as `#key:value` tags.
+    DogStatsd,
+}
+
+/// StatsD metric type of a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsdType {
+    /// Counter increment (`|c`).
+    Counter,
+    /// Absolute gauge (`|g`).
+    Gauge,
+    /// Timer sample in milliseconds (`|ms`).
+    Timer,
+    /// Histogram sample (`|h`, DogStatsD).
+    Histogram,
+}
+
+impl StatsdType {
+    /// Returns the type suffix of a line.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            StatsdType::Counter => "c",
+            StatsdType::Gauge => "g",
+            StatsdType::Timer => "ms",
+            StatsdType::Histogram => "h",
+        }
+    }
+}
+
+/// Observer rendering counters as StatsD lines and sending them over UDP.
+///
+/// Configured with the `with_*` builders, then used with
+/// [`render()`](Self::render) / [`send()`](Self::send), or moved into a
+/// background [`reporter()`](Self::reporter).
+#[derive(Debug)]
+pub struct StatsdObserver {
+    /// Protocol flavor.
+    flavor: Flavor,
+    /// Prefix joined to every name with a dot.
+    prefix: Option<String>,
+    /// Tags added to every line (DogStatsD only).
+    tags: Vec<(String, String)>,
+    /// Per-metric type overrides.
+    types: HashMap<String, StatsdType>,
+    /// Type of distribution samples.
+    distribution_type: StatsdType,
+    /// Maximum size of a packet.
+    max_packet_size: usize,
+    /// Last values sent for counter and histogram entries, or `None` if
+    /// values are sent as they are.
+    last_seen: Option<DeltaTracker>,
+}
+
+impl Default for StatsdObserver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatsdObserver {
+    /// Creates a new plain StatsD observer.
+    pub fn new() -> Self {
+        Self {
+            flavor: Flavor::Statsd,
+            prefix: None,
+            tags: Vec::new(),
+            types: HashMap::new(),
+            distribution_type: StatsdType::Timer,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            last_seen: Some(DeltaTracker::default()),
+        }
+    }
+
+    /// Sets the protocol flavor (default: [`Flavor::Statsd`]).
+    pub fn with_flavor(mut self, flavor: Flavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    /// Sets a prefix joined to every metric name with a dot.
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Adds a tag to every line (DogStatsD only).
+    pub fn with_tag(mut self, key: &str, value: &str) -> Self {
+        self.tags.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Overrides the StatsD type of a metric, by counter name.
+    ///
+    /// A metric without distribution sent as [`StatsdType::Timer`] or
+    /// [`StatsdType::Histogram`] is sent as a single sample of its value.
+    pub fn with_type(mut self, name: &str, statsd_type: StatsdType) -> Self {
+        self.types.insert(name.to_string(), statsd_type);
+        self
+    }
+
+    /// Sets the type of histogram and summary samples (default:
+    /// [`StatsdType::Timer`]).
+    pub fn with_distribution_type(mut self, statsd_type: StatsdType) -> Self {
+        self.distribution_type = statsd_type;
+        self
+    }
+
+    /// Sets the maximum size of a packet (default:
+    /// [`DEFAULT_MAX_PACKET_SIZE`]).
+    ///
+    /// Lines are batched into packets up to this size; a single longer line
+    /// is sent in a packet of its own.
+    pub fn with_max_packet_size(mut self, size: usize) -> Self {
+        self.max_packet_size = size;
+        self
+    }
+
+    /// Enables or disables delta tracking of counters and histograms
+    /// (default: enabled).
+    ///
+    /// With tracking, counters and histograms are sent as the difference
+    /// from the last values sent. Only [`send()`](Self::send) records the
+    /// values sent, so rendering does not change the next delta, and neither
+    /// does a failed send. A value lower than the last one sent is taken as
+    /// a counter reset and sent whole.
+    ///
+    /// Disable it for counters that report per-period values themselves,
+    /// such as those wrapped in [`Resettable`](crate::adapters::Resettable).
+    pub fn with_delta_tracking(mut self, enabled: bool) -> Self {
+        self.last_seen = enabled.then(DeltaTracker::default);
+        self
+    }
+
+    /// Renders counters as StatsD lines, without trailing newlines.
+    ///
+    /// With delta tracking, counters and histograms are rendered as the
+    /// difference from the last [`send()`](Self::send).
+    pub fn render_lines<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Vec<String> {
+        self.staged_lines(counters, &mut Pending::default())
+    }
+
+    /// Renders counters as packets of newline-separated lines, each at most
+    /// the maximum packet size unless it holds a single longer line.
+    pub fn render<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Vec<String> {
+        self.packets(self.render_lines(counters))
+    }
+
+    /// Renders counters as StatsD lines, adding the values whose deltas are
+    /// rendered to `pending`.
+    fn staged_lines<'a>(
+        &self,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+        pending: &mut Pending,
+    ) -> Vec<String> {
+        let mut lines = Vec::new();
+        for counter in counters {
+            for entry in counter.expand() {
+                let entry = self.delta(entry, pending);
+                self.render_entry(&entry, &mut lines);
+            }
+        }
+        lines
+    }
+
+    /// Batches lines into packets.
+    fn packets(&self, lines: Vec<String>) -> Vec<String> {
+        let mut packets = Vec::new();
+        let mut packet = String::new();
+        for line in lines {
+            if !packet.is_empty() && packet.len() + 1 + line.len() > self.max_packet_size {
+                packets.push(std::mem::take(&mut packet));
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(&line);
+        }
+        if !packet.is_empty() {
+            packets.push(packet);
+        }
+        packets
+    }
+
+    /// Sends counters through a connected UDP socket, returning the number
+    /// of packets sent.
+    ///
+    /// With delta tracking, the values sent are recorded once every packet
+    /// has been sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if sending a packet fails.
+    pub fn send<'a>(
+        &self,
+        socket: &UdpSocket,
+        counters: impl Iterator<Item = &'a dyn Observable>,
+    ) -> Result<usize> {
+        let mut pending = Pending::default();
+        let packets = self.packets(self.staged_lines(counters, &mut pending));
+        for packet in &packets {
+            socket.send(packet.as_bytes())?;
+        }
+        if let Some(last_seen) = &self.last_seen {
+            last_seen.commit(pending);
+        }
+        Ok(packets.len())
+    }
+
+    /// Returns a sender to the StatsD agent at `target`, for use as an
+    /// [`Observer`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be created or connected.
+    pub fn sender(self, target: impl ToSocketAddrs) -> Result<StatsdSender> {
+        Ok(StatsdSender {
+            socket: connect(target)?,
+            observer: self,
+        })
+    }
+
+    /// Starts a reporter thread sending the counters of `source` to
+    /// `target` every `interval`.
+    ///
+    /// Send errors are ignored: StatsD over UDP is fire-and-forget.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the socket cannot be created or connected, or the
+    /// thread cannot be spawned.
+    pub fn reporter<F>(
+        self,
+        target: impl ToSocketAddrs,
+        interval: Duration,
+        source: F,
+    ) -> Result<StatsdReporter>
+    where
+        F: Fn() -> Vec<&'static dyn Observable> + Send + 'static,
+    {
+        let socket = connect(target)?;
+        let (stop, stopped) = mpsc::channel::<()>();
+        let thread = thread::Builder::new()
+            .name("contatori-statsd".to_string())
+            .spawn(move || loop {
+                // Woken up early when the handle is dropped
+                let last = !matches!(
+                    stopped.recv_timeout(interval),
+                    Err(RecvTimeoutError::Timeout)
+                );
+                let _ = self.send(&socket, source().into_iter());
+                if last {
+                    break;
+                }
+            })?;
+        Ok(StatsdReporter {
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+
+    /// Replaces the value or distribution of a counter or histogram entry
+    /// with its difference from the last one sent.
+    fn delta(&self, mut entry: ObservableEntry, pending: &mut Pending) -> ObservableEntry {
+        let Some(last_seen) = &self.last_seen else {
+            return entry;
+        };
+        if entry.metric_kind == MetricKind::Gauge {
+            return entry;
+        }
+        let key = entry_key(
+            &entry.name,
+            entry.labels.iter().map(|(k, v)| (*k, v.as_ref())),
+        );
+        last_seen.stage(key, &mut entry.value, &mut entry.distribution, pending);
+        if let Some(distribution) = &entry.distribution {
+            entry.value = CounterValue::Unsigned(distribution.count);
+        }
+        entry
+    }
+
+    /// Renders the lines of one entry.
+    fn render_entry(&self, entry: &ObservableEntry, lines: &mut Vec<String>) {
+        let statsd_type =
+            self.types
+                .get(entry.name.as_ref())
+                .copied()
+                .unwrap_or(match entry.metric_kind {
+                    MetricKind::Counter => StatsdType::Counter,
+                    MetricKind::Gauge => StatsdType::Gauge,
+                    MetricKind::Histogram | MetricKind::Summary if entry.distribution.is_some() => {
+                        self.distribution_type
+                    }
+                    _ => StatsdType::Gauge,
+                });
+        let name = self.line_name(entry);
+        let tags = self.line_tags(entry);
+
+        match (statsd_type, &entry.distribution) {
+            (StatsdType::Timer | StatsdType::Histogram, Some(distribution)) => {
+                for (value, count) in samples(distribution) {
+                    let rate = 1.0 / count as f64;
+                    lines.push(format!(
+                        "{}:{}|{}|@{}{}",
+                        name,
+                        value,
+                        statsd_type.as_str(),
+                        rate,
+                        tags
+                    ));
+                }
+            }
+            (StatsdType::Gauge, _) => {
+                let value = entry.value;
+                // A leading sign makes plain StatsD gauges relative: reset
+                // to zero first to set a negative value
+                if self.flavor == Flavor::Statsd && value.as_f64() < 0.0 {
+                    lines.push(format!("{}:0|g{}", name, tags));
+                }
+                lines.push(format!("{}:{}|g{}", name, format_value(value), tags));
+            }
+            (_, _) => {
+                lines.push(format!(
+                    "{}:{}|{}{}",
+                    name,
+                    format_value(entry.value),
+                    statsd_type.as_str(),
+                    tags
+                ));
+            }
+        }
+    }
+
+    /// Returns the metric name of an entry, with prefix and, for plain
+    /// StatsD, labels folded in.
+    fn line_name(&self, entry: &ObservableEntry) -> String {
+        let mut name = String::new();
+        if let Some(prefix) = &self.prefix {
+            push_sanitized(&mut name, prefix);
+            name.push('.');
+        }
+        push_sanitized(&mut name, &entry.name);
+        if self.flavor == Flavor::Statsd {
+            for (key, value) in &entry.labels {
+                name.push('.');
+                push_sanitized(&mut name, key);
+                name.push('.');
+                push_sanitized(&mut name, value);
+            }
+        }
+        name
+    }
+
+    /// Returns the DogStatsD tag suffix of an entry, empty for plain StatsD
+    /// or without tags.
+    fn line_tags(&self, entry: &ObservableEntry) -> String {
+        if self.flavor == Flavor::Statsd {
+            return String::new();
+        }
+        let tags = self
+            .tags
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(entry.labels.iter().map(|(k, v)| (*k, v.as_ref())));
+        let mut suffix = String::new();
+        for (key, value) in tags {
+            suffix.push_str(if suffix.is_empty() { "|#" } else { "," });
+            push_sanitized(&mut suffix, key);
+            suffix.push(':');
+            push_sanitized(&mut suffix, value);
+        }
+        suffix
+    }
+}
+
+/// Returns a UDP socket connected to `target`.
+fn connect(target: impl ToSocketAddrs) -> Result<UdpSocket> {
+    let target = target.to_socket_addrs()?.next().ok_or_else(|| {
+        super::ObserverError::Metric("statsd target resolves to no address".to_string())
+    })?;
+    let bind = if target.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.connect(target)?;
+    Ok(socket)
+}
+
+/// Formats a value, keeping integers integral.
+fn format_value(value: CounterValue) -> String {
+    match value {
+        CounterValue::Float(v) if !v.is_finite() => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Appends `text` with the characters reserved by the protocol
+/// (`:|@#,` and whitespace) replaced by underscores.
+fn push_sanitized(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            ':' | '|' | '@' | '#' | ',' => out.push('_'),
+            c if c.is_whitespace() => out.push('_'),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Splits a distribution into `(value, count)` samples.
+///
+/// Finite buckets are sampled at their upper bound. Observations above the
+/// last bound are sampled at the mean left over by the finite buckets,
+/// never below the last bound. Summaries, without buckets, yield their mean.
+fn samples(distribution: &Distribution) -> Vec<(f64, u64)> {
+    let mut samples = Vec::new();
+    let mut previous = 0;
+    let mut accounted = 0.0;
+    for &(bound, cumulative) in &distribution.buckets {
+        let count = cumulative.saturating_sub(previous);
+        previous = cumulative;
+        if count > 0 && bound.is_finite() {
+            samples.push((bound, count));
+            accounted += bound * count as f64;
+        }
+    }
+    let overflow = distribution.count.saturating_sub(previous);
+    if overflow > 0 {
+        let mut value = (distribution.sum - accounted) / overflow as f64;
+        if let Some(&(last, _)) = distribution.buckets.last() {
+            value = value.max(last);
+        }
+        samples.push((value, overflow));
+    }
+    samples
+}
+
+/// Sends counters to a StatsD agent over a connected UDP socket.
+///
+/// Created with [`StatsdObserver::sender()`].
+#[derive(Debug)]
+pub struct StatsdSender {
+    observer: StatsdObserver,
+    socket: UdpSocket,
+}
+
+impl StatsdSender {
+    /// Sends counters, returning the number of packets sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if sending a packet fails.
+    pub fn send<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> Result<usize> {
+        self.observer.send(&self.socket, counters)
+    }
+}
+
+impl Observer for StatsdSender {
+    /// Sends counters; StatsD lines carry no timestamp, so `now` is unused.
+    fn observe(&mut self, counters: &[&dyn Observable], _now: SystemTime) -> Result<()> {
+        self.send(counters.iter().copied()).map(drop)
+    }
+}
+
+/// Handle to a background StatsD reporter.
+///
+/// Dropping the handle sends a last report and stops the thread.
+#[derive(Debug)]
+pub struct StatsdReporter {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StatsdReporter {
+    /// Sends a last report and stops the reporter thread.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for StatsdReporter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapters::Resettable;
+    use crate::counters::average::Average;
+    use crate::counters::histogram::Histogram;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::signed::Signed;
+    use crate::counters::unsigned::Unsigned;
+    use crate::testing::HttpRequests;
+
+    fn lines(observer: &StatsdObserver, counters: &[&dyn Observable]) -> Vec<String> {
+        observer.render_lines(counters.iter().copied())
+    }
+
+    /// Renders lines and records their values, like a successful send.
+    fn sent(observer: &StatsdObserver, counters: &[&dyn Observable]) -> Vec<String> {
+        let mut pending = Pending::default();
+        let lines = observer.staged_lines(counters.iter().copied(), &mut pending);
+        if let Some(last_seen) = &observer.last_seen {
+            last_seen.commit(pending);
+        }
+        lines
+    }
+
+    #[test]
+    fn test_counter_deltas() {
+        let requests = Monotone::new().with_name("requests");
+        let observer = StatsdObserver::new();
+
+        requests.add(5);
+        assert_eq!(sent(&observer, &[&requests]), ["requests:5|c"]);
+        requests.add(2);
+        // Rendering does not record anything
+        assert_eq!(lines(&observer, &[&requests]), ["requests:2|c"]);
+        assert_eq!(sent(&observer, &[&requests]), ["requests:2|c"]);
+        assert_eq!(sent(&observer, &[&requests]), ["requests:0|c"]);
+    }
+
+    #[test]
+    fn test_resettable_deltas() {
+        let requests = Resettable::new(Unsigned::new().with_name("requests"));
+        let observer = StatsdObserver::new()
+            .with_type("requests", StatsdType::Counter)
+            .with_delta_tracking(false);
+
+        requests.add(5);
+        assert_eq!(sent(&observer, &[&requests]), ["requests:5|c"]);
+        requests.add(7);
+        assert_eq!(sent(&observer, &[&requests]), ["requests:7|c"]);
+    }
+
+    #[test]
+    fn test_gauges() {
+        let queue = Unsigned::new().with_name("queue");
+        let balance = Signed::new().with_name("balance");
+        queue.add(3);
+        balance.sub(4);
+
+        let observer = StatsdObserver::new();
+        assert_eq!(
+            lines(&observer, &[&queue, &balance]),
+            ["queue:3|g", "balance:0|g", "balance:-4|g"]
+        );
+
+        let observer = StatsdObserver::new().with_flavor(Flavor::DogStatsd);
+        assert_eq!(lines(&observer, &[&balance]), ["balance:-4|g"]);
+    }
+
+    #[test]
+    fn test_histogram_samples() {
+        static BOUNDS: [f64; 3] = [1.0, 5.0, 10.0];
+        let latency = Histogram::new(&BOUNDS).with_name("latency");
+        for value in [0.5, 0.7, 3.0, 40.0] {
+            latency.observe(value);
+        }
+
+        // The overflow sample is the mean left over by the finite buckets
+        let observer = StatsdObserver::new();
+        assert_eq!(
+            sent(&observer, &[&latency]),
+            ["latency:1|ms|@0.5", "latency:5|ms|@1", "latency:37.2|ms|@1"]
+        );
+        // Only new observations are sent
+        assert!(sent(&observer, &[&latency]).is_empty());
+        latency.observe(2.0);
+        assert_eq!(sent(&observer, &[&latency]), ["latency:5|ms|@1"]);
+
+        let latency = Resettable::new(Histogram::new(&BOUNDS).with_name("latency"));
+        latency.observe(7.0);
+        let observer = StatsdObserver::new()
+            .with_distribution_type(StatsdType::Histogram)
+            .with_delta_tracking(false);
+        assert_eq!(sent(&observer, &[&latency]), ["latency:10|h|@1"]);
+        latency.observe(7.0);
+        assert_eq!(sent(&observer, &[&latency]), ["latency:10|h|@1"]);
+    }
+
+    #[test]
+    fn test_type_override() {
+        let latency = Average::new().with_name("latency");
+        latency.observe(10);
+        latency.observe(20);
+        let observer = StatsdObserver::new().with_type("latency", StatsdType::Timer);
+        assert_eq!(lines(&observer, &[&latency]), ["latency:15|ms"]);
+    }
+
+    #[test]
+    fn test_labels() {
+        let requests = HttpRequests::new();
+        requests.get.add(2);
+        requests.post.add(1);
+
+        let observer = StatsdObserver::new()
+            .with_prefix("app")
+            .with_tag("env", "prod");
+        let rendered = lines(&observer, &[&requests]);
+        assert!(rendered.contains(&"app.http_requests.method.GET:2|c".to_string()));
+        assert!(rendered.contains(&"app.http_requests:0|c".to_string()));
+
+        let observer = StatsdObserver::new()
+            .with_prefix("app")
+            .with_tag("env", "prod")
+            .with_flavor(Flavor::DogStatsd);
+        let rendered = lines(&observer, &[&requests]);
+        assert!(rendered.contains(&"app.http_requests:2|c|#env:prod,method:GET".to_string()));
+        assert!(rendered.contains(&"app.http_requests:0|c|#env:prod".to_string()));
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let odd = Unsigned::new().with_name("a:b|c d");
+        let observer = StatsdObserver::new()
+            .with_flavor(Flavor::DogStatsd)
+            .with_tag("k", "v,w");
+        assert_eq!(lines(&observer, &[&odd]), ["a_b_c_d:0|g|#k:v_w"]);
+    }
+
+    #[test]
+    fn test_batching() {
+        let counters: Vec<Unsigned> = (0..10)
+            .map(|i| Unsigned::new().with_owned_name(format!("metric_{}", i)))
+            .collect();
+        let counters: Vec<&dyn Observable> = counters.iter().map(|c| c as _).collect();
+
+        // "metric_N:0|g" is 12 bytes: three lines and two newlines fit in 40
+        let observer = StatsdObserver::new().with_max_packet_size(40);
+        let packets = observer.render(counters.iter().copied());
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets[0], "metric_0:0|g\nmetric_1:0|g\nmetric_2:0|g");
+        assert!(packets.iter().all(|p| p.len() <= 40));
+
+        // A line longer than the limit gets its own packet
+        let observer = StatsdObserver::new().with_max_packet_size(5);
+        assert_eq!(observer.render(counters.iter().copied()).len(), 10);
+    }
+
+    #[test]
+    fn test_send_udp() {
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(agent.local_addr().unwrap()).unwrap();
+
+        let queue = Unsigned::new().with_name("queue");
+        queue.add(9);
+        let sent = StatsdObserver::new()
+            .send(&socket, [&queue as &dyn Observable].into_iter())
+            .unwrap();
+        assert_eq!(sent, 1);
+
+        let mut buf = [0u8; 1500];
+        let n = agent.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"queue:9|g");
+    }
+
+    #[test]
+    fn test_sender_observer() {
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let queue = Unsigned::new().with_name("queue");
+        queue.add(4);
+
+        let mut sender = StatsdObserver::new()
+            .sender(agent.local_addr().unwrap())
+            .unwrap();
+        sender.observe(&[&queue], SystemTime::now()).unwrap();
+
+        let mut buf = [0u8; 1500];
+        let n = agent.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"queue:4|g");
+    }
+
+    #[test]
+    fn test_send_monotone_increments_by_default() {
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(agent.local_addr().unwrap()).unwrap();
+
+        let requests = Monotone::new().with_name("requests");
+        let counters: [&dyn Observable; 1] = [&requests];
+        let observer = StatsdObserver::new();
+
+        requests.add(5);
+        observer.send(&socket, counters.into_iter()).unwrap();
+        requests.add(2);
+        observer.send(&socket, counters.into_iter()).unwrap();
+
+        let mut buf = [0u8; 1500];
+        let n = agent.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"requests:5|c");
+        // Only the increment, not the cumulative 7
+        let n = agent.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"requests:2|c");
+    }
+
+    #[test]
+    fn test_reporter() {
+        static EVENTS: Monotone = Monotone::new().with_name("events");
+
+        let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        agent
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let reporter = StatsdObserver::new()
+            .reporter(
+                agent.local_addr().unwrap(),
+                Duration::from_millis(10),
+                || vec![&EVENTS as &'static dyn Observable],
+            )
+            .unwrap();
+
+        EVENTS.add(3);
+        let mut total = 0;
+        let mut buf = [0u8; 1500];
+        while total < 3 {
+            let n = agent.recv(&mut buf).unwrap();
+            let line = std::str::from_utf8(&buf[..n]).unwrap();
+            let value = line.strip_prefix("events:").unwrap().strip_suffix("|c");
+            total += value.unwrap().parse::<u64>().unwrap();
+        }
+        assert_eq!(total, 3);
+
+        // The last report is sent on shutdown
+        EVENTS.add(4);
+        reporter.shutdown();
+        let mut total = 0;
+        while let Ok(n) = agent.recv(&mut buf) {
+            let line = std::str::from_utf8(&buf[..n]).unwrap();
+            total += line
+                .strip_prefix("events:")
+                .and_then(|v| v.strip_suffix("|c"))
+                .unwrap()
+                .parse::<u64>()
+                .unwrap();
+            if total == 4 {
+                break;
+            }
+        }
+        assert_eq!(total, 4);
+    }
+}
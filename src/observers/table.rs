@@ -56,7 +56,11 @@
 //! // ╰────────────────┴────────────┴──────────────╯
 //! ```
 
+use super::{Render, Result};
 use crate::counters::Observable;
+use std::borrow::Cow;
+use std::io;
+use std::time::SystemTime;
 use tabled::{builder::Builder, settings::Style, Table, Tabled};
 
 /// Available table styles for rendering.
@@ -156,6 +160,8 @@ struct CounterRow {
     labels: String,
     #[tabled(rename = "Value")]
     value: String,
+    #[tabled(rename = "Unit")]
+    unit: String,
 }
 
 /// An observer that renders counters as a formatted ASCII table.
@@ -342,18 +348,24 @@ impl TableObserver {
     /// Renders counters in compact mode (grid layout).
     fn render_compact<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> String {
         let cells: Vec<String> = counters
-            .flat_map(|c| c.expand())
-            .map(|entry| {
+            .flat_map(|c| {
+                let unit = c.unit();
+                c.expand().into_iter().map(move |entry| (entry, unit))
+            })
+            .map(|(entry, unit)| {
                 let name = if entry.name.is_empty() {
                     "(unnamed)".to_string()
-                } else if entry.label.is_none() {
+                } else if entry.labels.is_empty() {
                     entry.name.to_string()
                 } else {
-                    // Format as name{label=value}
-                    let (k, v) = entry.label.as_ref().unwrap();
-                    format!("{}{{{}={}}}", entry.name, k, v)
+                    // Format as name{key=value,key=value}
+                    format!("{}{{{}}}", entry.name, format_labels(&entry.labels))
+                };
+                let value = match unit {
+                    Some(unit) => format!("{} {}", entry.value, unit),
+                    None => entry.value.to_string(),
                 };
-                self.format_compact_cell(&name, &entry.value.to_string())
+                self.format_compact_cell(&name, &value)
             })
             .collect();
 
@@ -383,15 +395,16 @@ impl TableObserver {
         }
     }
 
-    /// Renders counters in standard mode (three-column table).
+    /// Renders counters in standard mode (name, labels and value columns, plus
+    /// a unit column when some counter has a unit).
     fn render_standard<'a>(&self, counters: impl Iterator<Item = &'a dyn Observable>) -> String {
         let rows: Vec<CounterRow> = counters
-            .flat_map(|c| c.expand())
-            .map(|entry| {
-                let labels_str = match &entry.label {
-                    None => String::new(),
-                    Some((k, v)) => format!("{}={}", k, v),
-                };
+            .flat_map(|c| {
+                let unit = c.unit();
+                c.expand().into_iter().map(move |entry| (entry, unit))
+            })
+            .map(|(entry, unit)| {
+                let labels_str = format_labels(&entry.labels);
                 CounterRow {
                     name: if entry.name.is_empty() {
                         "(unnamed)".to_string()
@@ -400,13 +413,22 @@ impl TableObserver {
                     },
                     labels: labels_str,
                     value: entry.value.to_string(),
+                    unit: unit.map(|u| u.to_string()).unwrap_or_default(),
                 }
             })
             .collect();
 
+        let has_unit = rows.iter().any(|row| !row.unit.is_empty());
+
         let mut table = Table::new(&rows);
         self.apply_style(&mut table);
 
+        if !has_unit {
+            table.with(tabled::settings::Remove::column(
+                tabled::settings::object::Columns::last(),
+            ));
+        }
+
         if !self.config.show_header {
             table.with(tabled::settings::Remove::row(
                 tabled::settings::object::Rows::first(),
@@ -463,6 +485,28 @@ impl TableObserver {
     }
 }
 
+impl Render for TableObserver {
+    /// Writes the table followed by a newline.
+    fn render_to(
+        &self,
+        counters: &[&dyn Observable],
+        _now: SystemTime,
+        out: &mut dyn io::Write,
+    ) -> Result<()> {
+        writeln!(out, "{}", self.render(counters.iter().copied()))?;
+        Ok(())
+    }
+}
+
+/// Formats labels as a comma-separated `key=value` list.
+fn format_labels(labels: &[(&str, Cow<'_, str>)]) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -471,6 +515,7 @@ mod tests {
     use crate::counters::minimum::Minimum;
     use crate::counters::signed::Signed;
     use crate::counters::unsigned::Unsigned;
+    use crate::testing::HttpMetrics;
 
     #[test]
     fn test_render_empty() {
@@ -501,6 +546,36 @@ mod tests {
         assert!(output.contains("42"));
     }
 
+    #[test]
+    fn test_render_unit() {
+        let counter = Unsigned::new()
+            .with_name("heap")
+            .with_unit(crate::counters::Unit::Bytes);
+        counter.add(4096);
+
+        let counters: Vec<&dyn Observable> = vec![&counter];
+
+        let output = TableObserver::new().render(counters.iter().copied());
+        assert!(output.contains("Unit"));
+        assert!(output.contains("bytes"));
+
+        let output = TableObserver::new()
+            .compact(true)
+            .render(counters.iter().copied());
+        assert!(output.contains("heap: 4096 bytes"));
+    }
+
+    #[test]
+    fn test_render_without_unit() {
+        let counter = Unsigned::new().with_name("requests");
+        counter.add(42);
+
+        let counters: Vec<&dyn Observable> = vec![&counter];
+        let output = TableObserver::new().render(counters.into_iter());
+        assert!(output.contains("Value"));
+        assert!(!output.contains("Unit"));
+    }
+
     #[test]
     fn test_render_compact_single() {
         let counter = Unsigned::new().with_name("requests");
@@ -759,6 +834,25 @@ mod tests {
         assert!(output.contains("avg: 150"));
     }
 
+    #[test]
+    fn test_render_multiple_labels() {
+        let observer = TableObserver::new();
+        let counters: Vec<&dyn Observable> = vec![&HttpMetrics];
+        let output = observer.render(counters.into_iter());
+
+        assert!(output.contains("method=GET,route=/users,status=200"));
+        assert!(output.contains("method=POST,route=/orders,status=500"));
+    }
+
+    #[test]
+    fn test_render_compact_multiple_labels() {
+        let observer = TableObserver::new().compact(true);
+        let counters: Vec<&dyn Observable> = vec![&HttpMetrics];
+        let output = observer.render(counters.into_iter());
+
+        assert!(output.contains("http_requests{method=GET,route=/users,status=200}: 7"));
+    }
+
     #[test]
     fn test_config_builder() {
         let config = TableConfig {
@@ -0,0 +1,832 @@
+//! A registry of counters, fed to observers as a whole.
+//!
+//! Observers take an `impl Iterator<Item = &dyn Observable>`, which is
+//! usually assembled by hand. The [`Registry`] keeps that list instead:
+//! statics register themselves at link time with [`register!`](crate::register),
+//! counters created at runtime register with [`Registry::register`], and
+//! [`Registry::iter`] yields all of them, ready to be handed to any observer.
+//!
+//! # Feature Flag
+//!
+//! This module requires the `registry` feature:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["registry"] }
+//! ```
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! use contatori::counters::monotone::Monotone;
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::observers::prometheus::PrometheusObserver;
+//! use contatori::registry::Registry;
+//!
+//! static REQUESTS: Monotone = Monotone::new().with_name("requests");
+//! static ERRORS: Monotone = Monotone::new().with_name("errors");
+//!
+//! // Registered at link time, no list to maintain
+//! contatori::register!(REQUESTS, ERRORS);
+//!
+//! // Counters created at runtime live as long as the program
+//! let queue = Registry::global().register_owned(Unsigned::new().with_name("queue_len"))?;
+//! queue.add(3);
+//!
+//! let output = PrometheusObserver::new().render(Registry::global().iter()?)?;
+//! ```
+//!
+//! # Scopes
+//!
+//! A library can own a namespace with a registry of its own, built with a
+//! prefix and constant labels by [`Registry::scoped`] and attached to a parent with [`register_scope!`](crate::register_scope) or
+//! [`Registry::add_child`]. The parent yields the counters of the child with
+//! the prefix and labels applied, so every observer (JSON, table,
+//! Prometheus, OpenTelemetry) sees the same scoped names:
+//!
+//! ```rust,ignore
+//! static DB: Registry = Registry::scoped("db_", &[("component", "db")]);
+//! static QUERIES: Monotone = Monotone::new().with_name("queries");
+//!
+//! contatori::register!(in DB => QUERIES);
+//! contatori::register_scope!(DB);
+//!
+//! // db_queries{component="db"}
+//! let output = PrometheusObserver::new().render(Registry::global().iter()?)?;
+//! ```
+//!
+//! # Duplicate Names
+//!
+//! Two counters with the same name would be merged or rejected by most
+//! backends. [`Registry::register`] refuses a name that is already taken, and
+//! [`Registry::iter`] checks link-time registrations as well, both returning
+//! [`ObserverError::DuplicateMetric`]. Unnamed counters are never considered
+//! duplicates.
+
+use crate::counters::{
+    CounterValue, Distribution, MetricKind, Observable, ObservableEntry, Stability, Unit,
+};
+use crate::observers::{ObserverError, Result};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Mutex, RwLock};
+
+/// What a link-time registration adds to its registry.
+#[doc(hidden)]
+pub enum StaticItem {
+    /// A counter, registered with [`register!`](crate::register).
+    Counter(&'static (dyn Observable + Sync)),
+    /// A child registry, attached with [`register_scope!`](crate::register_scope).
+    Scope(&'static Registry),
+}
+
+/// A link-time registration.
+///
+/// Not meant to be constructed directly.
+#[doc(hidden)]
+pub struct StaticEntry {
+    pub parent: &'static Registry,
+    pub item: StaticItem,
+}
+
+inventory::collect!(StaticEntry);
+
+#[doc(hidden)]
+pub mod __private {
+    pub use inventory;
+}
+
+/// Registers statics in a registry at link time.
+///
+/// Without a target, counters go to the [global](Registry::global) registry;
+/// `in REGISTRY =>` registers them in a static [`Registry`] instead. The macro
+/// can be used at module level anywhere in the program, including in
+/// libraries: every registration is collected when the binary is linked.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::registry::Registry;
+///
+/// static HITS: Monotone = Monotone::new().with_name("cache_hits");
+/// static MISSES: Monotone = Monotone::new().with_name("cache_misses");
+///
+/// contatori::register!(HITS, MISSES);
+///
+/// static DB: Registry = Registry::scoped("db_", &[]);
+/// static QUERIES: Monotone = Monotone::new().with_name("queries");
+///
+/// contatori::register!(in DB => QUERIES);
+/// ```
+#[macro_export]
+macro_rules! register {
+    (in $registry:path => $($counter:path),+ $(,)?) => {
+        $(
+            $crate::registry::__private::inventory::submit! {
+                $crate::registry::StaticEntry {
+                    parent: &$registry,
+                    item: $crate::registry::StaticItem::Counter(&$counter),
+                }
+            }
+        )+
+    };
+    ($($counter:path),+ $(,)?) => {
+        $crate::register!(in $crate::registry::GLOBAL => $($counter),+);
+    };
+}
+
+/// Attaches static child registries to a parent at link time.
+///
+/// Without a target, children are attached to the
+/// [global](Registry::global) registry.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use contatori::registry::Registry;
+///
+/// static DB: Registry = Registry::scoped("db_", &[("component", "db")]);
+///
+/// contatori::register_scope!(DB);
+/// ```
+#[macro_export]
+macro_rules! register_scope {
+    (in $registry:path => $($child:path),+ $(,)?) => {
+        $(
+            $crate::registry::__private::inventory::submit! {
+                $crate::registry::StaticEntry {
+                    parent: &$registry,
+                    item: $crate::registry::StaticItem::Scope(&$child),
+                }
+            }
+        )+
+    };
+    ($($child:path),+ $(,)?) => {
+        $crate::register_scope!(in $crate::registry::GLOBAL => $($child),+);
+    };
+}
+
+/// A collection of counters that can be fed to any observer.
+///
+/// The process-wide registry is returned by [`Registry::global`]. Other
+/// registries are usually statics owned by a library, scoped with a
+/// prefix and constant labels with [`Registry::scoped`], and
+/// attached to a parent as [children](Self::add_child): the parent then
+/// yields their counters with the prefix prepended to the name and the labels
+/// added to every entry, whatever the observer.
+///
+/// Counters are never removed: a registry holds `'static` references, and
+/// [`register_owned`](Self::register_owned) moves a counter into the registry
+/// for the rest of the program.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use contatori::counters::monotone::Monotone;
+/// use contatori::registry::Registry;
+///
+/// static DB: Registry = Registry::scoped("db_", &[("component", "db")]);
+/// static QUERIES: Monotone = Monotone::new().with_name("queries");
+///
+/// contatori::register!(in DB => QUERIES);
+/// contatori::register_scope!(DB);
+///
+/// // db_queries{component="db"}
+/// let output = PrometheusObserver::new().render(Registry::global().iter()?)?;
+/// ```
+#[derive(Debug)]
+pub struct Registry {
+    /// Prefix prepended to the name of every counter.
+    prefix: &'static str,
+    /// Labels added to every entry.
+    labels: &'static [(&'static str, &'static str)],
+    /// Counters registered at runtime, in registration order.
+    dynamic: RwLock<Vec<&'static (dyn Observable + Sync)>>,
+    /// Children attached at runtime, in attachment order.
+    children: RwLock<Vec<&'static Registry>>,
+    /// Scoped views of the counters, keyed by [registration path](Self::entries).
+    scoped: Mutex<BTreeMap<Vec<usize>, &'static Scoped>>,
+}
+
+#[doc(hidden)]
+pub static GLOBAL: Registry = Registry::new();
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Registry {
+    /// Creates an empty registry, with no prefix and no labels.
+    pub const fn new() -> Self {
+        Self {
+            prefix: "",
+            labels: &[],
+            dynamic: RwLock::new(Vec::new()),
+            children: RwLock::new(Vec::new()),
+            scoped: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Creates an empty registry that scopes its counters.
+    ///
+    /// `prefix` is prepended to the name of every counter in this registry
+    /// and its children, and `labels` are added to every entry, before the
+    /// labels of the entry itself. Prefixes and labels of nested registries
+    /// accumulate, outermost first. A label whose key the entry (or a nested
+    /// registry) already sets is skipped, so the innermost value wins.
+    /// Unnamed counters stay unnamed.
+    pub const fn scoped(
+        prefix: &'static str,
+        labels: &'static [(&'static str, &'static str)],
+    ) -> Self {
+        Self {
+            prefix,
+            labels,
+            dynamic: RwLock::new(Vec::new()),
+            children: RwLock::new(Vec::new()),
+            scoped: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the process-wide registry.
+    ///
+    /// It also yields the statics registered with [`register!`](crate::register)
+    /// and the children attached with [`register_scope!`](crate::register_scope)
+    /// when no target registry is given.
+    pub fn global() -> &'static Registry {
+        &GLOBAL
+    }
+
+    /// Returns the prefix of this registry.
+    pub fn prefix(&self) -> &'static str {
+        self.prefix
+    }
+
+    /// Returns the constant labels of this registry.
+    pub fn labels(&self) -> &'static [(&'static str, &'static str)] {
+        self.labels
+    }
+
+    /// Registers a counter.
+    ///
+    /// Returns [`ObserverError::DuplicateMetric`] if a counter with the same
+    /// name is already registered.
+    pub fn register(&self, counter: &'static (dyn Observable + Sync)) -> Result<()> {
+        self.insert(counter.name(), || ((), counter))
+    }
+
+    /// Moves a counter into the registry and returns a reference to it.
+    ///
+    /// The counter lives for the rest of the program, like a static.
+    pub fn register_owned<T>(&self, counter: T) -> Result<&'static T>
+    where
+        T: Observable + Sync + 'static,
+    {
+        let name = counter.name().to_string();
+        // Leaked only once the name is known to be free
+        self.insert(&name, || {
+            let counter: &'static T = Box::leak(Box::new(counter));
+            (counter, counter)
+        })
+    }
+
+    /// Registers the counter returned by `leak` unless `name` is taken,
+    /// checking and registering under one lock so that concurrent
+    /// registrations of a name cannot both succeed.
+    fn insert<R>(
+        &self,
+        name: &str,
+        leak: impl FnOnce() -> (R, &'static (dyn Observable + Sync)),
+    ) -> Result<R> {
+        let mut dynamic = self.dynamic.write().unwrap_or_else(|e| e.into_inner());
+        let taken = !name.is_empty()
+            && self
+                .statics()
+                .filter_map(|item| match item {
+                    StaticItem::Counter(c) => Some(*c),
+                    StaticItem::Scope(_) => None,
+                })
+                .chain(dynamic.iter().copied())
+                .any(|c| c.name() == name);
+        if taken {
+            return Err(ObserverError::DuplicateMetric(name.to_string()));
+        }
+        let (registered, counter) = leak();
+        dynamic.push(counter);
+        Ok(registered)
+    }
+
+    /// Attaches a child registry.
+    ///
+    /// The counters of the child are yielded by this registry, scoped by the
+    /// prefix and labels of both. Returns [`ObserverError::Metric`] if the
+    /// child is this registry or one of its ancestors.
+    pub fn add_child(&'static self, child: &'static Registry) -> Result<()> {
+        if child.reaches(self) {
+            return Err(ObserverError::Metric(
+                "a registry cannot be its own descendant".to_string(),
+            ));
+        }
+        self.children
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(child);
+        Ok(())
+    }
+
+    /// Returns `true` if a counter with the given name is registered, taking
+    /// prefixes into account.
+    pub fn contains(&self, name: &str) -> bool {
+        self.snapshot().iter().any(|c| c.name() == name)
+    }
+
+    /// Returns the number of registered counters, including those of the
+    /// children.
+    pub fn len(&self) -> usize {
+        self.snapshot().len()
+    }
+
+    /// Returns `true` if no counter is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over all registered counters, to be passed to an
+    /// observer.
+    ///
+    /// Counters of this registry come first, followed by those of its
+    /// children. Link-time registrations come before runtime ones, in
+    /// unspecified order.
+    ///
+    /// Returns [`ObserverError::DuplicateMetric`] if two counters share the
+    /// same name once scoped.
+    pub fn iter(&self) -> Result<impl Iterator<Item = &'static dyn Observable>> {
+        let counters = self.snapshot();
+        let mut seen = HashSet::new();
+        for counter in &counters {
+            let name = counter.name();
+            if !name.is_empty() && !seen.insert(name) {
+                return Err(ObserverError::DuplicateMetric(name.to_string()));
+            }
+        }
+        Ok(counters.into_iter().map(|c| c as &'static dyn Observable))
+    }
+
+    /// Link-time registrations targeting this registry.
+    fn statics(&self) -> impl Iterator<Item = &'static StaticItem> + '_ {
+        inventory::iter::<StaticEntry>
+            .into_iter()
+            .filter(move |e| std::ptr::eq(e.parent, self))
+            .map(|e| &e.item)
+    }
+
+    /// Counters registered directly in this registry, unscoped.
+    fn own(&self) -> impl Iterator<Item = &'static (dyn Observable + Sync)> {
+        let dynamic = self.dynamic.read().unwrap_or_else(|e| e.into_inner());
+        self.statics()
+            .filter_map(|item| match item {
+                StaticItem::Counter(c) => Some(*c),
+                StaticItem::Scope(_) => None,
+            })
+            .chain(dynamic.iter().copied())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Children attached to this registry.
+    fn children(&self) -> Vec<&'static Registry> {
+        let dynamic = self.children.read().unwrap_or_else(|e| e.into_inner());
+        self.statics()
+            .filter_map(|item| match item {
+                StaticItem::Scope(r) => Some(*r),
+                StaticItem::Counter(_) => None,
+            })
+            .chain(dynamic.iter().copied())
+            .collect()
+    }
+
+    /// Returns `true` if `target` is this registry or one of its descendants.
+    fn reaches(&self, target: &Registry) -> bool {
+        std::ptr::eq(self, target) || self.children().iter().any(|c| c.reaches(target))
+    }
+
+    /// All counters of this registry and its children, scoped by this registry.
+    fn snapshot(&self) -> Vec<&'static (dyn Observable + Sync)> {
+        self.entries().into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// Like [`snapshot`](Self::snapshot), with the registration path of every
+    /// counter.
+    ///
+    /// The path identifies a registration, not a counter: `[0, i]` is the
+    /// `i`-th counter of this registry and `[1, j, ..]` the counter at the
+    /// remaining path in its `j`-th child. Registrations are never removed
+    /// and link-time ones come first, so a path always designates the same
+    /// registration.
+    fn entries(&self) -> Vec<(Vec<usize>, &'static (dyn Observable + Sync))> {
+        let own = self.own().enumerate().map(|(i, c)| (vec![0, i], c));
+        let children = self
+            .children()
+            .into_iter()
+            .enumerate()
+            .flat_map(|(j, child)| {
+                child.entries().into_iter().map(move |(path, c)| {
+                    let mut key = vec![1, j];
+                    key.extend(path);
+                    (key, c)
+                })
+            });
+        let counters = own.chain(children);
+        if self.prefix.is_empty() && self.labels.is_empty() {
+            return counters.collect();
+        }
+
+        let mut scoped = self.scoped.lock().unwrap_or_else(|e| e.into_inner());
+        counters
+            .map(|(key, inner)| {
+                let view: &'static Scoped = scoped
+                    .entry(key.clone())
+                    .or_insert_with(|| Box::leak(Box::new(Scoped::new(self, inner))));
+                (key, view as &'static (dyn Observable + Sync))
+            })
+            .collect()
+    }
+}
+
+/// A counter seen through a scoped [`Registry`].
+///
+/// Created once per registration and registry, and kept for the rest of the
+/// program like the counter itself.
+#[derive(Debug)]
+struct Scoped {
+    inner: &'static (dyn Observable + Sync),
+    name: String,
+    labels: &'static [(&'static str, &'static str)],
+}
+
+impl Scoped {
+    fn new(registry: &Registry, inner: &'static (dyn Observable + Sync)) -> Self {
+        let name = match inner.name() {
+            "" => String::new(),
+            name => format!("{}{}", registry.prefix, name),
+        };
+        Self {
+            inner,
+            name,
+            labels: registry.labels,
+        }
+    }
+}
+
+impl Observable for Scoped {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn metric_kind(&self) -> MetricKind {
+        self.inner.metric_kind()
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        self.inner.description()
+    }
+
+    fn unit(&self) -> Option<Unit> {
+        self.inner.unit()
+    }
+
+    fn stability(&self) -> Stability {
+        self.inner.stability()
+    }
+
+    fn is_additive(&self) -> bool {
+        self.inner.is_additive()
+    }
+
+    fn value(&self) -> CounterValue {
+        self.inner.value()
+    }
+
+    fn distribution(&self) -> Option<Distribution> {
+        self.inner.distribution()
+    }
+
+    fn expand(&self) -> Vec<ObservableEntry> {
+        let mut entries = self.inner.expand();
+        for entry in &mut entries {
+            if !self.name.is_empty() {
+                entry.name = Cow::Owned(self.name.clone());
+            }
+            let labels = std::mem::take(&mut entry.labels);
+            let mut scoped: Vec<_> = self
+                .labels
+                .iter()
+                .filter(|(k, _)| !labels.iter().any(|(key, _)| key == k))
+                .map(|&(k, v)| (k, Cow::Borrowed(v)))
+                .collect();
+            scoped.extend(labels);
+            entry.labels = scoped;
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::counters::monotone::Monotone;
+    use crate::counters::unsigned::Unsigned;
+
+    static LINKED: Monotone = Monotone::new().with_name("registry_test_linked");
+    static UNNAMED: Unsigned = Unsigned::new();
+
+    crate::register!(LINKED, UNNAMED);
+
+    #[test]
+    fn test_global_contains_linked_statics() {
+        let global = Registry::global();
+        assert!(global.contains("registry_test_linked"));
+
+        LINKED.add(3);
+        let linked = global
+            .iter()
+            .unwrap()
+            .find(|c| c.name() == "registry_test_linked")
+            .unwrap();
+        assert_eq!(linked.value().as_u64(), 3);
+    }
+
+    #[test]
+    fn test_new_registry_is_empty() {
+        let registry = Registry::new();
+        assert!(registry.is_empty());
+        assert!(!registry.contains("registry_test_linked"));
+        assert_eq!(registry.iter().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_register_runtime() {
+        static REQUESTS: Monotone = Monotone::new().with_name("requests");
+
+        let registry = Registry::new();
+        registry.register(&REQUESTS).unwrap();
+        let errors = registry
+            .register_owned(Unsigned::new().with_name("errors"))
+            .unwrap();
+        errors.add(2);
+
+        let names: Vec<_> = registry.iter().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["requests", "errors"]);
+        assert_eq!(registry.len(), 2);
+        assert_eq!(errors.value().as_u64(), 2);
+    }
+
+    #[test]
+    fn test_register_duplicate_name() {
+        static FIRST: Unsigned = Unsigned::new().with_name("dup");
+        static SECOND: Unsigned = Unsigned::new().with_name("dup");
+
+        let registry = Registry::new();
+        registry.register(&FIRST).unwrap();
+        let err = registry.register(&SECOND).unwrap_err();
+        assert!(matches!(err, ObserverError::DuplicateMetric(ref name) if name == "dup"));
+
+        let err = registry
+            .register_owned(Unsigned::new().with_name("dup"))
+            .unwrap_err();
+        assert!(matches!(err, ObserverError::DuplicateMetric(_)));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_duplicate_registrations() {
+        static REGISTRY: Registry = Registry::new();
+
+        let registered: usize = std::thread::scope(|scope| {
+            let threads: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        REGISTRY
+                            .register_owned(Unsigned::new().with_name("raced"))
+                            .is_ok()
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|t| usize::from(t.join().unwrap()))
+                .sum()
+        });
+        assert_eq!(registered, 1);
+        assert_eq!(REGISTRY.len(), 1);
+    }
+
+    #[test]
+    fn test_register_duplicate_of_linked_static() {
+        let err = Registry::global()
+            .register_owned(Monotone::new().with_name("registry_test_linked"))
+            .unwrap_err();
+        assert!(matches!(err, ObserverError::DuplicateMetric(_)));
+    }
+
+    #[test]
+    fn test_unnamed_counters_are_not_duplicates() {
+        static A: Unsigned = Unsigned::new();
+        static B: Unsigned = Unsigned::new();
+
+        let registry = Registry::new();
+        registry.register(&A).unwrap();
+        registry.register(&B).unwrap();
+        assert_eq!(registry.iter().unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_scope_prefix_and_labels() {
+        static DB: Registry = Registry::scoped("db_", &[("component", "db")]);
+        static ROOT: Registry = Registry::new();
+        static QUERIES: Monotone = Monotone::new().with_name("queries");
+
+        DB.register(&QUERIES).unwrap();
+        ROOT.add_child(&DB).unwrap();
+        QUERIES.add(4);
+
+        let counters: Vec<_> = ROOT.iter().unwrap().collect();
+        assert_eq!(counters.len(), 1);
+        assert_eq!(counters[0].name(), "db_queries");
+        assert_eq!(counters[0].value().as_u64(), 4);
+
+        let entries = counters[0].expand();
+        assert_eq!(entries[0].name, "db_queries");
+        assert_eq!(entries[0].labels, vec![("component", "db".into())]);
+
+        // The child scopes its own counters too
+        assert!(DB.contains("db_queries"));
+        // Views are created once per registration
+        let again = ROOT.iter().unwrap().next().unwrap();
+        assert!(std::ptr::addr_eq(counters[0], again));
+    }
+
+    #[test]
+    fn test_nested_scopes_accumulate() {
+        static HTTP: Registry = Registry::scoped("http_", &[("component", "http")]);
+        static V1: Registry = Registry::scoped("v1_", &[("api", "v1")]);
+        static ROOT: Registry = Registry::new();
+
+        HTTP.add_child(&V1).unwrap();
+        ROOT.add_child(&HTTP).unwrap();
+
+        crate::labeled_group!(
+            Requests,
+            "requests",
+            "method",
+            value: Unsigned,
+            get: "GET": Unsigned,
+        );
+        let requests = V1.register_owned(Requests::new()).unwrap();
+        requests.get.add(2);
+
+        let counter = ROOT.iter().unwrap().next().unwrap();
+        let entries = counter.expand();
+        let get = entries.iter().find(|e| e.labels.len() == 3).unwrap();
+        assert_eq!(get.name, "http_v1_requests");
+        assert_eq!(
+            get.labels,
+            vec![
+                ("component", "http".into()),
+                ("api", "v1".into()),
+                ("method", "GET".into()),
+            ]
+        );
+        assert_eq!(get.value.as_u64(), 2);
+    }
+
+    #[test]
+    fn test_scope_labels_are_not_duplicated() {
+        static TENANT: Registry = Registry::scoped("", &[("tenant", "default")]);
+        static ACME: Registry = Registry::scoped("", &[("tenant", "acme")]);
+
+        TENANT.add_child(&ACME).unwrap();
+        ACME.register_owned(Monotone::new().with_name("logins"))
+            .unwrap();
+
+        crate::labeled_group!(
+            Jobs,
+            "jobs",
+            "tenant",
+            value: Unsigned,
+            other: "other": Unsigned,
+        );
+        TENANT.register_owned(Jobs::new()).unwrap();
+
+        let counters: Vec<_> = TENANT.iter().unwrap().collect();
+        let labels: Vec<_> = counters
+            .iter()
+            .flat_map(|c| c.expand())
+            .map(|e| (e.name.into_owned(), e.labels))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("jobs".to_string(), vec![("tenant", "default".into())]),
+                ("jobs".to_string(), vec![("tenant", "other".into())]),
+                ("logins".to_string(), vec![("tenant", "acme".into())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_views_follow_registrations() {
+        #[derive(Debug)]
+        #[repr(C)]
+        struct Pair {
+            first: Monotone,
+            second: Monotone,
+        }
+
+        impl Observable for Pair {
+            fn name(&self) -> &str {
+                "pair"
+            }
+
+            fn value(&self) -> CounterValue {
+                self.first.value()
+            }
+        }
+
+        static SCOPE: Registry = Registry::scoped("pair_", &[]);
+        static PAIR: Pair = Pair {
+            first: Monotone::new().with_name("first"),
+            second: Monotone::new().with_name("second"),
+        };
+
+        // The struct and its first field share the same address
+        SCOPE.register(&PAIR.first).unwrap();
+        SCOPE.register(&PAIR).unwrap();
+        SCOPE.register(&PAIR.second).unwrap();
+
+        let names: Vec<_> = SCOPE.iter().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["pair_first", "pair_pair", "pair_second"]);
+    }
+
+    #[test]
+    fn test_linked_scope() {
+        static CACHE: Registry = Registry::scoped("cache_", &[]);
+        static PARENT: Registry = Registry::new();
+        static HITS: Monotone = Monotone::new().with_name("hits");
+
+        crate::register!(in CACHE => HITS);
+        crate::register_scope!(in PARENT => CACHE);
+
+        assert!(PARENT.contains("cache_hits"));
+        assert!(!Registry::global().contains("cache_hits"));
+    }
+
+    #[test]
+    fn test_add_child_rejects_cycles() {
+        static A: Registry = Registry::new();
+        static B: Registry = Registry::new();
+
+        A.add_child(&B).unwrap();
+        assert!(B.add_child(&A).is_err());
+        assert!(A.add_child(&A).is_err());
+    }
+
+    #[test]
+    fn test_scopes_avoid_duplicates() {
+        static DB: Registry = Registry::scoped("db_", &[]);
+        static CACHE: Registry = Registry::scoped("cache_", &[]);
+        static ROOT: Registry = Registry::new();
+
+        DB.register_owned(Monotone::new().with_name("errors"))
+            .unwrap();
+        CACHE
+            .register_owned(Monotone::new().with_name("errors"))
+            .unwrap();
+        ROOT.add_child(&DB).unwrap();
+        ROOT.add_child(&CACHE).unwrap();
+
+        let names: Vec<_> = ROOT.iter().unwrap().map(|c| c.name()).collect();
+        assert_eq!(names, vec!["db_errors", "cache_errors"]);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_iter_feeds_observer() {
+        use crate::observers::json::JsonObserver;
+
+        let registry = Registry::new();
+        let hits = registry
+            .register_owned(Monotone::new().with_name("hits"))
+            .unwrap();
+        hits.add(5);
+
+        let json = JsonObserver::new()
+            .to_json(registry.iter().unwrap())
+            .unwrap();
+        assert!(json.contains("\"hits\""));
+        assert!(json.contains("5"));
+    }
+}
@@ -30,9 +30,15 @@
 //! let yaml = serde_yaml::to_string(&snapshot).unwrap();
 //! let bytes = bincode::serialize(&snapshot).unwrap();
 //! ```
+//!
+//! For a compact, versioned binary frame, see the [`wire`] module (features
+//! `cbor`, `msgpack` or `bincode`).
+
+use crate::counters::{CounterValue, Distribution, MetricKind, Observable, Stability};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::counters::{CounterValue, Observable};
-use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "cbor", feature = "msgpack", feature = "bincode"))]
+pub mod wire;
 
 /// A snapshot of a single counter's state.
 ///
@@ -44,13 +50,17 @@ use serde::{Deserialize, Serialize};
 /// # Examples
 ///
 /// ```rust,ignore
-/// use contatori::counters::CounterValue;
+/// use contatori::counters::{CounterValue, MetricKind, Stability};
 /// use contatori::snapshot::CounterSnapshot;
 ///
 /// let snapshot = CounterSnapshot {
 ///     name: "requests".to_string(),
 ///     labels: vec![],
 ///     value: CounterValue::Unsigned(42),
+///     unit: None,
+///     kind: MetricKind::Gauge,
+///     distribution: None,
+///     stability: Stability::Stable,
 /// };
 ///
 /// let json = serde_json::to_string(&snapshot).unwrap();
@@ -60,11 +70,58 @@ use serde::{Deserialize, Serialize};
 pub struct CounterSnapshot {
     /// The name of the counter.
     pub name: String,
-    /// Optional label as (key, value) pair (e.g., ("method", "GET")).
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub label: Option<(String, String)>,
+    /// Labels as (key, value) pairs (e.g., `[("method", "GET"), ("status", "200")]`).
+    ///
+    /// Snapshots written before multiple labels were supported, with a
+    /// single optional `label` pair, are still read.
+    #[serde(
+        default,
+        alias = "label",
+        deserialize_with = "deserialize_labels",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub labels: Vec<(String, String)>,
     /// The value of the counter.
     pub value: CounterValue,
+    /// The unit of the counter (e.g., `"bytes"`), if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    /// The kind of the counter, omitted for gauges.
+    #[serde(default, skip_serializing_if = "is_gauge")]
+    pub kind: MetricKind,
+    /// The distribution of a histogram or summary, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<Distribution>,
+    /// The stability of the counter, omitted for stable counters.
+    #[serde(default, skip_serializing_if = "is_stable")]
+    pub stability: Stability,
+}
+
+/// Reads labels either as a list of pairs or as the single optional pair of
+/// the former `label` field.
+fn deserialize_labels<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, String)>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Labels {
+        Pairs(Vec<(String, String)>),
+        Single(Option<(String, String)>),
+    }
+    Ok(match Labels::deserialize(deserializer)? {
+        Labels::Pairs(labels) => labels,
+        Labels::Single(label) => label.into_iter().collect(),
+    })
+}
+
+/// Returns `true` for the default [`MetricKind`], which is not serialized.
+fn is_gauge(kind: &MetricKind) -> bool {
+    *kind == MetricKind::Gauge
+}
+
+/// Returns `true` for the default [`Stability`], which is not serialized.
+fn is_stable(stability: &Stability) -> bool {
+    *stability == Stability::Stable
 }
 
 impl CounterSnapshot {
@@ -72,21 +129,29 @@ impl CounterSnapshot {
     pub fn new(name: impl Into<String>, value: CounterValue) -> Self {
         Self {
             name: name.into(),
-            label: None,
+            labels: Vec::new(),
             value,
+            unit: None,
+            kind: MetricKind::Gauge,
+            distribution: None,
+            stability: Stability::Stable,
         }
     }
 
-    /// Creates a new counter snapshot with a label.
-    pub fn with_label(
+    /// Creates a new counter snapshot with labels.
+    pub fn with_labels(
         name: impl Into<String>,
-        label: Option<(String, String)>,
+        labels: Vec<(String, String)>,
         value: CounterValue,
     ) -> Self {
         Self {
             name: name.into(),
-            label,
+            labels,
             value,
+            unit: None,
+            kind: MetricKind::Gauge,
+            distribution: None,
+            stability: Stability::Stable,
         }
     }
 
@@ -95,6 +160,8 @@ impl CounterSnapshot {
     /// For single counters, returns one snapshot.
     /// For labeled groups, returns multiple snapshots (one per sub-counter).
     pub fn from_observable(counter: &dyn Observable) -> Vec<Self> {
+        let unit = counter.unit().map(|unit| unit.as_str().to_string());
+        let stability = counter.stability();
         counter
             .expand()
             .into_iter()
@@ -104,8 +171,16 @@ impl CounterSnapshot {
                 } else {
                     entry.name.to_string()
                 },
-                label: entry.label.map(|(k, v)| (k.to_string(), v.to_string())),
+                labels: entry
+                    .labels
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.into_owned()))
+                    .collect(),
                 value: entry.value,
+                unit: unit.clone(),
+                kind: entry.metric_kind,
+                distribution: entry.distribution,
+                stability,
             })
             .collect()
     }
@@ -197,23 +272,30 @@ mod tests {
     fn test_counter_snapshot_new() {
         let snapshot = CounterSnapshot::new("test", CounterValue::Unsigned(42));
         assert_eq!(snapshot.name, "test");
-        assert!(snapshot.label.is_none());
+        assert!(snapshot.labels.is_empty());
         assert_eq!(snapshot.value, CounterValue::Unsigned(42));
     }
 
     #[test]
-    fn test_counter_snapshot_with_label() {
-        let snapshot = CounterSnapshot::with_label(
+    fn test_counter_snapshot_with_labels() {
+        let snapshot = CounterSnapshot::with_labels(
             "test",
-            Some(("method".to_string(), "GET".to_string())),
+            vec![
+                ("method".to_string(), "GET".to_string()),
+                ("status".to_string(), "200".to_string()),
+            ],
             CounterValue::Unsigned(42),
         );
         assert_eq!(snapshot.name, "test");
-        assert!(snapshot.label.is_some());
+        assert_eq!(snapshot.labels.len(), 2);
         assert_eq!(
-            snapshot.label.unwrap(),
+            snapshot.labels[0],
             ("method".to_string(), "GET".to_string())
         );
+        assert_eq!(
+            snapshot.labels[1],
+            ("status".to_string(), "200".to_string())
+        );
         assert_eq!(snapshot.value, CounterValue::Unsigned(42));
     }
 
@@ -225,7 +307,7 @@ mod tests {
         let snapshots = CounterSnapshot::from_observable(&counter);
         assert_eq!(snapshots.len(), 1);
         assert_eq!(snapshots[0].name, "requests");
-        assert!(snapshots[0].label.is_none());
+        assert!(snapshots[0].labels.is_empty());
         assert_eq!(snapshots[0].value, CounterValue::Unsigned(100));
     }
 
@@ -321,11 +403,12 @@ mod tests {
         let with_labels: Vec<_> = snapshot
             .counters
             .iter()
-            .filter(|c| c.label.is_some())
+            .filter(|c| !c.labels.is_empty())
             .collect();
         assert_eq!(with_labels.len(), 2);
     }
 
+    #[cfg(feature = "json")]
     #[test]
     fn test_serialize_counter_snapshot() {
         let snapshot = CounterSnapshot::new("test", CounterValue::Unsigned(42));
@@ -334,6 +417,64 @@ mod tests {
         assert!(json.contains("42"));
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_serialize_counter_snapshot_labels() {
+        let snapshot = CounterSnapshot::with_labels(
+            "http_requests",
+            vec![
+                ("method".to_string(), "GET".to_string()),
+                ("status".to_string(), "200".to_string()),
+            ],
+            CounterValue::Unsigned(7),
+        );
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"http_requests","labels":[["method","GET"],["status","200"]],"value":7}"#
+        );
+
+        let back: CounterSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, snapshot);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_serialize_counter_snapshot_unit() {
+        let counter = Unsigned::new()
+            .with_name("heap")
+            .with_unit(crate::counters::Unit::Bytes);
+        counter.add(4096);
+
+        let snapshots = CounterSnapshot::from_observable(&counter);
+        assert_eq!(snapshots[0].unit.as_deref(), Some("bytes"));
+
+        let json = serde_json::to_string(&snapshots[0]).unwrap();
+        assert_eq!(json, r#"{"name":"heap","value":4096,"unit":"bytes"}"#);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_serialize_counter_snapshot_stability() {
+        let counter = Unsigned::new()
+            .with_name("legacy")
+            .with_stability(Stability::Deprecated);
+        counter.add(1);
+
+        let snapshots = CounterSnapshot::from_observable(&counter);
+        assert_eq!(snapshots[0].stability, Stability::Deprecated);
+
+        let json = serde_json::to_string(&snapshots[0]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"legacy","value":1,"stability":"deprecated"}"#
+        );
+
+        let back: CounterSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, snapshots[0]);
+    }
+
+    #[cfg(feature = "json")]
     #[test]
     fn test_deserialize_counter_snapshot() {
         let json = r#"{"name":"test","value":42}"#;
@@ -342,6 +483,19 @@ mod tests {
         assert_eq!(snapshot.value, CounterValue::Unsigned(42));
     }
 
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_deserialize_former_label_field() {
+        let json = r#"{"name":"test","label":["method","GET"],"value":1}"#;
+        let snapshot: CounterSnapshot = serde_json::from_str(json).unwrap();
+        assert_eq!(snapshot.labels, [("method".to_string(), "GET".to_string())]);
+
+        let json = r#"{"name":"test","label":null,"value":1}"#;
+        let snapshot: CounterSnapshot = serde_json::from_str(json).unwrap();
+        assert!(snapshot.labels.is_empty());
+    }
+
+    #[cfg(feature = "json")]
     #[test]
     fn test_serialize_metrics_snapshot() {
         let snapshot = MetricsSnapshot::with_timestamp(
@@ -353,6 +507,7 @@ mod tests {
         assert!(json.contains("1234567890"));
     }
 
+    #[cfg(feature = "json")]
     #[test]
     fn test_deserialize_metrics_snapshot() {
         let json = r#"{"timestamp_ms":1234567890,"counters":[{"name":"a","value":1}]}"#;
@@ -360,4 +515,18 @@ mod tests {
         assert_eq!(snapshot.timestamp_ms, Some(1234567890));
         assert_eq!(snapshot.counters.len(), 1);
     }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_serialize_counter_snapshot_kind() {
+        let counter = crate::counters::monotone::Monotone::new().with_name("requests");
+        counter.add(3);
+
+        let snapshots = CounterSnapshot::from_observable(&counter);
+        assert_eq!(snapshots[0].kind, MetricKind::Counter);
+        let json = serde_json::to_string(&snapshots[0]).unwrap();
+        assert_eq!(json, r#"{"name":"requests","value":3,"kind":"counter"}"#);
+        let back: CounterSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, snapshots[0]);
+    }
 }
@@ -0,0 +1,727 @@
+//! Compact, versioned binary encoding of [`MetricsSnapshot`].
+//!
+//! JSON is convenient but expensive when snapshots are shipped between
+//! processes at high frequency. This module encodes a snapshot into a
+//! self-delimiting binary frame using CBOR, MessagePack or bincode, with
+//! every name, label and unit stored once in a string table.
+//!
+//! # Feature Flags
+//!
+//! Each encoding is gated behind its own feature; the module is available
+//! when at least one of them is enabled:
+//!
+//! ```toml
+//! [dependencies]
+//! contatori = { version = "0.7", features = ["cbor"] } # or "msgpack", "bincode"
+//! ```
+//!
+//! # Wire Format
+//!
+//! A frame is a fixed 11-byte header followed by the payload:
+//!
+//! ```text
+//! offset  size  field
+//! 0       4     magic, b"CNTR"
+//! 4       1     major version
+//! 5       1     minor version
+//! 6       1     encoding: 1 = CBOR, 2 = MessagePack, 3 = bincode
+//! 7       4     payload length in bytes, u32 little-endian
+//! 11      n     payload
+//! ```
+//!
+//! The payload is a sequence of records, each encoded with the frame's
+//! encoding (bincode with variable-length integers). Version 1.0 defines the
+//! first one:
+//!
+//! ```text
+//! (timestamp_ms: Option<u64>,
+//!  strings: [String],
+//!  counters: [(name: u32, labels: [(u32, u32)], value: Value, unit: Option<u32>)])
+//! ```
+//!
+//! Records are tuples, so no field names are repeated. The `u32` fields
+//! index `strings`, and `Value` is an enum of `u(u64)`, `i(i64)` and
+//! `f(f64)`, so integer types survive the round trip exactly.
+//!
+//! Version 1.1 appends the kinds and distributions of the counters that are
+//! not plain gauges:
+//!
+//! ```text
+//! [(counter: u32, kind: u8, distribution: Option<(count: u64, sum: f64,
+//!                                                 buckets: [(f64, u64)],
+//!                                                 quantiles: [(f64, f64)])>)]
+//! ```
+//!
+//! where `counter` indexes `counters` and `kind` is 0 for a gauge, 1 for a
+//! counter, 2 for a histogram and 3 for a summary.
+//!
+//! Version 1.2 appends the stability of the counters that are not stable:
+//!
+//! ```text
+//! [(counter: u32, stability: u8)]
+//! ```
+//!
+//! where `stability` is 1 for experimental and 2 for deprecated. Counters of
+//! older frames are stable.
+//!
+//! Compatibility rules: a reader accepts every minor version of its major
+//! version. Newer minor versions may only append records, inside the payload
+//! length, which older readers skip. A new major version is rejected with
+//! [`WireError::UnsupportedVersion`].
+//!
+//! # Examples
+//!
+//! ```rust
+//! # #[cfg(feature = "cbor")]
+//! # {
+//! use contatori::counters::unsigned::Unsigned;
+//! use contatori::counters::Observable;
+//! use contatori::snapshot::wire::{self, Encoding};
+//! use contatori::snapshot::MetricsSnapshot;
+//!
+//! let requests = Unsigned::new().with_name("requests");
+//! requests.add(42);
+//!
+//! let snapshot = MetricsSnapshot::collect([&requests as &dyn Observable].into_iter());
+//! let frame = wire::encode(&snapshot, Encoding::Cbor).unwrap();
+//! assert_eq!(wire::decode(&frame).unwrap(), snapshot);
+//! # }
+//! ```
+
+use super::{CounterSnapshot, MetricsSnapshot};
+use crate::counters::{CounterValue, Distribution, MetricKind, Stability};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read};
+use thiserror::Error;
+
+/// Magic bytes opening every frame.
+pub const MAGIC: [u8; 4] = *b"CNTR";
+
+/// Major version written by this crate; frames with another major version are
+/// rejected.
+pub const MAJOR_VERSION: u8 = 1;
+
+/// Minor version written by this crate.
+pub const MINOR_VERSION: u8 = 2;
+
+/// Size of the frame header in bytes.
+pub const HEADER_LEN: usize = 11;
+
+/// Error encoding or decoding a frame.
+#[derive(Debug, Error)]
+pub enum WireError {
+    /// Error reading or writing the frame.
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    /// The frame does not start with [`MAGIC`].
+    #[error("not a snapshot frame")]
+    BadMagic,
+
+    /// The frame was written with an incompatible major version.
+    #[error("unsupported wire version {major}.{minor}")]
+    UnsupportedVersion {
+        /// Major version of the frame.
+        major: u8,
+        /// Minor version of the frame.
+        minor: u8,
+    },
+
+    /// The frame uses an unknown encoding, or one whose feature is disabled.
+    #[error("unsupported encoding id {0}")]
+    UnsupportedEncoding(u8),
+
+    /// The payload exceeds the 4 GiB frame limit.
+    #[error("payload too large: {0} bytes")]
+    TooLarge(usize),
+
+    /// Error from the encoder.
+    #[error("encode error: {0}")]
+    Encode(String),
+
+    /// Error from the decoder.
+    #[error("decode error: {0}")]
+    Decode(String),
+
+    /// A counter refers past the end of the string table.
+    #[error("string index {0} out of range")]
+    BadStringIndex(u32),
+}
+
+/// Result type for wire operations.
+pub type Result<T> = std::result::Result<T, WireError>;
+
+/// Payload encoding of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// CBOR (RFC 8949), via `ciborium`.
+    #[cfg(feature = "cbor")]
+    Cbor,
+    /// MessagePack, via `rmp-serde`.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+    /// bincode 1 with variable-length integers.
+    #[cfg(feature = "bincode")]
+    Bincode,
+}
+
+impl Encoding {
+    /// Returns the identifier stored in the frame header.
+    pub fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => 1,
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => 2,
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => 3,
+        }
+    }
+
+    /// Returns the encoding with identifier `id`, if it is enabled.
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            #[cfg(feature = "cbor")]
+            1 => Some(Encoding::Cbor),
+            #[cfg(feature = "msgpack")]
+            2 => Some(Encoding::MessagePack),
+            #[cfg(feature = "bincode")]
+            3 => Some(Encoding::Bincode),
+            _ => None,
+        }
+    }
+
+    /// Appends the encoding of `record` to `out`.
+    fn serialize(self, record: &impl Serialize, out: &mut Vec<u8>) -> Result<()> {
+        let encode_error = |err: &dyn std::fmt::Display| WireError::Encode(err.to_string());
+        match self {
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ciborium::into_writer(record, out).map_err(|e| encode_error(&e)),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => {
+                rmp_serde::encode::write(out, record).map_err(|e| encode_error(&e))
+            }
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => {
+                use bincode::Options;
+                bincode::DefaultOptions::new()
+                    .serialize_into(out, record)
+                    .map_err(|e| encode_error(&e))
+            }
+        }
+    }
+
+    /// Decodes the record at the start of `bytes` and advances past it.
+    fn deserialize<T: DeserializeOwned>(self, bytes: &mut &[u8]) -> Result<T> {
+        let decode_error = |err: &dyn std::fmt::Display| WireError::Decode(err.to_string());
+        match self {
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor => ciborium::from_reader(bytes).map_err(|e| decode_error(&e)),
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack => rmp_serde::from_read(bytes).map_err(|e| decode_error(&e)),
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode => {
+                use bincode::Options;
+                bincode::DefaultOptions::new()
+                    .deserialize_from(bytes)
+                    .map_err(|e| decode_error(&e))
+            }
+        }
+    }
+}
+
+/// Version 1.0 record: timestamp, string table and counters.
+///
+/// Records are tuples so that self-describing encodings do not repeat field
+/// names for every counter.
+#[derive(Debug, Serialize, Deserialize)]
+struct Payload(Option<u64>, Vec<String>, Vec<Entry>);
+
+/// Counter of a version 1.0 record: name, labels, value and unit, with
+/// strings as indices into the table.
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry(u32, Vec<(u32, u32)>, Value, Option<u32>);
+
+/// Tagged counterpart of [`CounterValue`], whose serde form is untagged.
+#[derive(Debug, Serialize, Deserialize)]
+enum Value {
+    #[serde(rename = "u")]
+    Unsigned(u64),
+    #[serde(rename = "i")]
+    Signed(i64),
+    #[serde(rename = "f")]
+    Float(f64),
+}
+
+/// Version 1.1 record: counter index, kind and distribution of the counters
+/// that are not plain gauges.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Kinds(Vec<(u32, u8, Option<WireDistribution>)>);
+
+/// Version 1.2 record: counter index and stability of the counters that are
+/// not stable.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Stabilities(Vec<(u32, u8)>);
+
+/// Tuple form of [`Distribution`]: count, sum, buckets and quantiles.
+type WireDistribution = (u64, f64, Vec<(f64, u64)>, Vec<(f64, f64)>);
+
+/// Returns the wire identifier of `kind`.
+fn kind_id(kind: MetricKind) -> u8 {
+    match kind {
+        MetricKind::Gauge => 0,
+        MetricKind::Counter => 1,
+        MetricKind::Histogram => 2,
+        MetricKind::Summary => 3,
+    }
+}
+
+/// Returns the kind with wire identifier `id`; unknown kinds read as gauges.
+fn kind_from_id(id: u8) -> MetricKind {
+    match id {
+        1 => MetricKind::Counter,
+        2 => MetricKind::Histogram,
+        3 => MetricKind::Summary,
+        _ => MetricKind::Gauge,
+    }
+}
+
+/// Returns the wire identifier of `stability`.
+fn stability_id(stability: Stability) -> u8 {
+    match stability {
+        Stability::Stable => 0,
+        Stability::Experimental => 1,
+        Stability::Deprecated => 2,
+    }
+}
+
+/// Returns the stability with wire identifier `id`; unknown identifiers read
+/// as stable.
+fn stability_from_id(id: u8) -> Stability {
+    match id {
+        1 => Stability::Experimental,
+        2 => Stability::Deprecated,
+        _ => Stability::Stable,
+    }
+}
+
+/// Builds the string table, storing each distinct string once.
+#[derive(Default)]
+struct Interner<'a> {
+    indices: HashMap<&'a str, u32>,
+    strings: Vec<String>,
+}
+
+impl<'a> Interner<'a> {
+    fn intern(&mut self, s: &'a str) -> u32 {
+        let strings = &mut self.strings;
+        *self.indices.entry(s).or_insert_with(|| {
+            strings.push(s.to_string());
+            (strings.len() - 1) as u32
+        })
+    }
+}
+
+impl Payload {
+    fn from_snapshot(snapshot: &MetricsSnapshot) -> Self {
+        let mut interner = Interner::default();
+        let counters = snapshot
+            .counters
+            .iter()
+            .map(|counter| {
+                Entry(
+                    interner.intern(&counter.name),
+                    counter
+                        .labels
+                        .iter()
+                        .map(|(k, v)| (interner.intern(k), interner.intern(v)))
+                        .collect(),
+                    match counter.value {
+                        CounterValue::Unsigned(v) => Value::Unsigned(v),
+                        CounterValue::Signed(v) => Value::Signed(v),
+                        CounterValue::Float(v) => Value::Float(v),
+                    },
+                    counter.unit.as_deref().map(|unit| interner.intern(unit)),
+                )
+            })
+            .collect();
+        Self(snapshot.timestamp_ms, interner.strings, counters)
+    }
+
+    fn into_snapshot(self, kinds: Kinds, stabilities: Stabilities) -> Result<MetricsSnapshot> {
+        let Payload(timestamp_ms, strings, entries) = self;
+        let lookup = |index: u32| {
+            strings
+                .get(index as usize)
+                .cloned()
+                .ok_or(WireError::BadStringIndex(index))
+        };
+        let mut counters = entries
+            .into_iter()
+            .map(|Entry(name, labels, value, unit)| {
+                Ok(CounterSnapshot {
+                    name: lookup(name)?,
+                    labels: labels
+                        .into_iter()
+                        .map(|(k, v)| Ok((lookup(k)?, lookup(v)?)))
+                        .collect::<Result<_>>()?,
+                    value: match value {
+                        Value::Unsigned(v) => CounterValue::Unsigned(v),
+                        Value::Signed(v) => CounterValue::Signed(v),
+                        Value::Float(v) => CounterValue::Float(v),
+                    },
+                    unit: unit.map(lookup).transpose()?,
+                    kind: MetricKind::Gauge,
+                    distribution: None,
+                    stability: Stability::Stable,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (index, kind, distribution) in kinds.0 {
+            let counter = counters
+                .get_mut(index as usize)
+                .ok_or_else(|| WireError::Decode(format!("counter {} out of range", index)))?;
+            counter.kind = kind_from_id(kind);
+            counter.distribution =
+                distribution.map(|(count, sum, buckets, quantiles)| Distribution {
+                    count,
+                    sum,
+                    buckets,
+                    quantiles,
+                });
+        }
+        for (index, stability) in stabilities.0 {
+            let counter = counters
+                .get_mut(index as usize)
+                .ok_or_else(|| WireError::Decode(format!("counter {} out of range", index)))?;
+            counter.stability = stability_from_id(stability);
+        }
+        Ok(MetricsSnapshot {
+            timestamp_ms,
+            counters,
+        })
+    }
+}
+
+/// Encodes `snapshot` into a complete frame.
+///
+/// # Errors
+///
+/// Returns [`WireError::Encode`] if the encoder fails, or
+/// [`WireError::TooLarge`] if the payload does not fit in a frame.
+pub fn encode(snapshot: &MetricsSnapshot, encoding: Encoding) -> Result<Vec<u8>> {
+    let kinds = snapshot
+        .counters
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.kind != MetricKind::Gauge || c.distribution.is_some())
+        .map(|(index, c)| {
+            let distribution = c
+                .distribution
+                .as_ref()
+                .map(|d| (d.count, d.sum, d.buckets.clone(), d.quantiles.clone()));
+            (index as u32, kind_id(c.kind), distribution)
+        })
+        .collect();
+    let stabilities = snapshot
+        .counters
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.stability != Stability::Stable)
+        .map(|(index, c)| (index as u32, stability_id(c.stability)))
+        .collect();
+
+    let mut payload = Vec::new();
+    encoding.serialize(&Payload::from_snapshot(snapshot), &mut payload)?;
+    encoding.serialize(&Kinds(kinds), &mut payload)?;
+    encoding.serialize(&Stabilities(stabilities), &mut payload)?;
+    let len = u32::try_from(payload.len()).map_err(|_| WireError::TooLarge(payload.len()))?;
+
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&MAGIC);
+    frame.extend_from_slice(&[MAJOR_VERSION, MINOR_VERSION, encoding.id()]);
+    frame.extend_from_slice(&len.to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Encodes `snapshot` and writes the frame to `out`.
+///
+/// # Errors
+///
+/// Returns the encoding error, or the I/O error reported by `out`.
+pub fn write_to(
+    out: &mut impl io::Write,
+    snapshot: &MetricsSnapshot,
+    encoding: Encoding,
+) -> Result<()> {
+    out.write_all(&encode(snapshot, encoding)?)?;
+    Ok(())
+}
+
+/// Decodes the frame at the start of `bytes`.
+///
+/// # Errors
+///
+/// Returns an error if the frame is truncated, malformed, or uses an
+/// unsupported version or encoding.
+pub fn decode(mut bytes: &[u8]) -> Result<MetricsSnapshot> {
+    read_from(&mut bytes)
+}
+
+/// Reads exactly one frame from `input` and decodes it.
+///
+/// Frames are self-delimiting, so a stream such as a socket can carry any
+/// number of them back to back.
+///
+/// # Errors
+///
+/// Returns an error if the frame is truncated, malformed, or uses an
+/// unsupported version or encoding.
+pub fn read_from(input: &mut impl Read) -> Result<MetricsSnapshot> {
+    let mut header = [0u8; HEADER_LEN];
+    input.read_exact(&mut header)?;
+    if header[..4] != MAGIC {
+        return Err(WireError::BadMagic);
+    }
+    let (major, minor) = (header[4], header[5]);
+    if major != MAJOR_VERSION {
+        return Err(WireError::UnsupportedVersion { major, minor });
+    }
+    let encoding = Encoding::from_id(header[6]).ok_or(WireError::UnsupportedEncoding(header[6]))?;
+    let len = u32::from_le_bytes([header[7], header[8], header[9], header[10]]) as usize;
+
+    let mut payload = Vec::new();
+    input.take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() < len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let mut records = payload.as_slice();
+    let counters: Payload = encoding.deserialize(&mut records)?;
+    let kinds = if minor >= 1 {
+        encoding.deserialize(&mut records)?
+    } else {
+        Kinds::default()
+    };
+    let stabilities = if minor >= 2 {
+        encoding.deserialize(&mut records)?
+    } else {
+        Stabilities::default()
+    };
+    counters.into_snapshot(kinds, stabilities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encodings() -> Vec<Encoding> {
+        vec![
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor,
+            #[cfg(feature = "msgpack")]
+            Encoding::MessagePack,
+            #[cfg(feature = "bincode")]
+            Encoding::Bincode,
+        ]
+    }
+
+    fn sample() -> MetricsSnapshot {
+        let labeled = |method: &str, value| {
+            let mut counter = CounterSnapshot::with_labels(
+                "http_requests",
+                vec![("method".to_string(), method.to_string())],
+                CounterValue::Unsigned(value),
+            );
+            counter.kind = MetricKind::Counter;
+            counter
+        };
+        let mut latency = CounterSnapshot::new("latency", CounterValue::Float(0.25));
+        latency.unit = Some("seconds".to_string());
+        latency.kind = MetricKind::Histogram;
+        latency.distribution = Some(Distribution {
+            count: 4,
+            sum: 1.0,
+            buckets: vec![(0.1, 1), (0.5, 3)],
+            quantiles: Vec::new(),
+        });
+        MetricsSnapshot::with_timestamp(
+            vec![
+                labeled("GET", 1024),
+                labeled("POST", 7),
+                CounterSnapshot::new("in_flight", CounterValue::Signed(3)),
+                CounterSnapshot::new("balance", CounterValue::Signed(-12)),
+                latency,
+            ],
+            1_700_000_000_123,
+        )
+    }
+
+    #[test]
+    fn test_round_trip_preserves_snapshot() {
+        for encoding in encodings() {
+            let frame = encode(&sample(), encoding).unwrap();
+            assert_eq!(&frame[..4], b"CNTR");
+            assert_eq!(frame[6], encoding.id());
+            // Signed values stay signed, unlike the untagged JSON form
+            assert_eq!(decode(&frame).unwrap(), sample(), "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_strings_are_deduplicated() {
+        let payload = Payload::from_snapshot(&sample());
+        assert_eq!(
+            payload.1,
+            [
+                "http_requests",
+                "method",
+                "GET",
+                "POST",
+                "in_flight",
+                "balance",
+                "latency",
+                "seconds"
+            ]
+        );
+        assert_eq!(payload.2[0].0, payload.2[1].0);
+
+        for encoding in encodings() {
+            let counters =
+                vec![
+                    CounterSnapshot::new("a_rather_long_metric_name", CounterValue::Unsigned(1));
+                    100
+                ];
+            let frame = encode(&MetricsSnapshot::new(counters), encoding).unwrap();
+            assert!(frame.len() < 1000, "{:?}: {} bytes", encoding, frame.len());
+        }
+    }
+
+    #[test]
+    fn test_newer_minor_version_is_readable() {
+        for encoding in encodings() {
+            let mut frame = encode(&sample(), encoding).unwrap();
+            // A newer writer appends another record after the known ones
+            let extension = [0xAB; 16];
+            let len = (frame.len() - HEADER_LEN + extension.len()) as u32;
+            frame[5] = MINOR_VERSION + 1;
+            frame[7..11].copy_from_slice(&len.to_le_bytes());
+            frame.extend_from_slice(&extension);
+
+            assert_eq!(decode(&frame).unwrap(), sample(), "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_reads_version_1_0() {
+        for encoding in encodings() {
+            let mut payload = Vec::new();
+            encoding
+                .serialize(&Payload::from_snapshot(&sample()), &mut payload)
+                .unwrap();
+            let mut frame = MAGIC.to_vec();
+            frame.extend_from_slice(&[1, 0, encoding.id()]);
+            frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&payload);
+
+            let snapshot = decode(&frame).unwrap();
+            assert_eq!(snapshot.counters.len(), sample().counters.len());
+            assert!(snapshot
+                .counters
+                .iter()
+                .all(|c| c.kind == MetricKind::Gauge));
+            assert!(snapshot.counters.iter().all(|c| c.distribution.is_none()));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_preserves_stability() {
+        let mut snapshot = sample();
+        snapshot.counters[2].stability = Stability::Deprecated;
+        snapshot.counters[4].stability = Stability::Experimental;
+        for encoding in encodings() {
+            let frame = encode(&snapshot, encoding).unwrap();
+            let decoded = decode(&frame).unwrap();
+            assert_eq!(decoded.counters[2].stability, Stability::Deprecated);
+            assert_eq!(decoded, snapshot, "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn test_reads_version_1_1_as_stable() {
+        let mut snapshot = sample();
+        snapshot.counters[2].stability = Stability::Deprecated;
+        for encoding in encodings() {
+            let mut payload = Vec::new();
+            encoding
+                .serialize(&Payload::from_snapshot(&snapshot), &mut payload)
+                .unwrap();
+            encoding.serialize(&Kinds::default(), &mut payload).unwrap();
+            let mut frame = MAGIC.to_vec();
+            frame.extend_from_slice(&[1, 1, encoding.id()]);
+            frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&payload);
+
+            let decoded = decode(&frame).unwrap();
+            assert!(decoded
+                .counters
+                .iter()
+                .all(|c| c.stability == Stability::Stable));
+        }
+    }
+
+    #[test]
+    fn test_rejects_foreign_frames() {
+        for encoding in encodings() {
+            let frame = encode(&sample(), encoding).unwrap();
+
+            let mut newer = frame.clone();
+            newer[4] = 2;
+            assert!(matches!(
+                decode(&newer),
+                Err(WireError::UnsupportedVersion {
+                    major: 2,
+                    minor: MINOR_VERSION
+                })
+            ));
+
+            let mut unknown = frame.clone();
+            unknown[6] = 0xFF;
+            assert!(matches!(
+                decode(&unknown),
+                Err(WireError::UnsupportedEncoding(0xFF))
+            ));
+
+            assert!(matches!(
+                decode(b"{\"counters\":[]}"),
+                Err(WireError::BadMagic)
+            ));
+            assert!(matches!(
+                decode(&frame[..frame.len() - 1]),
+                Err(WireError::Io(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_stream_of_frames() {
+        for encoding in encodings() {
+            let mut stream = Vec::new();
+            for ts in 0..3 {
+                let snapshot = MetricsSnapshot::with_timestamp(
+                    vec![CounterSnapshot::new("ticks", CounterValue::Unsigned(ts))],
+                    ts,
+                );
+                write_to(&mut stream, &snapshot, encoding).unwrap();
+            }
+
+            let mut input = stream.as_slice();
+            for ts in 0..3 {
+                assert_eq!(read_from(&mut input).unwrap().timestamp_ms, Some(ts));
+            }
+            assert!(input.is_empty());
+        }
+    }
+}
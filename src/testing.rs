@@ -0,0 +1,58 @@
+//! Fixtures shared by the unit tests of the observers.
+
+// Each fixture is only used by the tests of some features.
+#![allow(dead_code)]
+
+use crate::counters::monotone::Monotone;
+use crate::counters::{CounterValue, MetricKind, Observable, ObservableEntry};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+crate::labeled_group!(
+    HttpRequests,
+    "http_requests",
+    "method",
+    total: Monotone,
+    get: "GET": Monotone,
+    post: "POST": Monotone,
+);
+
+/// A hand-written observable emitting entries with several labels each.
+#[derive(Debug)]
+pub struct HttpMetrics;
+
+impl Observable for HttpMetrics {
+    fn name(&self) -> &str {
+        "http_requests"
+    }
+
+    fn value(&self) -> CounterValue {
+        CounterValue::Unsigned(0)
+    }
+
+    fn expand(&self) -> Vec<ObservableEntry> {
+        [("GET", "/users", "200", 7), ("POST", "/orders", "500", 2)]
+            .into_iter()
+            .map(|(method, route, status, value)| ObservableEntry {
+                name: "http_requests".into(),
+                labels: vec![
+                    ("method", method.into()),
+                    ("route", route.into()),
+                    ("status", status.into()),
+                ],
+                value: CounterValue::Unsigned(value),
+                metric_kind: MetricKind::Counter,
+                distribution: None,
+            })
+            .collect()
+    }
+}
+
+/// Returns the time `secs` seconds after the Unix epoch.
+pub fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Returns the time `millis` milliseconds after the Unix epoch.
+pub fn at_millis(millis: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(millis)
+}